@@ -0,0 +1,24 @@
+/// Settings of [`crate::sync_dirs`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ApplyOptions {
+    /// Whether to delete entries of the target that are not in the source tree, default is **false**
+    ///
+    /// Directories standing where the source has files are only deleted if this is enabled.
+    pub delete: bool,
+    /// Whether to only report what would change without touching the target, default is **false**
+    pub dry_run: bool,
+}
+
+impl ApplyOptions {
+    /// Sets whether to delete entries of the target that are not in the source tree, default is **false**
+    pub fn delete(mut self, delete: bool) -> Self {
+        self.delete = delete;
+        self
+    }
+
+    /// Sets whether to only report what would change without touching the target, default is **false**
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
@@ -0,0 +1,21 @@
+use crate::components::merkle_path::MerklePath;
+
+/// Changes made to a target directory by [`crate::sync_dirs`], or that would be made on a dry run
+///
+/// Relative paths are shared by the source tree and the target, absolute paths point into the target.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ApplyReport {
+    /// Directories created in the target
+    pub created_dirs: Vec<MerklePath>,
+    /// Files copied from the source, new or replacing changed ones
+    pub copied: Vec<MerklePath>,
+    /// Entries removed from the target, either extra or replaced by an entry of another type
+    pub deleted: Vec<MerklePath>,
+}
+
+impl ApplyReport {
+    /// Returns whether the target already matched the source tree
+    pub fn is_empty(&self) -> bool {
+        self.created_dirs.is_empty() && self.copied.is_empty() && self.deleted.is_empty()
+    }
+}
@@ -0,0 +1,13 @@
+use crate::components::digest::Digest;
+
+/// A content-defined chunk of a file, a leaf below the file's node
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct Chunk {
+    /// Position of the chunk in the file in bytes
+    pub offset: u64,
+    /// Length of the chunk in bytes
+    pub len: u64,
+    /// Hash of the chunk's bytes, computed with the primary algorithm of the tree
+    pub hash: Digest,
+}
@@ -0,0 +1,137 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::CidError;
+use crate::utils::{base32, protobuf};
+
+/// Multicodec code of the sha2-256 multihash
+const SHA2_256: u64 = 0x12;
+
+/// Codec of the block a [`Cid`] points to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub enum CidCodec {
+    /// Raw bytes, used for the leaves of files
+    Raw,
+    /// A dag-pb node, used for directories and files of multiple chunks
+    DagPb,
+}
+
+impl CidCodec {
+    /// Returns the multicodec code of the codec
+    pub fn code(&self) -> u64 {
+        match self {
+            CidCodec::Raw => 0x55,
+            CidCodec::DagPb => 0x70,
+        }
+    }
+
+    /// Finds the codec with the given multicodec code
+    pub fn from_code(code: u64) -> Option<Self> {
+        match code {
+            0x55 => Some(CidCodec::Raw),
+            0x70 => Some(CidCodec::DagPb),
+            _ => None,
+        }
+    }
+}
+
+/// A version 1 content identifier of a sha2-256 hashed IPFS block
+///
+/// - Displays as the base32 multibase string printed by kubo, e.g. `bafybei...`
+/// - Parses from the same form
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct Cid {
+    codec: CidCodec,
+    hash: [u8; 32],
+}
+
+impl Cid {
+    /// Creates a content identifier from the codec and sha2-256 hash of a block
+    pub fn new(codec: CidCodec, hash: [u8; 32]) -> Self {
+        Self { codec, hash }
+    }
+
+    /// Creates the content identifier of a block
+    pub(crate) fn of_block(codec: CidCodec, block: &[u8]) -> Self {
+        use sha2::Digest as _;
+        Self::new(codec, sha2::Sha256::digest(block).into())
+    }
+
+    /// Returns the codec of the block
+    pub fn codec(&self) -> CidCodec {
+        self.codec
+    }
+
+    /// Returns the sha2-256 hash of the block
+    pub fn hash(&self) -> &[u8; 32] {
+        &self.hash
+    }
+
+    /// Returns the binary form of the content identifier: version, codec and multihash
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(36);
+        protobuf::write_varint(&mut bytes, 1);
+        protobuf::write_varint(&mut bytes, self.codec.code());
+        protobuf::write_varint(&mut bytes, SHA2_256);
+        protobuf::write_varint(&mut bytes, self.hash.len() as u64);
+        bytes.extend_from_slice(&self.hash);
+        bytes
+    }
+
+    /// Parses the binary form of a content identifier
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, CidError> {
+        let mut read = || protobuf::read_varint(&mut bytes).ok_or(CidError::InvalidEncoding);
+
+        let version = read()?;
+        if version != 1 {
+            return Err(CidError::UnsupportedVersion(version));
+        }
+
+        let code = read()?;
+        let codec = match CidCodec::from_code(code) {
+            Some(codec) => codec,
+            None => return Err(CidError::UnsupportedCodec(code)),
+        };
+
+        let (hash_code, hash_len) = (read()?, read()?);
+        if hash_code != SHA2_256 || hash_len != 32 {
+            return Err(CidError::UnsupportedMultihash(hash_code, hash_len));
+        }
+
+        let hash = match <[u8; 32]>::try_from(bytes) {
+            Ok(hash) => hash,
+            Err(_) => return Err(CidError::InvalidEncoding),
+        };
+
+        Ok(Self::new(codec, hash))
+    }
+}
+
+impl Display for Cid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "b{}", base32::encode(&self.to_bytes()))
+    }
+}
+
+impl Debug for Cid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cid({})", self)
+    }
+}
+
+impl FromStr for Cid {
+    type Err = CidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = match s.strip_prefix('b') {
+            Some(encoded) => encoded,
+            None => return Err(CidError::UnsupportedMultibase(s.to_string())),
+        };
+        match base32::decode(encoded) {
+            Some(bytes) => Self::from_bytes(&bytes),
+            None => Err(CidError::InvalidEncoding),
+        }
+    }
+}
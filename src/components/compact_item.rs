@@ -0,0 +1,166 @@
+use std::fmt::{Debug, Formatter};
+
+#[cfg(feature = "fastcdc")]
+use crate::components::chunk::Chunk;
+use crate::components::digest::Digest;
+use crate::components::merkle_item::MerkleItem;
+use crate::components::merkle_path::MerklePath;
+use crate::iters::compact_tree_iter::CompactChildren;
+use crate::tree::compact_tree::{CompactTree, PathBuf, NONE};
+use crate::utils::algorithm::AlgorithmKind;
+
+/// A borrowed view of a single file or directory in a [`CompactTree`]
+#[derive(Clone, Copy)]
+pub struct CompactItem<'a> {
+    tree: &'a CompactTree,
+    index: u32,
+}
+
+impl<'a> CompactItem<'a> {
+    pub(crate) fn new(tree: &'a CompactTree, index: u32) -> Self {
+        Self { tree, index }
+    }
+
+    /// Returns the pre-order index of the item in its tree
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the hash of the item
+    pub fn hash(&self) -> &'a Digest {
+        &self.tree.nodes[self.index as usize].hash
+    }
+
+    /// Returns whether the item was a directory when it was indexed
+    pub fn is_dir(&self) -> bool {
+        self.tree.nodes[self.index as usize].is_dir
+    }
+
+    /// Returns the hash computed with the given kind of algorithm, if the tree was built with it
+    pub fn hash_for(&self, algorithm: AlgorithmKind) -> Option<&'a Digest> {
+        if self.hash().algorithm() == algorithm {
+            return Some(self.hash());
+        }
+        let position = self
+            .tree
+            .additional_algorithms
+            .iter()
+            .position(|additional| additional.kind() == algorithm)?;
+        let start = self.index as usize * self.tree.additional_algorithms.len();
+        self.tree.additional_hashes.get(start + position)
+    }
+
+    /// Returns the content-defined chunks of the file, empty if it was not split into chunks
+    #[cfg(feature = "fastcdc")]
+    pub fn chunks(&self) -> &'a [Chunk] {
+        let node = &self.tree.nodes[self.index as usize];
+        let start = node.chunks_start as usize;
+        &self.tree.chunks[start..start + node.chunks_len as usize]
+    }
+
+    /// Returns the name of the item, none for the root
+    #[cfg(feature = "camino")]
+    pub fn name(&self) -> Option<&'a str> {
+        self.name_index().map(|name| &*self.tree.names[name as usize])
+    }
+
+    /// Returns the name of the item, none for the root
+    #[cfg(not(feature = "camino"))]
+    pub fn name(&self) -> Option<&'a std::ffi::OsStr> {
+        self.name_index().map(|name| &*self.tree.names[name as usize])
+    }
+
+    fn name_index(&self) -> Option<u32> {
+        match self.tree.nodes[self.index as usize].name {
+            NONE => None,
+            name => Some(name),
+        }
+    }
+
+    /// Returns the parent of the item, none for the root
+    pub fn parent(&self) -> Option<CompactItem<'a>> {
+        match self.tree.nodes[self.index as usize].parent {
+            NONE => None,
+            parent => Some(self.tree.item(parent)),
+        }
+    }
+
+    /// Returns the number of files and directories below the item
+    pub fn descendants(&self) -> usize {
+        self.tree.nodes[self.index as usize].descendants as usize
+    }
+
+    /// Returns an iterator over the direct children of the item
+    pub fn children(&self) -> CompactChildren<'a> {
+        CompactChildren::new(self.tree, self.index)
+    }
+
+    /// Reconstructs the path of the item relative to the root
+    pub fn relative_path(&self) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = Some(*self);
+        while let Some(item) = current {
+            if let Some(name) = item.name() {
+                names.push(name);
+            }
+            current = item.parent();
+        }
+        names.iter().rev().collect()
+    }
+
+    /// Reconstructs the absolute path of the item
+    pub fn absolute_path(&self) -> PathBuf {
+        if self.index == 0 {
+            return self.tree.root_path.clone();
+        }
+        self.tree.root_path.join(self.relative_path())
+    }
+
+    /// Reconstructs both paths of the item
+    pub fn path(&self) -> MerklePath {
+        let relative = self.relative_path();
+        let absolute = if self.index == 0 {
+            self.tree.root_path.clone()
+        } else {
+            self.tree.root_path.join(&relative)
+        };
+        MerklePath::new(relative, absolute)
+    }
+
+    /// Creates an owned merkle item from the view, with every hash and chunk listing the tree holds for it
+    pub fn to_item(&self) -> MerkleItem {
+        #[cfg(feature = "retain")]
+        let item = MerkleItem::new(
+            self.path(),
+            *self.hash(),
+            self.children().map(|child| child.path()).collect(),
+        );
+        #[cfg(not(feature = "retain"))]
+        let item = MerkleItem::new(self.path(), *self.hash());
+
+        let additional_hashes = self
+            .tree
+            .additional_algorithms
+            .iter()
+            .filter_map(|algorithm| Some((algorithm.kind(), *self.hash_for(algorithm.kind())?)))
+            .collect();
+
+        MerkleItem {
+            additional_hashes,
+            is_dir: self.is_dir(),
+            #[cfg(feature = "fastcdc")]
+            chunks: self.chunks().to_vec(),
+            ..item
+        }
+    }
+}
+
+impl Debug for CompactItem<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompactItem")
+            .field("index", &self.index)
+            .field("path", &self.relative_path())
+            .field("hash", &self.hash())
+            .finish()
+    }
+}
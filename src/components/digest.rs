@@ -0,0 +1,224 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::error::DigestError;
+use crate::utils::algorithm::AlgorithmKind;
+
+/// Maximum length of a digest in bytes
+pub const MAX_DIGEST_LEN: usize = 64;
+
+/// A fixed-size hash value, stored inline and tagged with the algorithm that produced it
+///
+/// - Equality is checked in constant time
+/// - Displays as the algorithm name and lowercase hex, e.g. `blake3:af13...`, see [`Digest::to_hex`] for bare hex
+/// - Parses back from the same form
+#[derive(Clone, Copy)]
+pub struct Digest {
+    algorithm: AlgorithmKind,
+    len: u8,
+    bytes: [u8; MAX_DIGEST_LEN],
+}
+
+impl Digest {
+    /// Creates a digest from raw bytes produced by the given algorithm
+    pub fn new(algorithm: AlgorithmKind, bytes: &[u8]) -> Result<Self, DigestError> {
+        if bytes.is_empty() || bytes.len() > MAX_DIGEST_LEN {
+            return Err(DigestError::InvalidLength(bytes.len()));
+        }
+        Ok(Self::from_output(algorithm, bytes))
+    }
+
+    /// Creates a digest from the output of a hasher, which always fits
+    pub(crate) fn from_output(algorithm: AlgorithmKind, bytes: &[u8]) -> Self {
+        let mut inline = [0u8; MAX_DIGEST_LEN];
+        inline[..bytes.len()].copy_from_slice(bytes);
+        Self {
+            algorithm,
+            len: bytes.len() as u8,
+            bytes: inline,
+        }
+    }
+
+    /// Parses a digest from a hex string without an algorithm prefix
+    pub fn from_hex(algorithm: AlgorithmKind, hex: &str) -> Result<Self, DigestError> {
+        if !hex.len().is_multiple_of(2) {
+            return Err(DigestError::InvalidHex(hex.to_string()));
+        }
+
+        let bytes = hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                let high = (pair[0] as char).to_digit(16)?;
+                let low = (pair[1] as char).to_digit(16)?;
+                Some((high << 4 | low) as u8)
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| DigestError::InvalidHex(hex.to_string()))?;
+
+        Self::new(algorithm, &bytes)
+    }
+
+    /// Returns the kind of algorithm that produced the digest
+    pub fn algorithm(&self) -> AlgorithmKind {
+        self.algorithm
+    }
+
+    /// Returns the bytes of the digest
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    /// Returns the digest as lowercase hex, without the algorithm prefix
+    pub fn to_hex(&self) -> String {
+        let table = b"0123456789abcdef";
+        let mut hex = String::with_capacity(self.len() * 2);
+        for &byte in self.as_bytes() {
+            hex.push(table[(byte >> 4) as usize] as char);
+            hex.push(table[(byte & 0xf) as usize] as char);
+        }
+        hex
+    }
+
+    /// Returns the length of the digest in bytes
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns true if the digest has no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies the bytes of the digest into a vector
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl PartialEq for Digest {
+    fn eq(&self, other: &Self) -> bool {
+        // Unused bytes are always zero, so comparing the whole buffer
+        // takes the same time regardless of where the digests differ
+        let difference = self
+            .bytes
+            .iter()
+            .zip(other.bytes.iter())
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b));
+        std::hint::black_box(difference) == 0 && self.len == other.len && self.algorithm == other.algorithm
+    }
+}
+
+impl Eq for Digest {}
+
+impl Hash for Digest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.algorithm.hash(state);
+        self.as_bytes().hash(state);
+    }
+}
+
+impl PartialOrd for Digest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Digest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.algorithm
+            .cmp(&other.algorithm)
+            .then_with(|| self.as_bytes().cmp(other.as_bytes()))
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.to_hex())
+    }
+}
+
+impl Debug for Digest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Digest({})", self)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = DigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, hex) = match s.split_once(':') {
+            Some(parts) => parts,
+            None => return Err(DigestError::MissingAlgorithm(s.to_string())),
+        };
+        let algorithm = match AlgorithmKind::from_name(name) {
+            Some(algorithm) => algorithm,
+            None => return Err(DigestError::UnknownAlgorithm(name.to_string())),
+        };
+        Self::from_hex(algorithm, hex)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for Digest {
+    fn encode<E: bincode::enc::Encoder>(&self, encoder: &mut E) -> Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.algorithm, encoder)?;
+        bincode::Encode::encode(self.as_bytes(), encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for Digest {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        let algorithm: AlgorithmKind = bincode::Decode::decode(decoder)?;
+        let bytes: Vec<u8> = bincode::Decode::decode(decoder)?;
+        Digest::new(algorithm, &bytes).map_err(|error| bincode::error::DecodeError::OtherString(error.to_string()))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(Digest);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::algorithm::Algorithm;
+
+    #[test]
+    fn display_round_trips() {
+        let digest = Algorithm::Blake3.compute_hash(b"hello");
+        let displayed = digest.to_string();
+        assert_eq!(displayed, format!("blake3:{}", digest.to_hex()));
+        assert_eq!(displayed.parse::<Digest>().unwrap(), digest);
+        assert_eq!(format!("{:?}", digest), format!("Digest({})", displayed));
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let digest = Algorithm::Blake3.compute_hash(b"hello");
+        assert_eq!(Digest::from_hex(AlgorithmKind::Blake3, &digest.to_hex()).unwrap(), digest);
+        assert_eq!(
+            Digest::new(AlgorithmKind::Blake3, &[0x00, 0x0f, 0xa0, 0xff]).unwrap().to_hex(),
+            "000fa0ff"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_strings() {
+        let digest = Algorithm::Blake3.compute_hash(b"hello");
+        assert!(matches!(digest.to_hex().parse::<Digest>(), Err(DigestError::MissingAlgorithm(_))));
+        assert!(matches!("nope:00".parse::<Digest>(), Err(DigestError::UnknownAlgorithm(_))));
+        assert!(matches!("blake3:0".parse::<Digest>(), Err(DigestError::InvalidHex(_))));
+        assert!(matches!("blake3:zz".parse::<Digest>(), Err(DigestError::InvalidHex(_))));
+        assert!(matches!("blake3:".parse::<Digest>(), Err(DigestError::InvalidLength(0))));
+    }
+}
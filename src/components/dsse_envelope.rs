@@ -0,0 +1,130 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::components::statement::Statement;
+use crate::error::SigningError;
+use crate::utils::{base64, json};
+
+/// Payload type of in-toto statements wrapped in DSSE envelopes
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// A signature of a DSSE envelope
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsseSignature {
+    /// Hint of which key made the signature, if any
+    pub keyid: Option<String>,
+    pub sig: Signature,
+}
+
+/// A Dead Simple Signing Envelope around a payload, signed over its pre-authentication encoding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsseEnvelope {
+    pub payload_type: String,
+    pub payload: Vec<u8>,
+    pub signatures: Vec<DsseSignature>,
+}
+
+impl DsseEnvelope {
+    /// Wraps a statement and signs it with an Ed25519 key
+    pub fn sign(statement: &Statement, key: &SigningKey, keyid: Option<String>) -> Self {
+        let mut envelope = Self {
+            payload_type: IN_TOTO_PAYLOAD_TYPE.to_string(),
+            payload: statement.to_json().into_bytes(),
+            signatures: Vec::new(),
+        };
+        let sig = key.sign(&envelope.pae());
+        envelope.signatures.push(DsseSignature { keyid, sig });
+        envelope
+    }
+
+    /// Returns the pre-authentication encoding of the payload, which is what gets signed
+    pub fn pae(&self) -> Vec<u8> {
+        let mut pae = format!(
+            "DSSEv1 {} {} {} ",
+            self.payload_type.len(),
+            self.payload_type,
+            self.payload.len()
+        )
+        .into_bytes();
+        pae.extend_from_slice(&self.payload);
+        pae
+    }
+
+    /// Checks that one of the signatures was made by the given key
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), SigningError> {
+        let pae = self.pae();
+        match self.signatures.iter().any(|signature| key.verify(&pae, &signature.sig).is_ok()) {
+            true => Ok(()),
+            false => Err(SigningError::InvalidSignature),
+        }
+    }
+
+    /// Encodes the envelope as compact JSON
+    pub fn to_json(&self) -> String {
+        let signatures = self.signatures.iter().map(|signature| {
+            let mut entries = Vec::new();
+            if let Some(keyid) = &signature.keyid {
+                entries.push(("keyid", json::string(keyid)));
+            }
+            entries.push(("sig", json::string(&base64::encode(&signature.sig.to_bytes()))));
+            json::object(entries)
+        });
+        json::object([
+            ("payloadType", json::string(&self.payload_type)),
+            ("payload", json::string(&base64::encode(&self.payload))),
+            ("signatures", json::array(signatures)),
+        ])
+    }
+}
+
+impl Statement {
+    /// Wraps the statement in a DSSE envelope signed with an Ed25519 key
+    pub fn to_dsse(&self, key: &SigningKey, keyid: Option<String>) -> DsseEnvelope {
+        DsseEnvelope::sign(self, key, keyid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+    use crate::components::statement::Statement;
+    use crate::error::SigningError;
+
+    #[test]
+    fn pre_authentication_encoding_matches_the_specification() {
+        let envelope = DsseEnvelope {
+            payload_type: "http://example.com/HelloWorld".to_string(),
+            payload: b"hello world".to_vec(),
+            signatures: Vec::new(),
+        };
+        assert_eq!(envelope.pae(), b"DSSEv1 29 http://example.com/HelloWorld 11 hello world");
+    }
+
+    #[test]
+    fn signs_statements_and_verifies_them() {
+        let statement = Statement {
+            subjects: Vec::new(),
+            predicate_type: "https://example.com/predicate".to_string(),
+            predicate: None,
+        };
+        let key = SigningKey::from_bytes(&[1; 32]);
+        let envelope = statement.to_dsse(&key, Some("release".to_string()));
+
+        assert_eq!(envelope.payload_type, IN_TOTO_PAYLOAD_TYPE);
+        assert_eq!(envelope.payload, statement.to_json().into_bytes());
+        envelope.verify(&key.verifying_key()).unwrap();
+
+        let other = SigningKey::from_bytes(&[2; 32]).verifying_key();
+        assert!(matches!(envelope.verify(&other), Err(SigningError::InvalidSignature)));
+
+        let mut tampered = envelope.clone();
+        tampered.payload.push(b' ');
+        assert!(matches!(tampered.verify(&key.verifying_key()), Err(SigningError::InvalidSignature)));
+
+        let json = envelope.to_json();
+        let payload = base64::encode(&envelope.payload);
+        let prefix = format!(r#"{{"payloadType":"application/vnd.in-toto+json","payload":"{}","signatures":[{{"keyid":"release","sig":""#, payload);
+        assert!(json.starts_with(&prefix));
+    }
+}
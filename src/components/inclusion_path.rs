@@ -0,0 +1,39 @@
+use crate::components::digest::Digest;
+use crate::utils::algorithm::Algorithm;
+
+/// One step up an inclusion path, from a hash towards the root of the tree
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub enum InclusionStep {
+    /// Prefixes the hash with the name of its file or directory, for trees built with hashed names
+    Name(String),
+    /// Pairs the hash with a sibling on its left
+    Left(Digest),
+    /// Pairs the hash with a sibling on its right
+    Right(Digest),
+}
+
+/// The BLAKE3 hashes linking a file to the root of its tree
+///
+/// Starts from the plain BLAKE3 hash of the file's contents and lists, from the file up to the root,
+/// the names and the sibling hashes of the pairwise merkle hash of every directory on the way.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct InclusionPath {
+    /// BLAKE3 hash of the contents of the file
+    pub file_hash: Digest,
+    /// Steps from the file up to the root
+    pub steps: Vec<InclusionStep>,
+}
+
+impl InclusionPath {
+    /// Computes the root hash the path leads to
+    pub fn root_hash(&self) -> Digest {
+        let algorithm = Algorithm::Blake3;
+        self.steps.iter().fold(self.file_hash, |hash, step| match step {
+            InclusionStep::Name(name) => algorithm.compute_hash_from_slices(name.as_bytes(), hash.as_ref()),
+            InclusionStep::Left(sibling) => algorithm.compute_hash_from_slices(sibling.as_ref(), hash.as_ref()),
+            InclusionStep::Right(sibling) => algorithm.compute_hash_from_slices(hash.as_ref(), sibling.as_ref()),
+        })
+    }
+}
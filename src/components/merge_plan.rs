@@ -0,0 +1,72 @@
+use crate::components::merkle_item::MerkleItem;
+
+/// Why a path cannot be merged automatically
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConflictKind {
+    /// Both sides changed a file of the base differently
+    BothModified,
+    /// Both sides added a file that is not in the base, with different contents
+    BothAdded,
+    /// One side deleted what the other side changed
+    ModifyDelete,
+    /// One side has a file where the other side has a directory
+    TypeClash,
+}
+
+/// How a path is resolved by a merge
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MergeAction {
+    /// Neither side changed the path, the base is kept
+    Unchanged,
+    /// Only ours changed the path, or both changed it the same way
+    TakeOurs,
+    /// Only theirs changed the path
+    TakeTheirs,
+    /// Both sides changed the path in ways that cannot be combined
+    Conflict(ConflictKind),
+}
+
+/// A path of a merge with its items in each tree, none where the tree does not have it
+///
+/// Taking a side whose item is none means deleting the path.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MergeEntry {
+    #[cfg(feature = "camino")]
+    pub relative: camino::Utf8PathBuf,
+    #[cfg(not(feature = "camino"))]
+    pub relative: std::path::PathBuf,
+    pub base: Option<MerkleItem>,
+    pub ours: Option<MerkleItem>,
+    pub theirs: Option<MerkleItem>,
+    pub action: MergeAction,
+}
+
+/// Resolution of every path of a three-way merge, in pre-order
+///
+/// Subtrees resolved as a whole are listed once, without their descendants.
+/// Directories changed on both sides are not listed themselves, only their children are.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MergePlan {
+    pub entries: Vec<MergeEntry>,
+}
+
+impl MergePlan {
+    /// Returns the entries that cannot be merged automatically
+    pub fn conflicts(&self) -> impl Iterator<Item = &MergeEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.action, MergeAction::Conflict(_)))
+    }
+
+    /// Returns the entries that take a side, leaving out unchanged ones
+    pub fn changes(&self) -> impl Iterator<Item = &MergeEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.action, MergeAction::TakeOurs | MergeAction::TakeTheirs))
+    }
+
+    /// Returns whether the merge can be applied without resolving conflicts
+    pub fn is_clean(&self) -> bool {
+        self.conflicts().next().is_none()
+    }
+}
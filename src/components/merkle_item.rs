@@ -1,33 +1,113 @@
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
+#[cfg(feature = "fastcdc")]
+use crate::components::chunk::Chunk;
+use crate::components::digest::Digest;
 use crate::components::merkle_path::MerklePath;
+#[cfg(feature = "bao")]
+use crate::components::outboard::Outboard;
+#[cfg(feature = "bao")]
+use crate::components::slice_proof::SliceProof;
+#[cfg(feature = "bittorrent")]
+use crate::components::torrent_file::TorrentFile;
+#[cfg(feature = "unixfs")]
+use crate::components::unixfs_entry::UnixFsEntry;
+#[cfg(feature = "bao")]
+use crate::error::BaoError;
+use crate::utils::algorithm::AlgorithmKind;
 
 /// Holds the path, hash and children paths of a file or directory
 #[derive(Eq, PartialEq, Clone, Debug, Hash)]
 #[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
 pub struct MerkleItem {
     pub path: MerklePath,
-    pub hash: Vec<u8>,
+    /// Hash computed with the primary algorithm of the tree
+    pub hash: Digest,
+    /// Hashes computed with the additional algorithms of the tree, if any
+    pub additional_hashes: BTreeMap<AlgorithmKind, Digest>,
+    /// Whether the item was a directory when it was indexed
+    pub is_dir: bool,
     #[cfg(feature = "retain")]
     pub children_paths: std::collections::BTreeSet<MerklePath>,
+    /// BLAKE3 outboard of the file's contents, if the tree was built with outboards
+    #[cfg(feature = "bao")]
+    pub outboard: Option<Outboard>,
+    /// Content-defined chunks of the file, if the tree was built with chunking
+    #[cfg(feature = "fastcdc")]
+    pub chunks: Vec<Chunk>,
+    /// IPFS UnixFS identity of the file or directory, if the tree was built with UnixFS
+    #[cfg(feature = "unixfs")]
+    pub unixfs: Option<UnixFsEntry>,
+    /// BitTorrent v2 pieces of the file, if the tree was built with BitTorrent pieces
+    #[cfg(feature = "bittorrent")]
+    pub torrent: Option<TorrentFile>,
 }
 
 impl MerkleItem {
     #[cfg(not(feature = "retain"))]
-    pub fn new(path: MerklePath, hash: Vec<u8>) -> Self {
+    pub fn new(path: MerklePath, hash: Digest) -> Self {
         Self {
             path,
             hash,
+            additional_hashes: BTreeMap::new(),
+            is_dir: false,
+            #[cfg(feature = "bao")]
+            outboard: None,
+            #[cfg(feature = "fastcdc")]
+            chunks: Vec::new(),
+            #[cfg(feature = "unixfs")]
+            unixfs: None,
+            #[cfg(feature = "bittorrent")]
+            torrent: None,
         }
     }
     #[cfg(feature = "retain")]
-    pub fn new(path: MerklePath, hash: Vec<u8>, children_paths: std::collections::BTreeSet<MerklePath>) -> Self {
+    pub fn new(path: MerklePath, hash: Digest, children_paths: std::collections::BTreeSet<MerklePath>) -> Self {
         Self {
             path,
             hash,
+            additional_hashes: BTreeMap::new(),
+            is_dir: false,
             children_paths,
+            #[cfg(feature = "bao")]
+            outboard: None,
+            #[cfg(feature = "fastcdc")]
+            chunks: Vec::new(),
+            #[cfg(feature = "unixfs")]
+            unixfs: None,
+            #[cfg(feature = "bittorrent")]
+            torrent: None,
         }
     }
+
+    /// Returns the hash computed with the given kind of algorithm, if the tree was built with it
+    pub fn hash_for(&self, algorithm: AlgorithmKind) -> Option<&Digest> {
+        if self.hash.algorithm() == algorithm {
+            Some(&self.hash)
+        } else {
+            self.additional_hashes.get(&algorithm)
+        }
+    }
+
+    /// Returns every hash of the item, starting with the primary one
+    pub fn hashes(&self) -> impl Iterator<Item = &Digest> {
+        std::iter::once(&self.hash).chain(self.additional_hashes.values())
+    }
+
+    /// Produces a proof for `len` bytes of the file starting at `start`, using its outboard
+    ///
+    /// The proof verifies against the outboard's hash, which is the item's hash
+    /// when the tree is built with plain blake3 and without hashing names.
+    #[cfg(feature = "bao")]
+    pub fn slice(&self, start: u64, len: u64) -> Result<SliceProof, BaoError> {
+        let outboard = match &self.outboard {
+            Some(outboard) => outboard,
+            None => return Err(BaoError::MissingOutboard),
+        };
+        let file = std::fs::File::open(&self.path.absolute)?;
+        outboard.slice(file, start, len)
+    }
 }
 
 impl PartialOrd<Self> for MerkleItem {
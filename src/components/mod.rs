@@ -1,2 +1,36 @@
+#[cfg(feature = "apply")]
+pub mod apply_options;
+#[cfg(feature = "apply")]
+pub mod apply_report;
+#[cfg(feature = "fastcdc")]
+pub mod chunk;
+#[cfg(feature = "unixfs")]
+pub mod cid;
+pub mod compact_item;
+pub mod digest;
+#[cfg(feature = "bao")]
+pub mod inclusion_path;
+#[cfg(all(feature = "attestation", feature = "signing"))]
+pub mod dsse_envelope;
+pub mod merge_plan;
 pub mod merkle_item;
 pub mod merkle_path;
+#[cfg(feature = "nar")]
+pub mod nar_hash;
+#[cfg(feature = "oci")]
+pub mod oci_layer;
+#[cfg(feature = "bao")]
+pub mod outboard;
+#[cfg(feature = "signing")]
+pub mod signed_root;
+#[cfg(feature = "bao")]
+pub mod slice_proof;
+#[cfg(feature = "attestation")]
+pub mod statement;
+#[cfg(feature = "sync")]
+pub mod sync_diff;
+#[cfg(feature = "bittorrent")]
+pub mod torrent_file;
+#[cfg(feature = "unixfs")]
+pub mod unixfs_entry;
+pub mod verification_report;
@@ -0,0 +1,37 @@
+use std::fmt::{Display, Formatter};
+
+use crate::utils::{base64, nar};
+
+/// The SHA-256 hash of the Nix archive (NAR) serialization of a file or directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct NarHash([u8; 32]);
+
+impl NarHash {
+    /// Creates a NAR hash from its raw SHA-256 bytes
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the raw SHA-256 bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Returns the SRI form printed by `nix hash path`, such as `sha256-<base64>`
+    pub fn to_sri(&self) -> String {
+        format!("sha256-{}", base64::encode(&self.0))
+    }
+
+    /// Returns the nix32 form printed by `nix-hash --type sha256 --base32`
+    pub fn to_nix32(&self) -> String {
+        nar::nix32(&self.0)
+    }
+}
+
+impl Display for NarHash {
+    /// Formats the hash in its SRI form
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_sri())
+    }
+}
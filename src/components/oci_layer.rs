@@ -0,0 +1,59 @@
+use crate::components::digest::Digest;
+
+/// Media type of an uncompressed OCI image layer
+pub const OCI_LAYER_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar";
+
+/// Media type of a gzip compressed OCI image layer
+#[cfg(feature = "gzip")]
+pub const OCI_LAYER_GZIP_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+/// How the tar stream of a layer is compressed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LayerCompression {
+    /// Plain tar, the digest equals the diffID
+    #[default]
+    None,
+    /// Gzip with a zeroed header timestamp, so the compressed blob is reproducible too
+    #[cfg(feature = "gzip")]
+    Gzip,
+}
+
+impl LayerCompression {
+    /// Returns the OCI media type of layers compressed this way
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            LayerCompression::None => OCI_LAYER_MEDIA_TYPE,
+            #[cfg(feature = "gzip")]
+            LayerCompression::Gzip => OCI_LAYER_GZIP_MEDIA_TYPE,
+        }
+    }
+}
+
+/// An OCI content descriptor, pointing to a blob by its digest and size
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OciDescriptor {
+    pub media_type: &'static str,
+    /// SHA-256 of the blob, displayed as `sha256:<hex>`
+    pub digest: Digest,
+    /// Size of the blob in bytes
+    pub size: u64,
+}
+
+impl OciDescriptor {
+    /// Returns the descriptor as the JSON object found in image manifests
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"mediaType":"{}","digest":"{}","size":{}}}"#,
+            self.media_type, self.digest, self.size
+        )
+    }
+}
+
+/// The identity of an OCI image layer built from a tree
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OciLayer {
+    /// SHA-256 of the uncompressed tar, listed in the `rootfs.diff_ids` of image configs
+    pub diff_id: Digest,
+    /// Descriptor of the layer blob as it was written
+    pub descriptor: OciDescriptor,
+}
@@ -0,0 +1,184 @@
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::components::digest::Digest;
+use crate::components::slice_proof::SliceProof;
+use crate::error::BaoError;
+use crate::utils::algorithm::AlgorithmKind;
+use crate::utils::bao::{self, SliceRange, HEADER_LEN, PARENT_LEN};
+
+/// The BLAKE3 tree of a file's contents in the Bao outboard format, without the contents themselves
+///
+/// - Starts with the content length as 8 little endian bytes, followed by every parent node in pre-order
+/// - Its hash is the plain BLAKE3 hash of the contents, regardless of the algorithm the tree was built with
+/// - Used together with the contents to produce [`SliceProof`]s for arbitrary byte ranges
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode))]
+pub struct Outboard {
+    hash: Digest,
+    encoded: Vec<u8>,
+}
+
+impl Outboard {
+    /// Computes the outboard of the given contents
+    pub fn new(contents: &[u8]) -> Self {
+        let content_len = contents.len() as u64;
+
+        let mut encoded = Vec::with_capacity(HEADER_LEN + bao::parent_count(content_len) as usize * PARENT_LEN);
+        encoded.extend_from_slice(&content_len.to_le_bytes());
+
+        let hash = Self::encode(contents, 0, true, &mut encoded);
+
+        Self {
+            hash: Digest::from_output(AlgorithmKind::Blake3, &hash),
+            encoded,
+        }
+    }
+
+    /// Recreates an outboard from its hash and encoded bytes, checking that their length is consistent
+    pub fn from_parts(hash: Digest, encoded: Vec<u8>) -> Result<Self, BaoError> {
+        if hash.algorithm() != AlgorithmKind::Blake3 {
+            return Err(BaoError::UnsupportedAlgorithm(hash.algorithm()));
+        }
+
+        let header: [u8; HEADER_LEN] = match encoded.get(..HEADER_LEN) {
+            Some(header) => header.try_into().unwrap(),
+            None => return Err(BaoError::InvalidOutboardLength(HEADER_LEN, encoded.len())),
+        };
+
+        let expected = HEADER_LEN as u64 + bao::parent_count(u64::from_le_bytes(header)) * PARENT_LEN as u64;
+        if expected != encoded.len() as u64 {
+            return Err(BaoError::InvalidOutboardLength(expected as usize, encoded.len()));
+        }
+
+        Ok(Self { hash, encoded })
+    }
+
+    /// Returns the BLAKE3 hash of the contents, the root of the outboard
+    pub fn hash(&self) -> &Digest {
+        &self.hash
+    }
+
+    /// Returns the length of the contents the outboard was computed for
+    pub fn content_len(&self) -> u64 {
+        u64::from_le_bytes(self.encoded[..HEADER_LEN].try_into().unwrap())
+    }
+
+    /// Returns the encoded bytes of the outboard
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.encoded
+    }
+
+    /// Produces a proof for `len` bytes of the contents starting at `start`, reading only the chunks it needs
+    ///
+    /// The contents must be the same the outboard was computed for, otherwise the proof will fail to verify.
+    pub fn slice(&self, mut contents: impl Read + Seek, start: u64, len: u64) -> Result<SliceProof, BaoError> {
+        let content_len = self.content_len();
+        let range = SliceRange::new(start, len, content_len);
+
+        let mut encoded = self.encoded[..HEADER_LEN].to_vec();
+        let mut parents = &self.encoded[HEADER_LEN..];
+
+        Self::extract(&mut contents, &mut parents, 0, content_len, true, &range, &mut encoded)?;
+
+        Ok(SliceProof::new(start, len, encoded))
+    }
+
+    /// Writes the parent nodes of a subtree in pre-order and returns its chaining value
+    fn encode(contents: &[u8], offset: u64, is_root: bool, encoded: &mut Vec<u8>) -> [u8; 32] {
+        if bao::is_chunk(contents.len() as u64) {
+            return bao::chunk_cv(contents, offset, is_root);
+        }
+
+        let (left, right) = contents.split_at(bao::left_len(contents.len() as u64) as usize);
+
+        // Reserves the parent node, its chaining values are only known once both subtrees are encoded
+        let position = encoded.len();
+        encoded.extend_from_slice(&[0; PARENT_LEN]);
+
+        let left_cv = Self::encode(left, offset, false, encoded);
+        let right_cv = Self::encode(right, offset + left.len() as u64, false, encoded);
+
+        encoded[position..position + 32].copy_from_slice(&left_cv);
+        encoded[position + 32..position + PARENT_LEN].copy_from_slice(&right_cv);
+
+        bao::parent_cv(&left_cv, &right_cv, is_root)
+    }
+
+    /// Copies the parent nodes and chunks of a subtree that overlap the range into the slice
+    fn extract(
+        contents: &mut (impl Read + Seek),
+        parents: &mut &[u8],
+        node_start: u64,
+        node_len: u64,
+        is_root: bool,
+        range: &SliceRange,
+        encoded: &mut Vec<u8>,
+    ) -> Result<(), BaoError> {
+        if !is_root && !range.overlaps(node_start, node_len) {
+            *parents = &parents[bao::parent_count(node_len) as usize * PARENT_LEN..];
+            return Ok(());
+        }
+
+        if bao::is_chunk(node_len) {
+            contents.seek(SeekFrom::Start(node_start))?;
+            let position = encoded.len();
+            encoded.resize(position + node_len as usize, 0);
+            contents.read_exact(&mut encoded[position..])?;
+            return Ok(());
+        }
+
+        let (parent, rest) = parents.split_at(PARENT_LEN);
+        encoded.extend_from_slice(parent);
+        *parents = rest;
+
+        let left_len = bao::left_len(node_len);
+        Self::extract(contents, parents, node_start, left_len, false, range, encoded)?;
+        Self::extract(contents, parents, node_start + left_len, node_len - left_len, false, range, encoded)
+    }
+}
+
+impl Debug for Outboard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outboard")
+            .field("hash", &self.hash)
+            .field("content_len", &self.content_len())
+            .finish()
+    }
+}
+
+// Decodes through `from_parts`, so outboards of corrupt snapshots are refused instead of panicking later
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for Outboard {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        let hash: Digest = bincode::Decode::decode(decoder)?;
+        let encoded: Vec<u8> = bincode::Decode::decode(decoder)?;
+        Outboard::from_parts(hash, encoded).map_err(|error| bincode::error::DecodeError::OtherString(error.to_string()))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(Outboard);
+
+#[cfg(all(test, feature = "bincode"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoding_checks_the_encoded_length() {
+        let config = bincode::config::standard();
+        let outboard = Outboard::new(&[7; 5000]);
+        let bytes = bincode::encode_to_vec(&outboard, config).unwrap();
+        let (decoded, _): (Outboard, _) = bincode::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(decoded, outboard);
+
+        for len in [0, HEADER_LEN, outboard.encoded.len() - 1] {
+            let corrupt = Outboard {
+                hash: outboard.hash,
+                encoded: outboard.encoded[..len].to_vec(),
+            };
+            let bytes = bincode::encode_to_vec(&corrupt, config).unwrap();
+            assert!(bincode::decode_from_slice::<Outboard, _>(&bytes, config).is_err());
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey, SIGNATURE_LENGTH};
+
+use crate::components::digest::Digest;
+use crate::error::SigningError;
+use crate::utils::algorithm::{AlgorithmKind, OutputLength};
+use crate::utils::chunking::ChunkParams;
+
+/// Magic bytes every signed root payload starts with, the last byte is the version of the encoding
+pub const SIGNED_ROOT_MAGIC: [u8; 8] = *b"MRKLSIG\x01";
+
+/// The settings a signed root hash was built with, all of which are covered by the signature
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RootOptions {
+    /// Whether names were included in the hashes of files and directories
+    pub hash_names: bool,
+    /// The chunk sizes files were split into, if they were split at all
+    pub chunking: Option<ChunkParams>,
+    /// The context string of the algorithm, if it has one
+    pub context: Option<String>,
+    /// The output length of the algorithm in bytes, if it is variable
+    pub output_len: Option<OutputLength>,
+    /// The fingerprint of the key of the algorithm, if it has one, the key itself is never signed
+    pub key_fingerprint: Option<[u8; 32]>,
+}
+
+/// An Ed25519 signed root hash of a tree, verifiable offline with the signer's public key
+///
+/// The signature covers the canonical payload returned by [`SignedRoot::payload`], laid out as
+/// follows, with integers in big-endian:
+///
+/// - the 8 magic bytes `MRKLSIG\x01`
+/// - the algorithm name, such as `blake3`, as a `u8` length followed by its ASCII bytes
+/// - the root hash, as a `u8` length followed by its bytes
+/// - whether names were hashed, as a `u8` of `0` or `1`
+/// - the context string, as a `u8` of `0`, or `1` followed by a `u32` length and its UTF-8 bytes
+/// - the output length, as a `u8` of `0` when it is not variable
+/// - the key fingerprint, as a `u8` of `0`, or `1` followed by its 32 bytes
+/// - the chunk sizes, as a `u8` of `0`, or `1` followed by the minimum, average and maximum as `u32`s
+/// - the signing time, as a `u64` of seconds since the unix epoch
+///
+/// [`SignedRoot::to_bytes`] appends the 64 byte signature to the payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedRoot {
+    pub root_hash: Digest,
+    pub algorithm: AlgorithmKind,
+    pub options: RootOptions,
+    /// Seconds since the unix epoch at which the root was signed
+    pub timestamp: u64,
+    pub signature: Signature,
+}
+
+impl SignedRoot {
+    /// Signs a root hash built with the given algorithm and options
+    pub fn sign(root_hash: Digest, options: RootOptions, timestamp: u64, key: &SigningKey) -> Self {
+        let payload = Self::encode_payload(&root_hash, &options, timestamp);
+        Self {
+            algorithm: root_hash.algorithm(),
+            root_hash,
+            options,
+            timestamp,
+            signature: key.sign(&payload),
+        }
+    }
+
+    /// Returns the canonical payload the signature covers
+    pub fn payload(&self) -> Vec<u8> {
+        Self::encode_payload(&self.root_hash, &self.options, self.timestamp)
+    }
+
+    /// Checks the signature against the signer's public key
+    ///
+    /// Uses Ed25519's strict verification, which also refuses weak keys and non-canonical signatures.
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), SigningError> {
+        if self.algorithm != self.root_hash.algorithm() {
+            return Err(SigningError::AlgorithmMismatch);
+        }
+        key.verify_strict(&self.payload(), &self.signature)
+            .map_err(|_| SigningError::InvalidSignature)
+    }
+
+    /// Encodes the payload followed by the signature
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.payload();
+        bytes.extend_from_slice(&self.signature.to_bytes());
+        bytes
+    }
+
+    /// Decodes a signed root written by [`SignedRoot::to_bytes`], without verifying its signature
+    ///
+    /// Only the canonical encoding is accepted: flags must be `0` or `1` and strings valid UTF-8.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SigningError> {
+        let mut reader = PayloadReader(bytes);
+        if reader.take(SIGNED_ROOT_MAGIC.len())? != SIGNED_ROOT_MAGIC {
+            return Err(SigningError::InvalidMagic);
+        }
+
+        let name_len = reader.u8()? as usize;
+        let name = reader.string(name_len)?;
+        let algorithm = match AlgorithmKind::from_name(&name) {
+            Some(algorithm) => algorithm,
+            None => return Err(SigningError::UnsupportedAlgorithm(name)),
+        };
+
+        let hash_len = reader.u8()? as usize;
+        let root_hash = Digest::new(algorithm, reader.take(hash_len)?).map_err(SigningError::InvalidDigest)?;
+
+        let hash_names = reader.flag()?;
+        let context = match reader.flag()? {
+            false => None,
+            true => {
+                let len = u32::from_be_bytes(reader.array()?) as usize;
+                Some(reader.string(len)?)
+            }
+        };
+        let output_len = match reader.u8()? {
+            0 => None,
+            len => Some(OutputLength::new(len as usize).map_err(SigningError::InvalidOutputLength)?),
+        };
+        let key_fingerprint = match reader.flag()? {
+            false => None,
+            true => Some(reader.array()?),
+        };
+        let chunking = match reader.flag()? {
+            false => None,
+            true => {
+                let min = u32::from_be_bytes(reader.array()?);
+                let avg = u32::from_be_bytes(reader.array()?);
+                let max = u32::from_be_bytes(reader.array()?);
+                Some(ChunkParams::new(min, avg, max).map_err(SigningError::InvalidChunking)?)
+            }
+        };
+        let timestamp = u64::from_be_bytes(reader.array()?);
+        let signature = Signature::from_bytes(&reader.array::<SIGNATURE_LENGTH>()?);
+
+        if !reader.0.is_empty() {
+            return Err(SigningError::TrailingBytes(reader.0.len()));
+        }
+
+        Ok(Self {
+            root_hash,
+            algorithm,
+            options: RootOptions {
+                hash_names,
+                chunking,
+                context,
+                output_len,
+                key_fingerprint,
+            },
+            timestamp,
+            signature,
+        })
+    }
+
+    fn encode_payload(root_hash: &Digest, options: &RootOptions, timestamp: u64) -> Vec<u8> {
+        let mut payload = SIGNED_ROOT_MAGIC.to_vec();
+
+        let name = root_hash.algorithm().name().as_bytes();
+        payload.push(name.len() as u8);
+        payload.extend_from_slice(name);
+
+        payload.push(root_hash.len() as u8);
+        payload.extend_from_slice(root_hash.as_bytes());
+
+        payload.push(options.hash_names as u8);
+
+        match &options.context {
+            Some(context) => {
+                payload.push(1);
+                payload.extend_from_slice(&(context.len() as u32).to_be_bytes());
+                payload.extend_from_slice(context.as_bytes());
+            }
+            None => payload.push(0),
+        }
+
+        payload.push(options.output_len.map_or(0, |output_len| output_len.get() as u8));
+
+        match &options.key_fingerprint {
+            Some(key_fingerprint) => {
+                payload.push(1);
+                payload.extend_from_slice(key_fingerprint);
+            }
+            None => payload.push(0),
+        }
+
+        match &options.chunking {
+            Some(chunking) => {
+                payload.push(1);
+                for size in [chunking.min(), chunking.avg(), chunking.max()] {
+                    payload.extend_from_slice(&size.to_be_bytes());
+                }
+            }
+            None => payload.push(0),
+        }
+
+        payload.extend_from_slice(&timestamp.to_be_bytes());
+        payload
+    }
+}
+
+/// Reads the fields of a payload front to back
+struct PayloadReader<'a>(&'a [u8]);
+
+impl<'a> PayloadReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SigningError> {
+        if self.0.len() < len {
+            return Err(SigningError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, SigningError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn flag(&mut self) -> Result<bool, SigningError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(SigningError::InvalidFlag(flag)),
+        }
+    }
+
+    fn string(&mut self, len: usize) -> Result<String, SigningError> {
+        match std::str::from_utf8(self.take(len)?) {
+            Ok(string) => Ok(string.to_string()),
+            Err(_) => Err(SigningError::InvalidUtf8),
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SigningError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+}
@@ -0,0 +1,177 @@
+use std::ops::Range;
+
+use crate::components::digest::Digest;
+use crate::components::inclusion_path::{InclusionPath, InclusionStep};
+use crate::error::BaoError;
+use crate::utils::algorithm::AlgorithmKind;
+use crate::utils::bao::{self, SliceRange, HEADER_LEN, PARENT_LEN};
+
+/// A Bao slice proving a range of bytes of a file against the file's BLAKE3 hash
+///
+/// Holds the content length, followed by the parent nodes and chunks covering the range in pre-order.
+/// Proofs produced from a tree also hold the inclusion path of the file, proving it against the root hash.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct SliceProof {
+    start: u64,
+    len: u64,
+    encoded: Vec<u8>,
+    inclusion: Option<InclusionPath>,
+}
+
+impl SliceProof {
+    /// Creates a slice proof from the encoded bytes of a slice of `len` bytes starting at `start`
+    pub fn new(start: u64, len: u64, encoded: Vec<u8>) -> Self {
+        Self {
+            start,
+            len,
+            encoded,
+            inclusion: None,
+        }
+    }
+
+    /// Attaches the inclusion path of the file, linking its hash to the root of its tree
+    pub fn with_inclusion(self, inclusion: InclusionPath) -> Self {
+        Self {
+            inclusion: Some(inclusion),
+            ..self
+        }
+    }
+
+    /// Returns the inclusion path of the file, if the proof was produced from a tree
+    pub fn inclusion(&self) -> Option<&InclusionPath> {
+        self.inclusion.as_ref()
+    }
+
+    /// Returns the requested range of bytes
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.start.saturating_add(self.len)
+    }
+
+    /// Returns the encoded bytes of the slice
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.encoded
+    }
+
+    /// Verifies the slice against the BLAKE3 hash of the whole contents, returning the proven bytes
+    ///
+    /// The returned bytes are the requested range, cut short at the end of the contents.
+    pub fn verify(&self, hash: &Digest) -> Result<Vec<u8>, BaoError> {
+        if hash.algorithm() != AlgorithmKind::Blake3 {
+            return Err(BaoError::UnsupportedAlgorithm(hash.algorithm()));
+        }
+        let hash: [u8; 32] = hash.as_bytes().try_into().map_err(|_| BaoError::HashMismatch)?;
+
+        let (header, mut encoded) = match self.encoded.split_first_chunk::<HEADER_LEN>() {
+            Some(split) => split,
+            None => return Err(BaoError::UnexpectedEnd),
+        };
+        let content_len = u64::from_le_bytes(*header);
+
+        let range = SliceRange::new(self.start, self.len, content_len);
+        let mut output = Vec::new();
+
+        self.decode(&mut encoded, 0, content_len, &hash, true, &range, &mut output)?;
+
+        if !encoded.is_empty() {
+            return Err(BaoError::TrailingBytes(encoded.len()));
+        }
+
+        Ok(output)
+    }
+
+    /// Verifies the whole chain from the root hash of a tree down to the requested bytes of the file
+    /// at the given relative path, returning them
+    ///
+    /// The root hash must be the plain BLAKE3 hash of the tree, which the inclusion path leads to.
+    /// For trees built with hashed names, the names along the inclusion path must spell the relative path.
+    /// Trees built without hashed names leave names out of their hashes, so their proofs only show that
+    /// some file of the tree holds the bytes, not which path it has. Hashes do not cover kinds in either case,
+    /// so the pairwise hash of two entries of a directory can pose as a 64 byte file at the directory's path,
+    /// only paths known to be files should be asked for.
+    pub fn verify_root(&self, root_hash: &Digest, relative_path: impl AsRef<str>) -> Result<Vec<u8>, BaoError> {
+        if root_hash.algorithm() != AlgorithmKind::Blake3 {
+            return Err(BaoError::UnsupportedAlgorithm(root_hash.algorithm()));
+        }
+        let inclusion = match &self.inclusion {
+            Some(inclusion) => inclusion,
+            None => return Err(BaoError::MissingInclusionPath),
+        };
+
+        // Names run from the file up to the root, whose own name is not part of relative paths
+        let relative_path = relative_path.as_ref();
+        let mut names: Vec<_> = inclusion
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                InclusionStep::Name(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        if names.pop().is_some() {
+            let segments = relative_path.split('/').filter(|segment| !segment.is_empty());
+            if !names.into_iter().rev().eq(segments) {
+                return Err(BaoError::PathMismatch(relative_path.to_string()));
+            }
+        }
+
+        if inclusion.root_hash() != *root_hash {
+            return Err(BaoError::HashMismatch);
+        }
+        self.verify(&inclusion.file_hash)
+    }
+
+    /// Checks the parent nodes and chunks of a subtree against its chaining value,
+    /// collecting the bytes of the requested range
+    #[allow(clippy::too_many_arguments)]
+    fn decode(
+        &self,
+        encoded: &mut &[u8],
+        node_start: u64,
+        node_len: u64,
+        expected: &[u8; 32],
+        is_root: bool,
+        range: &SliceRange,
+        output: &mut Vec<u8>,
+    ) -> Result<(), BaoError> {
+        if !is_root && !range.overlaps(node_start, node_len) {
+            return Ok(());
+        }
+
+        if bao::is_chunk(node_len) {
+            let chunk = Self::take(encoded, node_len as usize)?;
+            if !bao::cv_eq(&bao::chunk_cv(chunk, node_start, is_root), expected) {
+                return Err(BaoError::HashMismatch);
+            }
+
+            // Keeps only the part of the chunk that was requested
+            let requested = self.range();
+            let from = requested.start.clamp(node_start, node_start + node_len) - node_start;
+            let to = requested.end.clamp(node_start, node_start + node_len) - node_start;
+            output.extend_from_slice(&chunk[from as usize..to as usize]);
+            return Ok(());
+        }
+
+        let parent = Self::take(encoded, PARENT_LEN)?;
+        let (left_cv, right_cv) = parent.split_at(32);
+        let left_cv: [u8; 32] = left_cv.try_into().unwrap();
+        let right_cv: [u8; 32] = right_cv.try_into().unwrap();
+        if !bao::cv_eq(&bao::parent_cv(&left_cv, &right_cv, is_root), expected) {
+            return Err(BaoError::HashMismatch);
+        }
+
+        let left_len = bao::left_len(node_len);
+        self.decode(encoded, node_start, left_len, &left_cv, false, range, output)?;
+        self.decode(encoded, node_start + left_len, node_len - left_len, &right_cv, false, range, output)
+    }
+
+    /// Takes the next bytes of the slice
+    fn take<'a>(encoded: &mut &'a [u8], len: usize) -> Result<&'a [u8], BaoError> {
+        if encoded.len() < len {
+            return Err(BaoError::UnexpectedEnd);
+        }
+        let (taken, rest) = encoded.split_at(len);
+        *encoded = rest;
+        Ok(taken)
+    }
+}
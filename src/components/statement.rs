@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use crate::components::digest::Digest;
+use crate::utils::json;
+
+/// Type URI of in-toto v1 statements
+pub const IN_TOTO_STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Which subjects an in-toto statement lists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SubjectMode {
+    /// A subject per file, named by its slash separated relative path and digested by its contents hashes
+    #[default]
+    Files,
+    /// A single subject named after the root, digested by the merkle root hashes of the tree
+    ///
+    /// Root hashes are not contents hashes, so they are keyed as `merkle-<algorithm>`, such as `merkle-blake3`
+    Root,
+}
+
+/// An artifact an in-toto statement is about
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Subject {
+    pub name: String,
+    /// Lowercase hex hashes by in-toto algorithm name, such as `sha256` or `sha3_256`, or `merkle-sha256` for root hashes
+    pub digest: BTreeMap<String, String>,
+}
+
+impl Subject {
+    /// Creates a subject from the contents hashes of a file
+    pub(crate) fn new<'a>(name: String, hashes: impl IntoIterator<Item = &'a Digest>) -> Self {
+        Self::with_prefix(name, hashes, "")
+    }
+
+    /// Creates a subject from the merkle root hashes of a tree, keyed apart from contents hashes
+    pub(crate) fn merkle_root<'a>(name: String, hashes: impl IntoIterator<Item = &'a Digest>) -> Self {
+        Self::with_prefix(name, hashes, "merkle-")
+    }
+
+    fn with_prefix<'a>(name: String, hashes: impl IntoIterator<Item = &'a Digest>, prefix: &str) -> Self {
+        let digest = hashes
+            .into_iter()
+            .map(|hash| (format!("{}{}", prefix, hash.algorithm().name().replace('-', "_")), hash.to_hex()))
+            .collect();
+        Self { name, digest }
+    }
+
+    fn to_json(&self) -> String {
+        let digest = json::object(self.digest.iter().map(|(algorithm, hash)| (algorithm.as_str(), json::string(hash))));
+        json::object([("name", json::string(&self.name)), ("digest", digest)])
+    }
+}
+
+/// An in-toto v1 statement about the files or the root of a tree
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Statement {
+    pub subjects: Vec<Subject>,
+    /// URI identifying the kind of predicate
+    pub predicate_type: String,
+    /// The predicate as an encoded JSON value, if any
+    pub predicate: Option<String>,
+}
+
+impl Statement {
+    /// Sets the predicate, which must be an encoded JSON value
+    pub fn predicate(mut self, predicate: impl Into<String>) -> Self {
+        self.predicate = Some(predicate.into());
+        self
+    }
+
+    /// Encodes the statement as compact JSON
+    pub fn to_json(&self) -> String {
+        let mut entries = vec![
+            ("_type", json::string(IN_TOTO_STATEMENT_TYPE)),
+            ("subject", json::array(self.subjects.iter().map(Subject::to_json))),
+            ("predicateType", json::string(&self.predicate_type)),
+        ];
+        if let Some(predicate) = &self.predicate {
+            entries.push(("predicate", predicate.clone()));
+        }
+        json::object(entries)
+    }
+}
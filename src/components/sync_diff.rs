@@ -0,0 +1,28 @@
+/// Files that differ between two peers after a sync session, as slash separated relative paths
+///
+/// Directories without files are not listed, files below a directory only one peer has are.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SyncDiff {
+    /// Files both peers have with different hashes
+    pub changed: Vec<String>,
+    /// Files only this peer has, including files where the other peer has a directory
+    pub only_local: Vec<String>,
+    /// Files only the other peer has, including files where this peer has a directory
+    pub only_remote: Vec<String>,
+}
+
+impl SyncDiff {
+    /// Returns whether both peers hold the same files
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.only_local.is_empty() && self.only_remote.is_empty()
+    }
+
+    /// Returns the same differences as seen by the other peer
+    pub fn mirrored(self) -> Self {
+        Self {
+            changed: self.changed,
+            only_local: self.only_remote,
+            only_remote: self.only_local,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::components::digest::Digest;
+use crate::utils::bittorrent::PieceLength;
+
+/// The BitTorrent v2 hashes of a file
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct TorrentFile {
+    /// Length of the file in bytes
+    pub length: u64,
+    /// Number of bytes covered by each hash of the piece layer
+    pub piece_length: PieceLength,
+    /// Root of the file's merkle tree of 16 KiB blocks, none for empty files
+    pub pieces_root: Option<Digest>,
+    /// Hashes of the file's pieces, empty unless the file is larger than a single piece
+    pub piece_layer: Vec<Digest>,
+}
@@ -0,0 +1,11 @@
+use crate::components::cid::Cid;
+
+/// The IPFS UnixFS identity of a file or directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
+pub struct UnixFsEntry {
+    /// Content identifier of the root block of the entry
+    pub cid: Cid,
+    /// Size of every block of the entry's DAG combined, the `Tsize` of links pointing to it
+    pub cumulative_size: u64,
+}
@@ -0,0 +1,24 @@
+use crate::components::merkle_path::MerklePath;
+
+/// Differences between a stored tree and a directory on disk
+///
+/// Relative paths are shared by both trees, absolute paths point into the verified directory.
+/// Missing and extra directories are listed once, without their descendants.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VerificationReport {
+    /// Entries of the stored tree that are not on disk
+    pub missing: Vec<MerklePath>,
+    /// Entries on disk that are not in the stored tree
+    pub extra: Vec<MerklePath>,
+    /// Files whose hashes differ from the stored ones
+    pub modified: Vec<MerklePath>,
+    /// Entries that were files and became directories or the other way around
+    pub type_changed: Vec<MerklePath>,
+}
+
+impl VerificationReport {
+    /// Returns whether the directory matches the stored tree
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.modified.is_empty() && self.type_changed.is_empty()
+    }
+}
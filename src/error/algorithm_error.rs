@@ -0,0 +1,23 @@
+use std::fmt::{Display, Formatter};
+
+/// Types of errors that can occur when configuring a hashing algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlgorithmError {
+    OutputLengthTooShort(usize, usize),
+    OutputLengthTooLong(usize, usize),
+}
+
+impl Display for AlgorithmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlgorithmError::OutputLengthTooShort(len, min) => {
+                write!(f, "Output length of {} bytes is below the safe minimum of {} bytes", len, min)
+            }
+            AlgorithmError::OutputLengthTooLong(len, max) => {
+                write!(f, "Output length of {} bytes is above the maximum of {} bytes", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AlgorithmError {}
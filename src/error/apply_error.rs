@@ -0,0 +1,62 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+use crate::components::verification_report::VerificationReport;
+use crate::error::IndexingError;
+
+/// Types of errors that can occur when making a target directory match a source tree.
+#[derive(Debug)]
+pub enum ApplyError {
+    NotADirectory(PathBuf),
+    UnableToReadFile(PathBuf, io::Error),
+    UnableToWriteFile(PathBuf, io::Error),
+    UnableToRemove(PathBuf, io::Error),
+    FileChanged(PathBuf),
+    DirectoryInTheWay(PathBuf),
+    LinkInPath(PathBuf),
+    Indexing(IndexingError),
+    VerificationFailed(VerificationReport),
+}
+
+impl Display for ApplyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplyError::NotADirectory(path) => {
+                write!(f, "Path is not a directory: {:?}", path)
+            }
+            ApplyError::UnableToReadFile(path, err) => {
+                write!(f, "Unable to read file: {:?}, error: {}", path, err)
+            }
+            ApplyError::UnableToWriteFile(path, err) => {
+                write!(f, "Unable to write file: {:?}, error: {}", path, err)
+            }
+            ApplyError::UnableToRemove(path, err) => {
+                write!(f, "Unable to remove: {:?}, error: {}", path, err)
+            }
+            ApplyError::FileChanged(path) => {
+                write!(f, "File no longer matches its hash in the tree: {:?}", path)
+            }
+            ApplyError::DirectoryInTheWay(path) => {
+                write!(f, "Directory stands where the source has a file, deleting it needs the delete option: {:?}", path)
+            }
+            ApplyError::LinkInPath(path) => {
+                write!(f, "Refusing to write below a link in the target directory: {:?}", path)
+            }
+            ApplyError::Indexing(error) => {
+                write!(f, "Unable to index the target directory: {}", error)
+            }
+            ApplyError::VerificationFailed(report) => {
+                write!(f, "Target directory does not match the source tree after syncing: {:?}", report)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApplyError {}
+
+impl From<IndexingError> for ApplyError {
+    fn from(error: IndexingError) -> Self {
+        ApplyError::Indexing(error)
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+/// Types of errors that can occur when exporting an in-toto statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttestationError {
+    HashesIncludeNames,
+    HashesChunked,
+    MissingName,
+    NonUtf8Path(PathBuf),
+}
+
+impl Display for AttestationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttestationError::HashesIncludeNames => {
+                write!(f, "File subjects need plain contents hashes, the tree was built with hashed names")
+            }
+            AttestationError::HashesChunked => {
+                write!(f, "File subjects need plain contents hashes, the tree was built with chunking")
+            }
+            AttestationError::MissingName => {
+                write!(f, "Root of the tree has no name to give its subject")
+            }
+            AttestationError::NonUtf8Path(path) => {
+                write!(f, "Subject names must be valid UTF-8, found: {:?}", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttestationError {}
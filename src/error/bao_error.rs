@@ -0,0 +1,68 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::utils::algorithm::AlgorithmKind;
+
+/// Types of errors that can occur when creating or verifying BLAKE3 outboards and slice proofs.
+#[derive(Debug)]
+pub enum BaoError {
+    Io(io::Error),
+    MissingOutboard,
+    InvalidOutboardLength(usize, usize),
+    UnsupportedAlgorithm(AlgorithmKind),
+    UnexpectedEnd,
+    TrailingBytes(usize),
+    HashMismatch,
+    MissingInclusionPath,
+    PathNotFound(String),
+    PathMismatch(String),
+    ChunkedContents,
+}
+
+impl Display for BaoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BaoError::Io(error) => {
+                write!(f, "Unable to read the contents of the file, error: {}", error)
+            }
+            BaoError::MissingOutboard => {
+                write!(f, "No outboard was computed for the item")
+            }
+            BaoError::InvalidOutboardLength(expected, found) => {
+                write!(f, "Invalid outboard length, expected: {} bytes, found: {} bytes", expected, found)
+            }
+            BaoError::UnsupportedAlgorithm(kind) => {
+                write!(f, "Slice proofs can only be verified against blake3 hashes, found: {}", kind)
+            }
+            BaoError::UnexpectedEnd => {
+                write!(f, "Slice proof ended unexpectedly")
+            }
+            BaoError::TrailingBytes(len) => {
+                write!(f, "Slice proof has {} unexpected trailing bytes", len)
+            }
+            BaoError::HashMismatch => {
+                write!(f, "Slice proof does not match the expected hash")
+            }
+            BaoError::MissingInclusionPath => {
+                write!(f, "Slice proof has no inclusion path to verify against a root hash")
+            }
+            BaoError::PathNotFound(path) => {
+                write!(f, "No file with the path: {} was found in the tree", path)
+            }
+            BaoError::PathMismatch(path) => {
+                write!(f, "Slice proof is not for the file with the path: {}", path)
+            }
+            BaoError::ChunkedContents => {
+                write!(f, "Files of trees built with chunking cannot be proven against the root hash")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BaoError {}
+
+impl From<io::Error> for BaoError {
+    fn from(error: io::Error) -> Self {
+        BaoError::Io(error)
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+use crate::error::SnapshotError;
+
+/// Types of errors that can occur when exporting to or restoring from a content-addressed store.
+#[derive(Debug)]
+pub enum CasError {
+    UnableToReadFile(PathBuf, io::Error),
+    UnableToWriteFile(PathBuf, io::Error),
+    FileChanged(PathBuf),
+    MissingBlob(String),
+    CorruptBlob(String),
+    UnsafePath(PathBuf),
+    ManifestMismatch,
+    TargetNotEmpty(PathBuf),
+    Snapshot(SnapshotError),
+}
+
+impl Display for CasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CasError::UnableToReadFile(path, err) => {
+                write!(f, "Unable to read file: {:?}, error: {}", path, err)
+            }
+            CasError::UnableToWriteFile(path, err) => {
+                write!(f, "Unable to write file: {:?}, error: {}", path, err)
+            }
+            CasError::FileChanged(path) => {
+                write!(f, "File no longer matches its hash in the tree: {:?}", path)
+            }
+            CasError::MissingBlob(hash) => {
+                write!(f, "Store has no blob for hash: {}", hash)
+            }
+            CasError::CorruptBlob(hash) => {
+                write!(f, "Blob does not match its hash: {}", hash)
+            }
+            CasError::UnsafePath(path) => {
+                write!(f, "Snapshot holds a path that is not plainly below its root: {:?}", path)
+            }
+            CasError::ManifestMismatch => {
+                write!(f, "Blob manifest does not list one blob per file of the snapshot")
+            }
+            CasError::TargetNotEmpty(path) => {
+                write!(f, "Restore target must be missing or an empty directory: {:?}", path)
+            }
+            CasError::Snapshot(error) => {
+                write!(f, "Unable to read or write the snapshot: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CasError {}
+
+impl From<SnapshotError> for CasError {
+    fn from(error: SnapshotError) -> Self {
+        CasError::Snapshot(error)
+    }
+}
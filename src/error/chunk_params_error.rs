@@ -0,0 +1,32 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+/// Types of errors that can occur when configuring content-defined chunking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkParamsError {
+    MinSizeOutOfRange(u32, RangeInclusive<u32>),
+    AvgSizeOutOfRange(u32, RangeInclusive<u32>),
+    MaxSizeOutOfRange(u32, RangeInclusive<u32>),
+    Unordered(u32, u32, u32),
+}
+
+impl Display for ChunkParamsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkParamsError::MinSizeOutOfRange(size, range) => {
+                write!(f, "Minimum chunk size of {} bytes is outside of the range {:?}", size, range)
+            }
+            ChunkParamsError::AvgSizeOutOfRange(size, range) => {
+                write!(f, "Average chunk size of {} bytes is outside of the range {:?}", size, range)
+            }
+            ChunkParamsError::MaxSizeOutOfRange(size, range) => {
+                write!(f, "Maximum chunk size of {} bytes is outside of the range {:?}", size, range)
+            }
+            ChunkParamsError::Unordered(min, avg, max) => {
+                write!(f, "Chunk sizes must satisfy min <= avg <= max, found: {}/{}/{}", min, avg, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkParamsError {}
@@ -0,0 +1,35 @@
+use std::fmt::{Display, Formatter};
+
+/// Types of errors that can occur when parsing a content identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CidError {
+    UnsupportedMultibase(String),
+    InvalidEncoding,
+    UnsupportedVersion(u64),
+    UnsupportedCodec(u64),
+    UnsupportedMultihash(u64, u64),
+}
+
+impl Display for CidError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CidError::UnsupportedMultibase(cid) => {
+                write!(f, "Only base32 content identifiers are supported, found: {}", cid)
+            }
+            CidError::InvalidEncoding => {
+                write!(f, "Invalid content identifier encoding")
+            }
+            CidError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported content identifier version: {}", version)
+            }
+            CidError::UnsupportedCodec(code) => {
+                write!(f, "Unsupported content identifier codec: {:#x}", code)
+            }
+            CidError::UnsupportedMultihash(code, len) => {
+                write!(f, "Unsupported multihash with code {:#x} and length {}, expected sha2-256", code, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CidError {}
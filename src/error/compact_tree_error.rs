@@ -0,0 +1,42 @@
+use std::fmt::{Display, Formatter};
+
+use crate::components::merkle_path::MerklePath;
+use crate::utils::algorithm::AlgorithmKind;
+
+/// Types of errors that can occur when converting a merkle tree into a compact tree or indexing one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactTreeError {
+    TooManyNodes,
+    TooManyNames,
+    TooManyChunks,
+    UnsupportedExtras(MerklePath),
+    MissingHash(MerklePath, AlgorithmKind),
+}
+
+impl Display for CompactTreeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompactTreeError::TooManyNodes => {
+                write!(f, "Tree has more than {} nodes, too many for a compact tree", u32::MAX - 1)
+            }
+            CompactTreeError::TooManyNames => {
+                write!(f, "Tree has more than {} distinct names, too many for a compact tree", u32::MAX - 1)
+            }
+            CompactTreeError::TooManyChunks => {
+                write!(f, "Tree has more than {} chunks, too many for a compact tree", u32::MAX - 1)
+            }
+            CompactTreeError::UnsupportedExtras(path) => {
+                write!(
+                    f,
+                    "Item holds outboards, UnixFS identities or BitTorrent pieces, which compact trees do not keep: {:?}",
+                    path.relative
+                )
+            }
+            CompactTreeError::MissingHash(path, algorithm) => {
+                write!(f, "Item has no hash for algorithm: {:?}, path: {:?}", algorithm, path.relative)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompactTreeError {}
@@ -0,0 +1,31 @@
+use std::fmt::{Display, Formatter};
+
+/// Types of errors that can occur when creating or parsing a digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestError {
+    InvalidLength(usize),
+    InvalidHex(String),
+    MissingAlgorithm(String),
+    UnknownAlgorithm(String),
+}
+
+impl Display for DigestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DigestError::InvalidLength(len) => {
+                write!(f, "Invalid digest length: {}", len)
+            }
+            DigestError::InvalidHex(hex) => {
+                write!(f, "Invalid hex digest: {}", hex)
+            }
+            DigestError::MissingAlgorithm(digest) => {
+                write!(f, "Digest is missing an algorithm prefix: {}", digest)
+            }
+            DigestError::UnknownAlgorithm(name) => {
+                write!(f, "Unknown or disabled hashing algorithm: {}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DigestError {}
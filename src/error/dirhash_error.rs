@@ -0,0 +1,45 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// Types of errors that can occur when computing or checking a Go module dirhash.
+#[derive(Debug)]
+pub enum DirHashError {
+    UnableToReadFile(PathBuf, io::Error),
+    UnableToReadMetadata(PathBuf, io::Error),
+    NonUtf8Path(PathBuf),
+    FileNameWithNewline(String),
+    MalformedGoSumLine(String),
+    UnsupportedHashVersion(String),
+    MissingGoMod,
+}
+
+impl Display for DirHashError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DirHashError::UnableToReadFile(path, err) => {
+                write!(f, "Unable to read file: {:?}, error: {}", path, err)
+            }
+            DirHashError::UnableToReadMetadata(path, err) => {
+                write!(f, "Unable to read metadata: {:?}, error: {}", path, err)
+            }
+            DirHashError::NonUtf8Path(path) => {
+                write!(f, "Go module paths must be valid UTF-8, found: {:?}", path)
+            }
+            DirHashError::FileNameWithNewline(name) => {
+                write!(f, "File names cannot contain newlines: {:?}", name)
+            }
+            DirHashError::MalformedGoSumLine(line) => {
+                write!(f, "Expected a go.sum line of a module, a version and a hash, found: {:?}", line)
+            }
+            DirHashError::UnsupportedHashVersion(hash) => {
+                write!(f, "Only h1: hashes are supported, found: {}", hash)
+            }
+            DirHashError::MissingGoMod => {
+                write!(f, "The tree has no go.mod file at its root")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DirHashError {}
@@ -5,6 +5,9 @@ use std::path::{PathBuf, StripPrefixError};
  #[cfg(feature = "camino")]
 use camino::Utf8PathBuf;
 
+use crate::error::CompactTreeError;
+use crate::utils::algorithm::AlgorithmKind;
+
 /// Types of errors that can occur when recursively indexing a directory for its hashes.
 #[derive(Debug)]
 pub enum IndexingError {
@@ -29,6 +32,9 @@ pub enum IndexingError {
     UnableToReadDirEntry(PathBuf, io::Error),
      #[cfg(not(feature = "camino"))]
     UnableToStripRootPrefix(PathBuf, String, StripPrefixError),
+    Cancelled,
+    ConflictingAlgorithms(AlgorithmKind),
+    CompactTree(CompactTreeError),
 }
 
 impl Display for IndexingError {
@@ -52,6 +58,15 @@ impl Display for IndexingError {
             IndexingError::UnableToStripRootPrefix(path, root, error) => {
                 write!(f, "Unable to strip root prefix for path: {:?}, where root: {}, error: {}", path, root, error)
             }
+            IndexingError::Cancelled => {
+                write!(f, "Indexing was cancelled")
+            }
+            IndexingError::ConflictingAlgorithms(kind) => {
+                write!(f, "Algorithms of the same kind must have the same parameters, found several: {:?}", kind)
+            }
+            IndexingError::CompactTree(error) => {
+                write!(f, "Unable to index into a compact tree: {}", error)
+            }
         }
     }
 }
@@ -59,5 +74,11 @@ impl Display for IndexingError {
 
 impl std::error::Error for IndexingError {}
 
+impl From<CompactTreeError> for IndexingError {
+    fn from(error: CompactTreeError) -> Self {
+        IndexingError::CompactTree(error)
+    }
+}
+
 
 
@@ -0,0 +1,19 @@
+use std::fmt::{Display, Formatter};
+
+/// Types of errors that can occur when merging directory trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    SettingsMismatch,
+}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::SettingsMismatch => {
+                write!(f, "Trees were built with different algorithms, chunking or name hashing")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
@@ -1,4 +1,61 @@
+pub use algorithm_error::AlgorithmError;
+#[cfg(feature = "apply")]
+pub use apply_error::ApplyError;
+#[cfg(feature = "attestation")]
+pub use attestation_error::AttestationError;
+#[cfg(feature = "bao")]
+pub use bao_error::BaoError;
+#[cfg(feature = "cas")]
+pub use cas_error::CasError;
+pub use chunk_params_error::ChunkParamsError;
+pub use compact_tree_error::CompactTreeError;
+#[cfg(feature = "unixfs")]
+pub use cid_error::CidError;
+pub use digest_error::DigestError;
+#[cfg(feature = "dirhash")]
+pub use dirhash_error::DirHashError;
 pub use indexing_error::IndexingError;
+pub use merge_error::MergeError;
+#[cfg(feature = "oci")]
+pub use oci_error::OciError;
+#[cfg(feature = "nar")]
+pub use nar_error::NarError;
+#[cfg(feature = "signing")]
+pub use signing_error::SigningError;
+#[cfg(feature = "bincode")]
+pub use snapshot_error::SnapshotError;
+#[cfg(feature = "sync")]
+pub use sync_error::SyncError;
+#[cfg(feature = "bittorrent")]
+pub use torrent_error::TorrentError;
 
+mod algorithm_error;
+#[cfg(feature = "apply")]
+mod apply_error;
+#[cfg(feature = "attestation")]
+mod attestation_error;
+#[cfg(feature = "bao")]
+mod bao_error;
+#[cfg(feature = "cas")]
+mod cas_error;
+mod chunk_params_error;
+mod compact_tree_error;
+#[cfg(feature = "unixfs")]
+mod cid_error;
+mod digest_error;
+#[cfg(feature = "dirhash")]
+mod dirhash_error;
 mod indexing_error;
-
+mod merge_error;
+#[cfg(feature = "nar")]
+mod nar_error;
+#[cfg(feature = "oci")]
+mod oci_error;
+#[cfg(feature = "signing")]
+mod signing_error;
+#[cfg(feature = "bincode")]
+mod snapshot_error;
+#[cfg(feature = "sync")]
+mod sync_error;
+#[cfg(feature = "bittorrent")]
+mod torrent_error;
@@ -0,0 +1,37 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// Types of errors that can occur when serializing a tree into a Nix archive.
+#[derive(Debug)]
+pub enum NarError {
+    UnableToReadMetadata(PathBuf, io::Error),
+    UnableToReadFile(PathBuf, io::Error),
+    UnableToReadLink(PathBuf, io::Error),
+    FileChangedWhileReading(PathBuf),
+    UnableToWrite(io::Error),
+}
+
+impl Display for NarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NarError::UnableToReadMetadata(path, err) => {
+                write!(f, "Unable to read metadata: {:?}, error: {}", path, err)
+            }
+            NarError::UnableToReadFile(path, err) => {
+                write!(f, "Unable to read file: {:?}, error: {}", path, err)
+            }
+            NarError::UnableToReadLink(path, err) => {
+                write!(f, "Unable to read symbolic link: {:?}, error: {}", path, err)
+            }
+            NarError::FileChangedWhileReading(path) => {
+                write!(f, "File changed size while being read: {:?}", path)
+            }
+            NarError::UnableToWrite(err) => {
+                write!(f, "Unable to write the archive, error: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NarError {}
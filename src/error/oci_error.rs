@@ -0,0 +1,29 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// Types of errors that can occur when writing an OCI image layer from a tree.
+#[derive(Debug)]
+pub enum OciError {
+    UnableToReadFile(PathBuf, io::Error),
+    FileChanged(PathBuf),
+    UnableToWrite(io::Error),
+}
+
+impl Display for OciError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OciError::UnableToReadFile(path, err) => {
+                write!(f, "Unable to read file: {:?}, error: {}", path, err)
+            }
+            OciError::FileChanged(path) => {
+                write!(f, "File no longer matches its hash in the tree: {:?}", path)
+            }
+            OciError::UnableToWrite(err) => {
+                write!(f, "Unable to write the layer, error: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OciError {}
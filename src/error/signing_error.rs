@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter};
+
+use crate::error::{AlgorithmError, ChunkParamsError, DigestError, IndexingError};
+#[cfg(feature = "bincode")]
+use crate::error::SnapshotError;
+
+/// Types of errors that can occur when decoding or verifying a signed root.
+#[derive(Debug)]
+pub enum SigningError {
+    InvalidMagic,
+    Truncated,
+    TrailingBytes(usize),
+    InvalidFlag(u8),
+    InvalidUtf8,
+    UnsupportedAlgorithm(String),
+    InvalidDigest(DigestError),
+    InvalidOutputLength(AlgorithmError),
+    InvalidChunking(ChunkParamsError),
+    InvalidSignature,
+    AlgorithmMismatch,
+    OptionsMismatch,
+    RootMismatch,
+    KeyRequired,
+    Indexing(IndexingError),
+    #[cfg(feature = "bincode")]
+    Snapshot(SnapshotError),
+}
+
+impl Display for SigningError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SigningError::InvalidMagic => {
+                write!(f, "Not a signed root, the magic bytes do not match")
+            }
+            SigningError::Truncated => {
+                write!(f, "Signed root ends before all of its fields")
+            }
+            SigningError::TrailingBytes(len) => {
+                write!(f, "Signed root is followed by {} unexpected bytes", len)
+            }
+            SigningError::InvalidFlag(flag) => {
+                write!(f, "Signed root flags must be 0 or 1, found: {}", flag)
+            }
+            SigningError::InvalidUtf8 => {
+                write!(f, "Signed root strings must be valid UTF-8")
+            }
+            SigningError::UnsupportedAlgorithm(name) => {
+                write!(f, "Signed root uses an algorithm that is not enabled: {}", name)
+            }
+            SigningError::InvalidDigest(error) => {
+                write!(f, "Signed root hash is invalid: {}", error)
+            }
+            SigningError::InvalidOutputLength(error) => {
+                write!(f, "Signed output length is invalid: {}", error)
+            }
+            SigningError::InvalidChunking(error) => {
+                write!(f, "Signed chunk sizes are invalid: {}", error)
+            }
+            SigningError::InvalidSignature => {
+                write!(f, "Signature does not match the signed root and verifying key")
+            }
+            SigningError::AlgorithmMismatch => {
+                write!(f, "Tree was not built with the signed algorithm")
+            }
+            SigningError::OptionsMismatch => {
+                write!(f, "Tree was not built with the signed options")
+            }
+            SigningError::RootMismatch => {
+                write!(f, "Root hash does not match the signed root hash")
+            }
+            SigningError::KeyRequired => {
+                write!(f, "Signed root uses a keyed algorithm, a key is required to rebuild it")
+            }
+            SigningError::Indexing(error) => {
+                write!(f, "Unable to index the directory: {}", error)
+            }
+            #[cfg(feature = "bincode")]
+            SigningError::Snapshot(error) => {
+                write!(f, "Unable to load the snapshot: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SigningError {}
+
+impl From<IndexingError> for SigningError {
+    fn from(error: IndexingError) -> Self {
+        SigningError::Indexing(error)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl From<SnapshotError> for SigningError {
+    fn from(error: SnapshotError) -> Self {
+        SigningError::Snapshot(error)
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::error::AlgorithmError;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+use crate::utils::chunking::ChunkParams;
+
+/// Types of errors that can occur when saving, loading or comparing snapshots.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Encode(bincode::error::EncodeError),
+    Decode(bincode::error::DecodeError),
+    InvalidMagic([u8; 8]),
+    UnsupportedFormatVersion(u16),
+    UnsupportedHashScheme(u16),
+    UnsupportedAlgorithm(u8),
+    InvalidAlgorithm(AlgorithmError),
+    MissingAlgorithmParameters(AlgorithmKind),
+    KeyRequired,
+    KeyMismatch,
+    AlgorithmMismatch(Algorithm, Algorithm),
+    ChunkingMismatch(Option<ChunkParams>, Option<ChunkParams>),
+    HashNamesMismatch(bool, bool),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(error) => {
+                write!(f, "Unable to read or write snapshot, error: {}", error)
+            }
+            SnapshotError::Encode(error) => {
+                write!(f, "Unable to encode snapshot, error: {}", error)
+            }
+            SnapshotError::Decode(error) => {
+                write!(f, "Unable to decode snapshot, error: {}", error)
+            }
+            SnapshotError::InvalidMagic(magic) => {
+                write!(f, "Not a merkle_hash snapshot, found magic bytes: {:?}", magic)
+            }
+            SnapshotError::UnsupportedFormatVersion(version) => {
+                write!(f, "Unsupported snapshot format version: {}", version)
+            }
+            SnapshotError::UnsupportedHashScheme(scheme) => {
+                write!(f, "Snapshot was hashed with an incompatible scheme version: {}", scheme)
+            }
+            SnapshotError::UnsupportedAlgorithm(id) => {
+                write!(f, "Snapshot uses an unknown or disabled hashing algorithm with id: {}", id)
+            }
+            SnapshotError::InvalidAlgorithm(error) => {
+                write!(f, "Snapshot uses an invalid algorithm configuration, error: {}", error)
+            }
+            SnapshotError::MissingAlgorithmParameters(kind) => {
+                write!(f, "Snapshot is missing the parameters of its hashing algorithm: {}", kind)
+            }
+            SnapshotError::KeyRequired => {
+                write!(f, "Snapshot was built with a keyed algorithm, a key is required to load it")
+            }
+            SnapshotError::KeyMismatch => {
+                write!(f, "Snapshot was built with a different key")
+            }
+            SnapshotError::AlgorithmMismatch(expected, found) => {
+                write!(f, "Algorithm mismatch, expected: {}, found: {}", expected, found)
+            }
+            SnapshotError::ChunkingMismatch(expected, found) => {
+                let describe = |chunking: &Option<ChunkParams>| match chunking {
+                    Some(chunking) => chunking.to_string(),
+                    None => "none".to_string(),
+                };
+                write!(f, "Chunking mismatch, expected: {}, found: {}", describe(expected), describe(found))
+            }
+            SnapshotError::HashNamesMismatch(expected, found) => {
+                write!(f, "Hash names mismatch, expected: {}, found: {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+impl From<bincode::error::EncodeError> for SnapshotError {
+    fn from(error: bincode::error::EncodeError) -> Self {
+        SnapshotError::Encode(error)
+    }
+}
+
+impl From<bincode::error::DecodeError> for SnapshotError {
+    fn from(error: bincode::error::DecodeError) -> Self {
+        SnapshotError::Decode(error)
+    }
+}
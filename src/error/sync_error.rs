@@ -0,0 +1,53 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// Types of errors that can occur during a sync session.
+#[derive(Debug)]
+pub enum SyncError {
+    Io(io::Error),
+    InvalidMagic,
+    Malformed,
+    FrameTooLarge(u32),
+    FrameTooLargeToSend(usize),
+    UnexpectedFrame,
+    UnsupportedAlgorithm(String),
+    SettingsMismatch,
+    NonUtf8Path(PathBuf),
+}
+
+impl Display for SyncError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncError::Io(error) => {
+                write!(f, "IO error: {}", error)
+            }
+            SyncError::InvalidMagic => {
+                write!(f, "Peer does not speak the sync protocol, the magic bytes do not match")
+            }
+            SyncError::Malformed => {
+                write!(f, "Peer sent a malformed frame")
+            }
+            SyncError::FrameTooLarge(len) => {
+                write!(f, "Peer sent a frame of {} bytes, above the limit", len)
+            }
+            SyncError::FrameTooLargeToSend(len) => {
+                write!(f, "Frame of {} bytes is above the limit the peer accepts", len)
+            }
+            SyncError::UnexpectedFrame => {
+                write!(f, "Peer sent a frame out of order")
+            }
+            SyncError::UnsupportedAlgorithm(name) => {
+                write!(f, "Peer uses an algorithm that is not enabled: {}", name)
+            }
+            SyncError::SettingsMismatch => {
+                write!(f, "Peer tree was built with different settings, its hashes are not comparable")
+            }
+            SyncError::NonUtf8Path(path) => {
+                write!(f, "Synced paths must be valid UTF-8, found: {:?}", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
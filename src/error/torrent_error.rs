@@ -0,0 +1,31 @@
+use std::fmt::{Display, Formatter};
+
+/// Types of errors that can occur when computing BitTorrent v2 hashes or exporting a torrent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TorrentError {
+    InvalidPieceLength(u64),
+    MissingPieces,
+    MissingName,
+    NonUtf8Name(String),
+}
+
+impl Display for TorrentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TorrentError::InvalidPieceLength(len) => {
+                write!(f, "Piece length of {} bytes is not a power of two of at least 16 KiB", len)
+            }
+            TorrentError::MissingPieces => {
+                write!(f, "Tree was not built with BitTorrent pieces")
+            }
+            TorrentError::MissingName => {
+                write!(f, "Root of the tree has no name to give the torrent")
+            }
+            TorrentError::NonUtf8Name(name) => {
+                write!(f, "Torrent names must be valid UTF-8, found: {}", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TorrentError {}
@@ -0,0 +1,68 @@
+use std::iter::FusedIterator;
+
+use crate::components::compact_item::CompactItem;
+use crate::tree::compact_tree::CompactTree;
+
+/// Compact tree iterator, yields items in pre-order
+pub struct CompactTreeIter<'a> {
+    tree: &'a CompactTree,
+    next: u32,
+}
+
+impl<'a> CompactTreeIter<'a> {
+    pub fn new(tree: &'a CompactTree) -> Self {
+        Self { tree, next: 0 }
+    }
+}
+
+impl<'a> Iterator for CompactTreeIter<'a> {
+    type Item = CompactItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.tree.get(self.next as usize)?;
+        self.next += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.tree.len() - (self.next as usize).min(self.tree.len());
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CompactTreeIter<'_> {}
+
+impl FusedIterator for CompactTreeIter<'_> {}
+
+/// Iterator over the direct children of a compact tree item
+pub struct CompactChildren<'a> {
+    tree: &'a CompactTree,
+    next: u32,
+    end: u32,
+}
+
+impl<'a> CompactChildren<'a> {
+    pub(crate) fn new(tree: &'a CompactTree, parent: u32) -> Self {
+        let descendants = tree.nodes[parent as usize].descendants;
+        Self {
+            tree,
+            next: parent + 1,
+            end: parent + 1 + descendants,
+        }
+    }
+}
+
+impl<'a> Iterator for CompactChildren<'a> {
+    type Item = CompactItem<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+        let item = self.tree.item(self.next);
+        self.next += 1 + self.tree.nodes[self.next as usize].descendants;
+        Some(item)
+    }
+}
+
+impl FusedIterator for CompactChildren<'_> {}
@@ -0,0 +1,37 @@
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Node iterator, yields items in breadth-first order, level by level
+pub struct MerkleNodeBfsIter<'a> {
+    queue: VecDeque<&'a MerkleNode>,
+}
+
+impl<'a> MerkleNodeBfsIter<'a> {
+    pub fn new(node: &'a MerkleNode) -> Self {
+        Self {
+            queue: VecDeque::from([node]),
+        }
+    }
+}
+
+impl<'a> Iterator for MerkleNodeBfsIter<'a> {
+    type Item = &'a MerkleItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(&node.children);
+        Some(&node.item)
+    }
+}
+
+impl MerkleNode {
+    /// Returns an iterator over each file and directory descendant of the current node, level by level
+    pub fn iter_bfs(&self) -> MerkleNodeBfsIter<'_> {
+        MerkleNodeBfsIter::new(self)
+    }
+}
+
+impl FusedIterator for MerkleNodeBfsIter<'_> {}
@@ -0,0 +1,53 @@
+use std::collections::btree_set::Iter;
+use std::iter::FusedIterator;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Node iterator, yields items in pre-order with their depth below the starting node
+pub struct MerkleNodeDepthIter<'a> {
+    value: Option<&'a MerkleItem>,
+    stack: Vec<Iter<'a, MerkleNode>>,
+}
+
+impl<'a> MerkleNodeDepthIter<'a> {
+    pub fn new(node: &'a MerkleNode) -> Self {
+        Self {
+            value: Some(&node.item),
+            stack: vec![node.children.iter()],
+        }
+    }
+}
+
+impl<'a> Iterator for MerkleNodeDepthIter<'a> {
+    type Item = (usize, &'a MerkleItem);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.value.take() {
+            return Some((0, value));
+        }
+
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(child) => {
+                    let depth = self.stack.len();
+                    self.stack.push(child.children.iter());
+                    return Some((depth, &child.item));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl MerkleNode {
+    /// Returns an iterator over each file and directory descendant of the current node with its depth,
+    /// the current node being at depth **0**
+    pub fn iter_with_depth(&self) -> MerkleNodeDepthIter<'_> {
+        MerkleNodeDepthIter::new(self)
+    }
+}
+
+impl FusedIterator for MerkleNodeDepthIter<'_> {}
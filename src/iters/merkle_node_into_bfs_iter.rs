@@ -0,0 +1,37 @@
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Owned node iterator, yields items in breadth-first order, level by level
+pub struct MerkleNodeIntoBfsIter {
+    queue: VecDeque<MerkleNode>,
+}
+
+impl MerkleNodeIntoBfsIter {
+    pub fn new(node: MerkleNode) -> Self {
+        Self {
+            queue: VecDeque::from([node]),
+        }
+    }
+}
+
+impl Iterator for MerkleNodeIntoBfsIter {
+    type Item = MerkleItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.children);
+        Some(node.item)
+    }
+}
+
+impl MerkleNode {
+    /// Consumes the node into an iterator over each file and directory descendant, level by level
+    pub fn into_iter_bfs(self) -> MerkleNodeIntoBfsIter {
+        MerkleNodeIntoBfsIter::new(self)
+    }
+}
+
+impl FusedIterator for MerkleNodeIntoBfsIter {}
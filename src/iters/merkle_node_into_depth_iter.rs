@@ -0,0 +1,53 @@
+use std::collections::btree_set::IntoIter;
+use std::iter::FusedIterator;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Owned node iterator, yields items in pre-order with their depth below the starting node
+pub struct MerkleNodeIntoDepthIter {
+    value: Option<MerkleItem>,
+    stack: Vec<IntoIter<MerkleNode>>,
+}
+
+impl MerkleNodeIntoDepthIter {
+    pub fn new(node: MerkleNode) -> Self {
+        Self {
+            value: Some(node.item),
+            stack: vec![node.children.into_iter()],
+        }
+    }
+}
+
+impl Iterator for MerkleNodeIntoDepthIter {
+    type Item = (usize, MerkleItem);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.value.take() {
+            return Some((0, value));
+        }
+
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(child) => {
+                    let depth = self.stack.len();
+                    self.stack.push(child.children.into_iter());
+                    return Some((depth, child.item));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl MerkleNode {
+    /// Consumes the node into an iterator over each file and directory descendant with its depth,
+    /// the node itself being at depth **0**
+    pub fn into_iter_with_depth(self) -> MerkleNodeIntoDepthIter {
+        MerkleNodeIntoDepthIter::new(self)
+    }
+}
+
+impl FusedIterator for MerkleNodeIntoDepthIter {}
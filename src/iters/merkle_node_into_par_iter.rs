@@ -0,0 +1,39 @@
+use rayon::iter::plumbing::UnindexedConsumer;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Owned parallel node iterator, yields items in no particular order
+pub struct MerkleNodeIntoParIter {
+    node: MerkleNode,
+}
+
+impl MerkleNodeIntoParIter {
+    pub fn new(node: MerkleNode) -> Self {
+        Self { node }
+    }
+}
+
+impl ParallelIterator for MerkleNodeIntoParIter {
+    type Item = MerkleItem;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        rayon::iter::once(self.node.item)
+            .chain(self.node.children.into_par_iter().flat_map(MerkleNodeIntoParIter::new))
+            .drive_unindexed(consumer)
+    }
+}
+
+impl IntoParallelIterator for MerkleNode {
+    type Iter = MerkleNodeIntoParIter;
+
+    type Item = MerkleItem;
+
+    fn into_par_iter(self) -> Self::Iter {
+        MerkleNodeIntoParIter::new(self)
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::btree_set::IntoIter;
+use std::iter::FusedIterator;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Owned node iterator, yields items in post-order, children before their parents
+pub struct MerkleNodeIntoPostOrderIter {
+    stack: Vec<(MerkleItem, IntoIter<MerkleNode>)>,
+}
+
+impl MerkleNodeIntoPostOrderIter {
+    pub fn new(node: MerkleNode) -> Self {
+        Self {
+            stack: vec![(node.item, node.children.into_iter())],
+        }
+    }
+}
+
+impl Iterator for MerkleNodeIntoPostOrderIter {
+    type Item = MerkleItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (_, children) = self.stack.last_mut()?;
+            match children.next() {
+                Some(child) => self.stack.push((child.item, child.children.into_iter())),
+                None => return self.stack.pop().map(|(item, _)| item),
+            }
+        }
+    }
+}
+
+impl MerkleNode {
+    /// Consumes the node into an iterator over each file and directory descendant, children before their parents
+    pub fn into_iter_post_order(self) -> MerkleNodeIntoPostOrderIter {
+        MerkleNodeIntoPostOrderIter::new(self)
+    }
+}
+
+impl FusedIterator for MerkleNodeIntoPostOrderIter {}
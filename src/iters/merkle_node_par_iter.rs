@@ -0,0 +1,90 @@
+use rayon::iter::plumbing::UnindexedConsumer;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Parallel node iterator, yields items in no particular order
+///
+/// Directories are split into their children, so work is shared between threads without collecting the items first.
+pub struct MerkleNodeParIter<'a> {
+    node: &'a MerkleNode,
+}
+
+impl<'a> MerkleNodeParIter<'a> {
+    pub fn new(node: &'a MerkleNode) -> Self {
+        Self { node }
+    }
+}
+
+impl<'a> ParallelIterator for MerkleNodeParIter<'a> {
+    type Item = &'a MerkleItem;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        rayon::iter::once(&self.node.item)
+            .chain(self.node.children.par_iter().flat_map(MerkleNodeParIter::new))
+            .drive_unindexed(consumer)
+    }
+}
+
+impl MerkleNode {
+    /// Returns a parallel iterator over each file and directory descendant of the current node
+    pub fn par_iter(&self) -> MerkleNodeParIter<'_> {
+        MerkleNodeParIter::new(self)
+    }
+}
+
+impl<'a> IntoParallelIterator for &'a MerkleNode {
+    type Iter = MerkleNodeParIter<'a>;
+
+    type Item = &'a MerkleItem;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    use crate::components::merkle_item::MerkleItem;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path};
+
+    fn sorted(items: impl IntoIterator<Item = MerkleItem>) -> Vec<MerkleItem> {
+        let mut items: Vec<_> = items.into_iter().collect();
+        items.sort();
+        items
+    }
+
+    #[test]
+    fn yields_every_item_once() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d/e", b"three"), ("b/d/f", b"four"), ("g/", b"")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let expected: Vec<_> = tree.iter().cloned().collect();
+        assert_eq!(expected.len(), 8);
+
+        assert_eq!(sorted(tree.par_iter().cloned().collect::<Vec<_>>()), expected);
+        assert_eq!(sorted((&tree).into_par_iter().cloned().collect::<Vec<_>>()), expected);
+        assert_eq!(sorted((&tree.root).into_par_iter().cloned().collect::<Vec<_>>()), expected);
+        assert_eq!(sorted(tree.root.clone().into_par_iter().collect::<Vec<_>>()), expected);
+        assert_eq!(sorted(tree.into_par_iter().collect::<Vec<_>>()), expected);
+    }
+
+    #[test]
+    fn counts_items_inside_a_thread_pool() {
+        let names: Vec<_> = (0..64).map(|index| format!("dir{}/file{}", index % 8, index)).collect();
+        let entries: Vec<_> = names.iter().map(|name| (name.as_str(), b"contents".as_slice())).collect();
+        let dir = fixture(&entries);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let files = thread_pool.install(|| tree.par_iter().filter(|item| !item.is_dir).count());
+        assert_eq!(files, 64);
+        assert_eq!(thread_pool.install(|| tree.par_iter().count()), tree.iter().count());
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::btree_set::Iter;
+use std::iter::FusedIterator;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Node iterator, yields items in post-order, children before their parents
+pub struct MerkleNodePostOrderIter<'a> {
+    stack: Vec<(&'a MerkleItem, Iter<'a, MerkleNode>)>,
+}
+
+impl<'a> MerkleNodePostOrderIter<'a> {
+    pub fn new(node: &'a MerkleNode) -> Self {
+        Self {
+            stack: vec![(&node.item, node.children.iter())],
+        }
+    }
+}
+
+impl<'a> Iterator for MerkleNodePostOrderIter<'a> {
+    type Item = &'a MerkleItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (_, children) = self.stack.last_mut()?;
+            match children.next() {
+                Some(child) => self.stack.push((&child.item, child.children.iter())),
+                None => return self.stack.pop().map(|(item, _)| item),
+            }
+        }
+    }
+}
+
+impl MerkleNode {
+    /// Returns an iterator over each file and directory descendant of the current node, children before their parents
+    pub fn iter_post_order(&self) -> MerkleNodePostOrderIter<'_> {
+        MerkleNodePostOrderIter::new(self)
+    }
+}
+
+impl FusedIterator for MerkleNodePostOrderIter<'_> {}
@@ -0,0 +1,39 @@
+use crate::components::merkle_item::MerkleItem;
+use crate::tree::merkle_node::MerkleNode;
+
+/// Tells [`MerkleNode::walk`] how to go on after visiting an item
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WalkControl {
+    /// Visits the children of the item next
+    Continue,
+    /// Leaves out the children of the item and goes on with its siblings
+    SkipChildren,
+    /// Ends the walk
+    Stop,
+}
+
+impl MerkleNode {
+    /// Visits each file and directory descendant of the current node in pre-order with its depth,
+    /// the current node being at depth **0**, and lets the callback skip subtrees or stop
+    pub fn walk(&self, mut visit: impl FnMut(usize, &MerkleItem) -> WalkControl) {
+        let mut stack = match visit(0, &self.item) {
+            WalkControl::Continue => vec![self.children.iter()],
+            WalkControl::SkipChildren | WalkControl::Stop => return,
+        };
+
+        while let Some(children) = stack.last_mut() {
+            let child = match children.next() {
+                Some(child) => child,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+            match visit(stack.len(), &child.item) {
+                WalkControl::Continue => stack.push(child.children.iter()),
+                WalkControl::SkipChildren => {}
+                WalkControl::Stop => return,
+            }
+        }
+    }
+}
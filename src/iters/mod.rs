@@ -1,2 +1,14 @@
+pub mod compact_tree_iter;
+pub mod merkle_node_bfs_iter;
+pub mod merkle_node_depth_iter;
+pub mod merkle_node_into_bfs_iter;
+pub mod merkle_node_into_depth_iter;
 pub mod merkle_node_into_iter;
+#[cfg(feature = "parallel")]
+pub mod merkle_node_into_par_iter;
+pub mod merkle_node_into_post_order_iter;
 pub mod merkle_node_iter;
+#[cfg(feature = "parallel")]
+pub mod merkle_node_par_iter;
+pub mod merkle_node_post_order_iter;
+pub mod merkle_node_walk;
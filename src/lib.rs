@@ -7,16 +7,34 @@ To use this crate, add `merkle_hash` as a dependency to your project's `Cargo.to
 
 ```toml
 [dependencies]
-merkle_hash = "3.8"
+merkle_hash = "4.0"
 ```
 
 # Features
 
 * Finds the master hash of a directory tree with ease.
 * Offers multiple hashing algorithms.
+* Keyed and key derivation BLAKE3 modes, so root hashes cannot be precomputed by outsiders.
+* Variable length BLAKE3 output, from 16 up to 64 bytes.
+* Multiple hashing algorithms computed in a single traversal, reading each file once.
+* BLAKE3 outboards of files and verified slice proofs of arbitrary byte ranges.
+* Chunk level merkle trees inside files with FastCDC content-defined chunking.
+* IPFS UnixFS mode, producing the same CIDv1 as `ipfs add --cid-version=1 --raw-leaves`.
+* BitTorrent v2 pieces roots and piece layers of files, exportable as `.torrent` files.
+* Nix NAR hashes of directory trees, in their SRI and nix32 forms.
+* Go module `h1:` dirhashes, checking `go.sum` lines directly.
+* Reproducible OCI image layers of directory trees, with their diffIDs and descriptors.
+* Ed25519 signed root hashes with a canonical payload, verifiable offline against directories and snapshots.
+* in-toto v1 statements about the files or root of a tree, optionally wrapped in signed DSSE envelopes.
+* Deduplicated backups into a content-addressed store, restored with every blob verified.
+* Anti-entropy sync of two trees over any byte stream, exchanging hashes only for differing subtrees.
+* rsync-like application of a tree to a target directory, with dry runs, optional deletion and atomic file replacement.
+* Three-way merges of a base and two changed trees into a plan, skipping identical subtrees by hash.
 * Allows including names in the hashing process.
 * Uses a merkle tree algorithm to compute the hashes of directories.
-* External iteration over the paths and hashes of files and directories.
+* External iteration over the paths and hashes of files and directories, in pre-order with depths, post-order or breadth-first.
+* Compact, arena based tree representation for very large directory trees.
+* Verification of directories against stored trees, reporting missing, extra, modified and type-changed entries.
 
 # Limitations
 
@@ -24,12 +42,31 @@ merkle_hash = "3.8"
 
 # Optional
 
-* `sha` - Add this cargo feature to include `SHA-256` and `SHA-512` as hashing algorithms.
-* `parallel` - Enabled by default, this feature makes the crate utilize all available threads.
+* `sha` - Add this cargo feature to include `SHA-256`, `SHA-384` and `SHA-512` as hashing algorithms.
+* `sha3` - Add this cargo feature to include `SHA3-256` as a hashing algorithm.
+* `sha1` - Add this cargo feature to include `SHA-1` as a hashing algorithm, for legacy interoperability only.
+* `blake2` - Add this cargo feature to include `BLAKE2b` and `BLAKE2s` as hashing algorithms.
+* `xxhash` - Add this cargo feature to include `XXH3-128` as a non-cryptographic hashing algorithm.
+* `crc32c` - Add this cargo feature to include `CRC-32C` as a non-cryptographic hashing algorithm.
+* `bao` - Add this cargo feature to compute Bao outboards of files and produce and verify slice proofs from the root hash of the tree down to byte ranges.
+* `fastcdc` - Add this cargo feature to split files into content-defined chunks that become leaves below their files.
+* `unixfs` - Add this cargo feature to compute the IPFS UnixFS content identifiers of files and directories.
+* `bittorrent` - Add this cargo feature to compute BitTorrent v2 pieces roots and export `.torrent` files.
+* `nar` - Add this cargo feature to serialize trees into Nix archives and compute their NAR hashes.
+* `dirhash` - Add this cargo feature to compute Go module dirhashes and verify `go.sum` lines.
+* `oci` - Add this cargo feature to write reproducible OCI image layers and compute their diffIDs and descriptors.
+* `gzip` - Add this cargo feature to gzip compress OCI image layers, enables `oci`.
+* `signing` - Add this cargo feature to sign root hashes with Ed25519 keys and verify them.
+* `attestation` - Add this cargo feature to export in-toto statements, signed DSSE envelopes also need `signing`.
+* `cas` - Add this cargo feature to export trees into content-addressed stores and restore them, enables `bincode`.
+* `sync` - Add this cargo feature to compare trees with a peer over any `Read + Write` transport, with TCP and Unix socket helpers.
+* `apply` - Add this cargo feature to make a target directory match a tree, copying only changed files.
+* `parallel` - Enabled by default, this feature makes the crate utilize all available threads and adds rayon parallel iterators over trees.
 * `camino` - Enabled by default, this feature makes all paths UTF-8 validated.
 * `encode` - Enabled by default, this feature adds the `bytes_to_hex` and `to_hex_string` functions.
 * `retain` - Disabled by default, this feature duplicates the children paths of directories upon traversal.
-* `bincode` - Disabled by default, this feature enables bincode support.
+* `bincode` - Disabled by default, this feature enables bincode support and versioned tree snapshots.
+* `async` - Disabled by default, this feature adds `build_async` for building trees on a tokio runtime.
 
 # Example: Get the master hash of a directory tree:
 ```rust,no_run,ignore
@@ -66,21 +103,91 @@ let btree_set: BTreeSet<MerkleItem> = tree.into_iter().collect();
 pub use blake3;
 #[cfg(feature = "camino")]
 pub use camino;
+#[cfg(feature = "signing")]
+pub use ed25519_dalek;
 #[cfg(feature = "parallel")]
 pub use rayon;
-
+#[cfg(feature = "async")]
+pub use tokio;
+
+#[cfg(feature = "apply")]
+pub use components::apply_options::ApplyOptions;
+#[cfg(feature = "apply")]
+pub use components::apply_report::ApplyReport;
+#[cfg(feature = "fastcdc")]
+pub use components::chunk::Chunk;
+#[cfg(feature = "unixfs")]
+pub use components::cid::{Cid, CidCodec};
+pub use components::compact_item::CompactItem;
+pub use components::digest::{Digest, MAX_DIGEST_LEN};
+#[cfg(feature = "bao")]
+pub use components::inclusion_path::{InclusionPath, InclusionStep};
+#[cfg(all(feature = "attestation", feature = "signing"))]
+pub use components::dsse_envelope::{DsseEnvelope, DsseSignature, IN_TOTO_PAYLOAD_TYPE};
+pub use components::merge_plan::{ConflictKind, MergeAction, MergeEntry, MergePlan};
 pub use components::merkle_item::MerkleItem;
 pub use components::merkle_path::MerklePath;
+#[cfg(feature = "nar")]
+pub use components::nar_hash::NarHash;
+#[cfg(feature = "gzip")]
+pub use components::oci_layer::OCI_LAYER_GZIP_MEDIA_TYPE;
+#[cfg(feature = "oci")]
+pub use components::oci_layer::{LayerCompression, OciDescriptor, OciLayer, OCI_LAYER_MEDIA_TYPE};
+#[cfg(feature = "bao")]
+pub use components::outboard::Outboard;
+#[cfg(feature = "signing")]
+pub use components::signed_root::{RootOptions, SignedRoot, SIGNED_ROOT_MAGIC};
+#[cfg(feature = "bao")]
+pub use components::slice_proof::SliceProof;
+#[cfg(feature = "attestation")]
+pub use components::statement::{Statement, Subject, SubjectMode, IN_TOTO_STATEMENT_TYPE};
+#[cfg(feature = "sync")]
+pub use components::sync_diff::SyncDiff;
+#[cfg(feature = "bittorrent")]
+pub use components::torrent_file::TorrentFile;
+#[cfg(feature = "unixfs")]
+pub use components::unixfs_entry::UnixFsEntry;
+pub use components::verification_report::VerificationReport;
+pub use iters::compact_tree_iter::{CompactChildren, CompactTreeIter};
+pub use iters::merkle_node_bfs_iter::MerkleNodeBfsIter;
+pub use iters::merkle_node_depth_iter::MerkleNodeDepthIter;
+pub use iters::merkle_node_into_bfs_iter::MerkleNodeIntoBfsIter;
+pub use iters::merkle_node_into_depth_iter::MerkleNodeIntoDepthIter;
 pub use iters::merkle_node_into_iter::MerkleNodeIntoIter;
+#[cfg(feature = "parallel")]
+pub use iters::merkle_node_into_par_iter::MerkleNodeIntoParIter;
+pub use iters::merkle_node_into_post_order_iter::MerkleNodeIntoPostOrderIter;
 pub use iters::merkle_node_iter::MerkleNodeIter;
+#[cfg(feature = "parallel")]
+pub use iters::merkle_node_par_iter::MerkleNodeParIter;
+pub use iters::merkle_node_post_order_iter::MerkleNodePostOrderIter;
+pub use iters::merkle_node_walk::WalkControl;
+#[cfg(feature = "apply")]
+pub use tree::apply::sync_dirs;
+pub use tree::compact_tree::CompactTree;
 pub use tree::merkle_node::MerkleNode;
 pub use tree::merkle_tree::MerkleTree;
 pub use tree::merkle_tree_builder::MerkleTreeBuilder;
-pub use utils::algorithm::Algorithm;
+#[cfg(feature = "bincode")]
+pub use tree::snapshot::{
+    Snapshot, SnapshotAlgorithm, SnapshotHeader, HASH_SCHEME_VERSION, SNAPSHOT_FORMAT_VERSION, SNAPSHOT_MAGIC,
+};
+#[cfg(feature = "bittorrent")]
+pub use tree::torrent::{FileTreeNode, Torrent};
+pub use utils::algorithm::{Algorithm, AlgorithmKind, Blake3Key, Blake3Mode, OutputLength, MIN_OUTPUT_LEN};
+#[cfg(feature = "bittorrent")]
+pub use utils::bittorrent::{PieceLength, TORRENT_BLOCK_SIZE};
+pub use utils::chunking::{ChunkParams, AVG_CHUNK_SIZE_RANGE, MAX_CHUNK_SIZE_RANGE, MIN_CHUNK_SIZE_RANGE};
 #[cfg(feature = "encode")]
 pub use utils::hex_encoding::bytes_to_hex;
 #[cfg(feature = "encode")]
 pub use utils::hex_encoding::Encodable;
+#[cfg(feature = "nar")]
+pub use utils::nar::NAR_MAGIC;
+#[cfg(feature = "sync")]
+pub use utils::sync_wire::{MAX_FRAME_LEN, SYNC_MAGIC};
+#[cfg(feature = "unixfs")]
+pub use utils::unixfs::{UNIXFS_CHUNK_SIZE, UNIXFS_MAX_LINKS};
 
 mod components;
 mod iters;
@@ -0,0 +1,293 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::components::apply_options::ApplyOptions;
+use crate::components::apply_report::ApplyReport;
+use crate::components::merkle_path::MerklePath;
+use crate::error::ApplyError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+
+/// Makes a target directory match a source tree, copying only the files whose hashes differ
+///
+/// Missing directories are created, changed and missing files are copied after checking they still match
+/// their hashes in the source tree, and entries that are not in the source are deleted if enabled.
+/// Every file is written under a temporary name next to its destination and renamed into place,
+/// so readers never see a partially written file. Once done, the target is verified against the tree.
+///
+/// Target entries are matched by name and kind before their hashes are compared, so renamed entries are
+/// replaced even when the tree does not hash names, and an empty file never stands in for an empty directory.
+/// A target directory standing where the source has a file is only deleted if deleting is enabled,
+/// otherwise nothing is changed and an error is returned.
+///
+/// Links in the target are never written through. A link standing where the source has a directory is
+/// removed and replaced by a directory, and no file is ever created below a link.
+pub fn sync_dirs(
+    source_tree: &MerkleTree,
+    target_dir: impl AsRef<str>,
+    options: ApplyOptions,
+) -> Result<ApplyReport, ApplyError> {
+    let target_dir = target_dir.as_ref();
+    let source_root: &Path = source_tree.root.item.path.absolute.as_ref();
+    if !source_tree.root.item.is_dir {
+        return Err(ApplyError::NotADirectory(source_root.to_path_buf()));
+    }
+
+    let mut report = ApplyReport::default();
+    let target_root = Path::new(target_dir);
+    if !target_root.exists() {
+        let target = MerklePath::new(source_tree.root.item.path.relative.clone(), target_dir.into());
+        source_tree.apply_node(&source_tree.root, target, options, &mut report)?;
+    } else if !target_root.is_dir() {
+        return Err(ApplyError::NotADirectory(target_root.to_path_buf()));
+    } else {
+        let differences = source_tree.verify_dir(target_dir)?;
+
+        // Refuses up front, so the target is left as it was
+        if !options.delete {
+            for target in &differences.type_changed {
+                let path: &Path = target.absolute.as_ref();
+                if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir()) {
+                    return Err(ApplyError::DirectoryInTheWay(path.to_path_buf()));
+                }
+            }
+        }
+
+        for target in differences.type_changed {
+            let node = source_tree.apply_source(&target);
+            apply_removal(&target, options, &mut report)?;
+            source_tree.apply_node(node, target, options, &mut report)?;
+        }
+        if options.delete {
+            for target in differences.extra {
+                apply_removal(&target, options, &mut report)?;
+            }
+        }
+        for target in differences.missing.into_iter().chain(differences.modified) {
+            let node = source_tree.apply_source(&target);
+            source_tree.apply_node(node, target, options, &mut report)?;
+        }
+    }
+
+    if !options.dry_run {
+        let mut remaining = source_tree.verify_dir(target_dir)?;
+        if !options.delete {
+            remaining.extra.clear();
+        }
+        if !remaining.is_clean() {
+            return Err(ApplyError::VerificationFailed(remaining));
+        }
+    }
+    Ok(report)
+}
+
+/// Removes a file or a whole directory from the target
+fn apply_removal(target: &MerklePath, options: ApplyOptions, report: &mut ApplyReport) -> Result<(), ApplyError> {
+    if !options.dry_run {
+        let path: &Path = target.absolute.as_ref();
+        let removed = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+            Ok(_) => fs::remove_file(path),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = removed {
+            return Err(ApplyError::UnableToRemove(path.to_path_buf(), err));
+        }
+    }
+    report.deleted.push(target.clone());
+    Ok(())
+}
+
+/// Checks that no directory between the target root and a target entry is a link
+fn apply_check_links(target: &MerklePath) -> Result<(), ApplyError> {
+    let absolute: &Path = target.absolute.as_ref();
+    let relative: &Path = target.relative.as_ref();
+    let depth = relative.components().count();
+    for path in absolute.ancestors().skip(1).take(depth.saturating_sub(1)) {
+        if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink()) {
+            return Err(ApplyError::LinkInPath(path.to_path_buf()));
+        }
+    }
+    Ok(())
+}
+
+impl MerkleTree {
+    /// Finds the source node at the relative path of a target entry
+    fn apply_source(&self, target: &MerklePath) -> &MerkleNode {
+        let relative: &Path = target.relative.as_ref();
+        let mut node = &self.root;
+        while let Some(child) = node.children.iter().find(|child| relative.starts_with(&child.item.path.relative)) {
+            node = child;
+        }
+        node
+    }
+
+    /// Copies a source node and its descendants to a target path
+    fn apply_node(
+        &self,
+        node: &MerkleNode,
+        target: MerklePath,
+        options: ApplyOptions,
+        report: &mut ApplyReport,
+    ) -> Result<(), ApplyError> {
+        let source: &Path = node.item.path.absolute.as_ref();
+        if !options.dry_run {
+            apply_check_links(&target)?;
+        }
+        if !node.item.is_dir {
+            if !options.dry_run {
+                self.apply_file(node, source, target.absolute.as_ref())?;
+            }
+            report.copied.push(target);
+            return Ok(());
+        }
+
+        if !options.dry_run {
+            let path: &Path = target.absolute.as_ref();
+            if let Err(err) = fs::create_dir_all(path) {
+                return Err(ApplyError::UnableToWriteFile(path.to_path_buf(), err));
+            }
+        }
+        for child in &node.children {
+            let relative = child.item.path.relative.clone();
+            let absolute = target.absolute.join(relative.file_name().unwrap_or_default());
+            self.apply_node(child, MerklePath::new(relative, absolute), options, report)?;
+        }
+        report.created_dirs.push(target);
+        Ok(())
+    }
+
+    /// Replaces a target file with the contents of a source file through a temporary file and a rename
+    fn apply_file(&self, node: &MerkleNode, source: &Path, target: &Path) -> Result<(), ApplyError> {
+        let contents = match fs::read(source) {
+            Ok(contents) => contents,
+            Err(err) => return Err(ApplyError::UnableToReadFile(source.to_path_buf(), err)),
+        };
+        if self.file_item_hash(&node.item, &contents) != node.item.hash {
+            return Err(ApplyError::FileChanged(source.to_path_buf()));
+        }
+
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        let partial = target.with_file_name(format!(".{}.partial", name));
+        let written = fs::File::create(&partial)
+            .and_then(|mut file| {
+                file.write_all(&contents)?;
+                file.set_permissions(fs::metadata(source)?.permissions())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&partial, target));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&partial);
+            return Err(ApplyError::UnableToWriteFile(target.to_path_buf(), err));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::components::apply_options::ApplyOptions;
+    use crate::error::ApplyError;
+    use crate::tree::apply::sync_dirs;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path};
+
+    fn source(entries: &[(&str, &[u8])]) -> (tempfile::TempDir, MerkleTree) {
+        let dir = fixture(entries);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        (dir, tree)
+    }
+
+    #[test]
+    fn copies_changes_and_deletes_extras() {
+        let (_source_dir, tree) = source(&[("a", b"1"), ("sub/b", b"2"), ("sub/c", b"3")]);
+        let target = fixture(&[("a", b"old"), ("sub/b", b"2"), ("extra", b"x")]);
+
+        let report = sync_dirs(&tree, path(&target), ApplyOptions::default()).unwrap();
+        assert_eq!(report.copied.len(), 2);
+        assert!(report.deleted.is_empty());
+        assert!(target.path().join("extra").exists());
+
+        let report = sync_dirs(&tree, path(&target), ApplyOptions::default().delete(true)).unwrap();
+        assert_eq!(report.deleted.len(), 1);
+        assert!(!target.path().join("extra").exists());
+        assert!(tree.verify_dir(path(&target)).unwrap().is_clean());
+    }
+
+    #[test]
+    fn replaces_renamed_entries_without_hashed_names() {
+        let (_source_dir, tree) = source(&[("dir/x", b"same"), ("y", b"y"), ("z/a", b"q")]);
+        let target = fixture(&[("renamed/x", b"same"), ("y", b"y"), ("z/b", b"q")]);
+        assert!(!tree.hash_names);
+
+        sync_dirs(&tree, path(&target), ApplyOptions::default().delete(true)).unwrap();
+        assert_eq!(fs::read(target.path().join("dir/x")).unwrap(), b"same");
+        assert_eq!(fs::read(target.path().join("z/a")).unwrap(), b"q");
+        assert!(!target.path().join("renamed").exists() && !target.path().join("z/b").exists());
+    }
+
+    #[test]
+    fn replaces_empty_files_standing_in_for_empty_directories() {
+        let (_source_dir, tree) = source(&[("empty/", b""), ("blank", b"")]);
+        let target = fixture(&[("empty", b""), ("blank/", b"")]);
+
+        let report = sync_dirs(&tree, path(&target), ApplyOptions::default().delete(true)).unwrap();
+        assert_eq!(report.deleted.len(), 2);
+        assert!(target.path().join("empty").is_dir());
+        assert!(target.path().join("blank").is_file());
+    }
+
+    #[test]
+    fn dry_runs_leave_the_target_untouched() {
+        let (_source_dir, tree) = source(&[("a", b"1"), ("new/b", b"2")]);
+        let target = fixture(&[("a", b"old"), ("extra", b"x")]);
+
+        let options = ApplyOptions::default().delete(true).dry_run(true);
+        let report = sync_dirs(&tree, path(&target), options).unwrap();
+        assert_eq!(report.copied.len(), 2);
+        assert_eq!(report.created_dirs.len(), 1);
+        assert_eq!(report.deleted.len(), 1);
+        assert_eq!(fs::read(target.path().join("a")).unwrap(), b"old");
+        assert!(target.path().join("extra").exists() && !target.path().join("new").exists());
+
+        let missing = target.path().join("fresh");
+        sync_dirs(&tree, missing.to_str().unwrap(), ApplyOptions::default()).unwrap();
+        assert!(tree.verify_dir(missing.to_str().unwrap()).unwrap().is_clean());
+    }
+
+    #[test]
+    fn keeps_directories_standing_in_for_files_unless_deleting() {
+        let (_source_dir, tree) = source(&[("keep", b"file"), ("a", b"new")]);
+        let target = fixture(&[("keep/precious", b"mine"), ("a", b"old")]);
+
+        let applied = sync_dirs(&tree, path(&target), ApplyOptions::default());
+        assert!(matches!(applied, Err(ApplyError::DirectoryInTheWay(_))));
+        assert_eq!(fs::read(target.path().join("keep/precious")).unwrap(), b"mine");
+        assert_eq!(fs::read(target.path().join("a")).unwrap(), b"old");
+
+        sync_dirs(&tree, path(&target), ApplyOptions::default().delete(true)).unwrap();
+        assert_eq!(fs::read(target.path().join("keep")).unwrap(), b"file");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn never_writes_through_links() {
+        use std::os::unix::fs::symlink;
+
+        let (_source_dir, tree) = source(&[("sub/file", b"file"), ("linked", b"new")]);
+        let elsewhere = fixture(&[("other", b"other"), ("outside", b"outside")]);
+        let target = fixture(&[]);
+        symlink(elsewhere.path(), target.path().join("sub")).unwrap();
+        symlink(elsewhere.path().join("outside"), target.path().join("linked")).unwrap();
+
+        sync_dirs(&tree, path(&target), ApplyOptions::default()).unwrap();
+        assert!(!elsewhere.path().join("file").exists());
+        assert_eq!(fs::read(elsewhere.path().join("outside")).unwrap(), b"outside");
+        assert!(!fs::symlink_metadata(target.path().join("sub")).unwrap().is_symlink());
+        assert!(!fs::symlink_metadata(target.path().join("linked")).unwrap().is_symlink());
+        assert!(tree.verify_dir(path(&target)).unwrap().is_clean());
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use crate::components::statement::{Statement, Subject, SubjectMode};
+use crate::error::AttestationError;
+use crate::tree::merkle_tree::MerkleTree;
+
+impl MerkleTree {
+    /// Creates an in-toto v1 statement with the given predicate type about the files or the root of the tree
+    ///
+    /// Digests hold the hashes of every algorithm the tree was built with. File subjects need plain
+    /// contents hashes, so trees built with hashed names or chunking can only be attested by their root.
+    /// The root subject is digested under `merkle-<algorithm>` keys so its hashes are never taken for contents hashes.
+    pub fn in_toto_statement(
+        &self,
+        mode: SubjectMode,
+        predicate_type: impl Into<String>,
+    ) -> Result<Statement, AttestationError> {
+        let subjects = match mode {
+            SubjectMode::Files => self.file_subjects()?,
+            SubjectMode::Root => {
+                let absolute: &Path = self.root.item.path.absolute.as_ref();
+                let name = match absolute.file_name() {
+                    Some(name) => Self::subject_name(Path::new(name))?,
+                    None => return Err(AttestationError::MissingName),
+                };
+                vec![Subject::merkle_root(name, self.root.item.hashes())]
+            }
+        };
+
+        Ok(Statement {
+            subjects,
+            predicate_type: predicate_type.into(),
+            predicate: None,
+        })
+    }
+
+    fn file_subjects(&self) -> Result<Vec<Subject>, AttestationError> {
+        if self.hash_names {
+            return Err(AttestationError::HashesIncludeNames);
+        }
+        if self.chunking.is_some() {
+            return Err(AttestationError::HashesChunked);
+        }
+
+        let mut subjects = Vec::new();
+        for item in self.iter() {
+            if item.is_dir {
+                continue;
+            }
+            // A tree of a single file names it by itself
+            let relative: &Path = item.path.relative.as_ref();
+            let absolute: &Path = item.path.absolute.as_ref();
+            let name = match relative.as_os_str().is_empty() {
+                true => absolute.file_name().map(Path::new).unwrap_or(relative),
+                false => relative,
+            };
+            subjects.push(Subject::new(Self::subject_name(name)?, item.hashes()));
+        }
+        subjects.sort_by(|subject, other| subject.name.cmp(&other.name));
+        Ok(subjects)
+    }
+
+    /// Joins the components of a path with slashes
+    fn subject_name(path: &Path) -> Result<String, AttestationError> {
+        let mut components = Vec::new();
+        for component in path.components() {
+            match component.as_os_str().to_str() {
+                Some(component) => components.push(component),
+                None => return Err(AttestationError::NonUtf8Path(path.to_path_buf())),
+            }
+        }
+        Ok(components.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::statement::SubjectMode;
+    use crate::error::AttestationError;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::algorithm::Algorithm;
+    use crate::utils::fixture::{fixture, path};
+
+    const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+    #[test]
+    fn lists_files_by_relative_path() {
+        let dir = fixture(&[("b/c", b"two"), ("a", b"one"), ("empty/", b"")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let statement = tree.in_toto_statement(SubjectMode::Files, PREDICATE_TYPE).unwrap();
+
+        let names: Vec<_> = statement.subjects.iter().map(|subject| subject.name.as_str()).collect();
+        assert_eq!(names, ["a", "b/c"]);
+        let one = Algorithm::Blake3.compute_hash(b"one").to_hex();
+        assert_eq!(statement.subjects[0].digest["blake3"], one);
+
+        let json = statement.predicate("{}").to_json();
+        let expected = format!(
+            r#"{{"_type":"https://in-toto.io/Statement/v1","subject":[{{"name":"a","digest":{{"blake3":"{}"}}}},"#,
+            one
+        );
+        assert!(json.starts_with(&expected));
+        assert!(json.ends_with(r#""predicateType":"https://slsa.dev/provenance/v1","predicate":{}}"#));
+    }
+
+    #[cfg(all(feature = "sha", feature = "sha3"))]
+    #[test]
+    fn digests_hold_every_algorithm_under_in_toto_names() {
+        let dir = fixture(&[("hello", b"hello")]);
+        let tree = MerkleTree::builder(dir.path().join("hello").to_str().unwrap())
+            .algorithms([Algorithm::Blake3, Algorithm::Sha256, Algorithm::Sha3_256])
+            .build()
+            .unwrap();
+        let statement = tree.in_toto_statement(SubjectMode::Files, PREDICATE_TYPE).unwrap();
+
+        let subject = &statement.subjects[0];
+        assert_eq!(subject.name, "hello");
+        assert_eq!(subject.digest["sha256"], "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824");
+        assert_eq!(subject.digest["sha3_256"], "3338be694f50c5f338814986cdf0686453a888b84f424d792af4b9202398f392");
+        assert!(subject.digest.contains_key("blake3"));
+    }
+
+    #[test]
+    fn names_the_root_subject_after_the_directory() {
+        let dir = fixture(&[("project/a", b"one")]);
+        let root = dir.path().join("project");
+        let tree = MerkleTree::builder(root.to_str().unwrap()).build().unwrap();
+        let statement = tree.in_toto_statement(SubjectMode::Root, PREDICATE_TYPE).unwrap();
+
+        assert_eq!(statement.subjects.len(), 1);
+        assert_eq!(statement.subjects[0].name, "project");
+        assert_eq!(statement.subjects[0].digest["merkle-blake3"], tree.root.item.hash.to_hex());
+        assert!(!statement.subjects[0].digest.contains_key("blake3"));
+    }
+
+    #[test]
+    fn file_subjects_need_plain_contents_hashes() {
+        let dir = fixture(&[("a", b"one")]);
+        let tree = MerkleTree::builder(path(&dir)).hash_names(true).build().unwrap();
+
+        let files = tree.in_toto_statement(SubjectMode::Files, PREDICATE_TYPE);
+        assert_eq!(files.unwrap_err(), AttestationError::HashesIncludeNames);
+        assert!(tree.in_toto_statement(SubjectMode::Root, PREDICATE_TYPE).is_ok());
+    }
+}
@@ -0,0 +1,330 @@
+use std::fs;
+use std::io::{BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::components::digest::Digest;
+use crate::components::merkle_item::MerkleItem;
+use crate::error::{CasError, SnapshotError};
+use crate::tree::merkle_tree::MerkleTree;
+use crate::tree::snapshot::Snapshot;
+
+impl MerkleTree {
+    /// Exports the tree into a content-addressed store directory, deduplicating files by their contents hash
+    ///
+    /// Every unique file is copied into `<store>/<hex contents hash>` once, after checking it still matches
+    /// its hash. Contents hashes leave out names, so renamed copies share a blob even when names are hashed.
+    /// The tree is saved as `<store>/<hex root hash>.snapshot`, whose path is returned, next to a
+    /// `<hex root hash>.blobs` manifest listing the blob of every file in pre-order.
+    pub fn export_to_cas(&self, store_dir: impl AsRef<Path>) -> Result<PathBuf, CasError> {
+        let store_dir = store_dir.as_ref();
+        if let Err(err) = fs::create_dir_all(store_dir) {
+            return Err(CasError::UnableToWriteFile(store_dir.to_path_buf(), err));
+        }
+
+        let mut blobs = Vec::new();
+        for item in self.iter().filter(|item| !item.is_dir) {
+            let absolute: &Path = item.path.absolute.as_ref();
+
+            // Without hashed names the item hash is the contents hash, so stored blobs need not be read again
+            if !self.hash_names && store_dir.join(item.hash.to_hex()).exists() {
+                blobs.push(item.hash);
+                continue;
+            }
+
+            let contents = match fs::read(absolute) {
+                Ok(contents) => contents,
+                Err(err) => return Err(CasError::UnableToReadFile(absolute.to_path_buf(), err)),
+            };
+            let contents_hash = self.file_contents_hash(&contents);
+            if self.named_file_hash(&item.path, contents_hash) != item.hash {
+                return Err(CasError::FileChanged(absolute.to_path_buf()));
+            }
+            blobs.push(contents_hash);
+
+            let blob_path = store_dir.join(contents_hash.to_hex());
+            if !blob_path.exists() {
+                Self::write_atomically(&blob_path, &contents)?;
+            }
+        }
+
+        let root = self.root.item.hash.to_hex();
+        let snapshot_path = store_dir.join(format!("{}.snapshot", root));
+        let mut snapshot = Vec::new();
+        Snapshot::write(self, &mut snapshot)?;
+        Self::write_atomically(&snapshot_path, &snapshot)?;
+
+        let mut manifest = Vec::new();
+        bincode::encode_into_std_write(&blobs, &mut manifest, bincode::config::standard())
+            .map_err(SnapshotError::from)?;
+        Self::write_atomically(&Self::cas_manifest_path(&snapshot_path), &manifest)?;
+
+        Ok(snapshot_path)
+    }
+
+    /// Rebuilds a directory exported with [`MerkleTree::export_to_cas`] into the target path, returning its tree
+    ///
+    /// Every path and every blob is checked against the snapshot before anything is written, so a damaged store
+    /// or a hostile snapshot leaves the target untouched. Paths that could escape the target are refused, and
+    /// the target must be missing or an empty directory, so no link already in it is ever followed.
+    /// Every file is written under a temporary name first and then renamed into place.
+    /// A tree whose root is a file is restored to the target path itself.
+    /// Trees built with a keyed algorithm cannot be restored, as their key is not stored.
+    pub fn restore_from_cas(
+        snapshot: impl AsRef<Path>,
+        store_dir: impl AsRef<Path>,
+        target_dir: impl AsRef<Path>,
+    ) -> Result<MerkleTree, CasError> {
+        let (snapshot, store_dir, target_dir) = (snapshot.as_ref(), store_dir.as_ref(), target_dir.as_ref());
+
+        let tree = match fs::File::open(snapshot) {
+            Ok(file) => Snapshot::read(BufReader::new(file))?.tree,
+            Err(err) => return Err(CasError::UnableToReadFile(snapshot.to_path_buf(), err)),
+        };
+        let manifest_path = Self::cas_manifest_path(snapshot);
+        let blobs: Vec<Digest> = match fs::File::open(&manifest_path) {
+            Ok(file) => bincode::decode_from_std_read(&mut BufReader::new(file), bincode::config::standard())
+                .map_err(SnapshotError::from)?,
+            Err(err) => return Err(CasError::UnableToReadFile(manifest_path, err)),
+        };
+
+        for item in tree.iter() {
+            let relative: &Path = item.path.relative.as_ref();
+            if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+                return Err(CasError::UnsafePath(relative.to_path_buf()));
+            }
+        }
+        let files: Vec<_> = tree.iter().filter(|item| !item.is_dir).collect();
+        if files.len() != blobs.len() {
+            return Err(CasError::ManifestMismatch);
+        }
+        for (item, blob) in files.into_iter().zip(&blobs) {
+            tree.cas_read_blob(store_dir, item, blob)?;
+        }
+        match fs::metadata(target_dir) {
+            Ok(metadata) if metadata.is_dir() => match fs::read_dir(target_dir).map(|mut entries| entries.next()) {
+                Ok(None) => {}
+                Ok(Some(_)) => return Err(CasError::TargetNotEmpty(target_dir.to_path_buf())),
+                Err(err) => return Err(CasError::UnableToReadFile(target_dir.to_path_buf(), err)),
+            },
+            Ok(_) => return Err(CasError::TargetNotEmpty(target_dir.to_path_buf())),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(CasError::UnableToReadFile(target_dir.to_path_buf(), err)),
+        }
+
+        let mut blobs = blobs.iter();
+        for item in tree.iter() {
+            let relative: &Path = item.path.relative.as_ref();
+            let target = match relative.as_os_str().is_empty() {
+                true => target_dir.to_path_buf(),
+                false => target_dir.join(relative),
+            };
+
+            if item.is_dir {
+                if let Err(err) = fs::create_dir_all(&target) {
+                    return Err(CasError::UnableToWriteFile(target, err));
+                }
+                continue;
+            }
+
+            // Reads the blob again, as the store may have changed since it was checked
+            let blob = blobs.next().ok_or(CasError::ManifestMismatch)?;
+            let contents = tree.cas_read_blob(store_dir, item, blob)?;
+            if let Some(parent) = target.parent() {
+                if let Err(err) = fs::create_dir_all(parent) {
+                    return Err(CasError::UnableToWriteFile(parent.to_path_buf(), err));
+                }
+            }
+            Self::write_atomically(&target, &contents)?;
+        }
+
+        Ok(tree)
+    }
+
+    /// Reads the blob of a file from the store, checking it against its contents hash and the file's hash
+    fn cas_read_blob(&self, store_dir: &Path, item: &MerkleItem, blob: &Digest) -> Result<Vec<u8>, CasError> {
+        let blob = blob.to_hex();
+        let contents = match fs::read(store_dir.join(&blob)) {
+            Ok(contents) => contents,
+            Err(_) => return Err(CasError::MissingBlob(blob)),
+        };
+        if self.file_contents_hash(&contents).to_hex() != blob || self.file_path_hash(&item.path, &contents) != item.hash {
+            return Err(CasError::CorruptBlob(blob));
+        }
+        Ok(contents)
+    }
+
+    /// Returns the path of the blob manifest written next to a snapshot
+    fn cas_manifest_path(snapshot: &Path) -> PathBuf {
+        snapshot.with_extension("blobs")
+    }
+
+    /// Writes a file under a temporary name first, so an interrupted write never leaves a partial file
+    fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), CasError> {
+        let mut partial_path = path.as_os_str().to_owned();
+        partial_path.push(".partial");
+        let partial_path = PathBuf::from(partial_path);
+
+        let written = fs::File::create(&partial_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            writer.write_all(contents)?;
+            writer.into_inner().map_err(|err| err.into_error())?.sync_all()
+        });
+        if let Err(err) = written.and_then(|_| fs::rename(&partial_path, path)) {
+            let _ = fs::remove_file(&partial_path);
+            return Err(CasError::UnableToWriteFile(path.to_path_buf(), err));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::error::CasError;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path, write};
+
+    fn blob_count(store: &Path) -> usize {
+        let names = fs::read_dir(store).unwrap().map(|entry| entry.unwrap().file_name());
+        names.filter(|name| !name.to_string_lossy().contains('.')).count()
+    }
+
+    fn walk(dir: &Path) -> Vec<std::path::PathBuf> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                paths.extend(walk(&path));
+            }
+            paths.push(path);
+        }
+        paths
+    }
+
+    #[test]
+    fn round_trips_files_empty_files_and_empty_directories() {
+        for hash_names in [false, true] {
+            let dir = fixture(&[
+                ("a", b"same"),
+                ("sub/b", b"same"),
+                ("sub/c", b"other"),
+                ("blank", b""),
+                ("empty/", b""),
+            ]);
+            let tree = MerkleTree::builder(path(&dir)).hash_names(hash_names).build().unwrap();
+            let store = tempfile::tempdir().unwrap();
+            let snapshot = tree.export_to_cas(store.path()).unwrap();
+
+            // Contents hashes leave out names, so the two copies of `same` share a blob
+            assert_eq!(blob_count(store.path()), 3);
+            assert!(snapshot.with_extension("blobs").is_file());
+
+            let target = tempfile::tempdir().unwrap();
+            let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), target.path()).unwrap();
+            assert_eq!(restored.root.item.hash, tree.root.item.hash);
+            assert!(target.path().join("empty").is_dir());
+            assert!(target.path().join("blank").is_file());
+            assert!(tree.verify_dir(path(&target)).unwrap().is_clean());
+
+            let partial = |dir: &Path| walk(dir).iter().any(|path| path.to_string_lossy().ends_with(".partial"));
+            assert!(!partial(store.path()) && !partial(target.path()));
+        }
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_target() {
+        let dir = fixture(&[("a", b"one")]);
+        let mut tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let mut child = tree.root.children.pop_first().unwrap();
+        child.item.path.relative = "../escaped".into();
+        tree.root.children.insert(child);
+
+        let store = tempfile::tempdir().unwrap();
+        let snapshot = tree.export_to_cas(store.path()).unwrap();
+        let parent = tempfile::tempdir().unwrap();
+        let target = parent.path().join("target");
+
+        let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), &target);
+        assert!(matches!(restored, Err(CasError::UnsafePath(_))));
+        assert!(!parent.path().join("escaped").exists() && !target.exists());
+    }
+
+    #[test]
+    fn refuses_missing_and_corrupt_blobs() {
+        let dir = fixture(&[("a", b"one")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let snapshot = tree.export_to_cas(store.path()).unwrap();
+        let blob = tree.file_contents_hash(b"one").to_hex();
+
+        write(store.path(), &blob, b"two");
+        let target = tempfile::tempdir().unwrap();
+        let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), target.path());
+        assert!(matches!(restored, Err(CasError::CorruptBlob(corrupt)) if corrupt == blob));
+
+        fs::remove_file(store.path().join(&blob)).unwrap();
+        let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), target.path());
+        assert!(matches!(restored, Err(CasError::MissingBlob(missing)) if missing == blob));
+        assert!(!target.path().join("a").exists());
+    }
+
+    #[test]
+    fn refuses_files_changed_since_indexing() {
+        let dir = fixture(&[("a", b"before")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        write(dir.path(), "a", b"after");
+        let store = tempfile::tempdir().unwrap();
+        assert!(matches!(tree.export_to_cas(store.path()), Err(CasError::FileChanged(_))));
+    }
+
+    #[test]
+    fn restores_trees_whose_root_is_a_file() {
+        let dir = fixture(&[("file", b"contents")]);
+        let file = dir.path().join("file");
+        let tree = MerkleTree::builder(file.to_str().unwrap()).hash_names(true).build().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let snapshot = tree.export_to_cas(store.path()).unwrap();
+
+        let parent = tempfile::tempdir().unwrap();
+        let target = parent.path().join("restored");
+        let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), &target).unwrap();
+        assert_eq!(restored.root.item.hash, tree.root.item.hash);
+        assert_eq!(fs::read(&target).unwrap(), b"contents");
+    }
+
+    #[test]
+    fn checks_every_blob_before_writing() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("d", b"three")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let snapshot = tree.export_to_cas(store.path()).unwrap();
+
+        let last = tree.file_contents_hash(b"three").to_hex();
+        write(store.path(), &last, b"four");
+        let target = tempfile::tempdir().unwrap();
+        let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), target.path());
+        assert!(matches!(restored, Err(CasError::CorruptBlob(_))));
+        assert_eq!(fs::read_dir(target.path()).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_targets_that_are_not_empty() {
+        use std::os::unix::fs::symlink;
+
+        let dir = fixture(&[("sub/file", b"one")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let store = tempfile::tempdir().unwrap();
+        let snapshot = tree.export_to_cas(store.path()).unwrap();
+
+        let elsewhere = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        symlink(elsewhere.path(), target.path().join("sub")).unwrap();
+        let restored = MerkleTree::restore_from_cas(&snapshot, store.path(), target.path());
+        assert!(matches!(restored, Err(CasError::TargetNotEmpty(_))));
+        assert_eq!(fs::read_dir(elsewhere.path()).unwrap().count(), 0);
+    }
+}
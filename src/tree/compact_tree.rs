@@ -0,0 +1,598 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::sync::Mutex;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(feature = "fastcdc")]
+use crate::components::chunk::Chunk;
+use crate::components::compact_item::CompactItem;
+use crate::components::digest::Digest;
+use crate::components::merkle_path::MerklePath;
+use crate::error::{CompactTreeError, IndexingError};
+use crate::iters::compact_tree_iter::CompactTreeIter;
+use crate::tree::merkle_node::{IndexContext, MerkleNode};
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+use crate::utils::chunking::ChunkParams;
+use crate::utils::read_limiter::ReadLimiter;
+
+#[cfg(feature = "camino")]
+pub(crate) type PathBuf = camino::Utf8PathBuf;
+#[cfg(not(feature = "camino"))]
+pub(crate) type PathBuf = std::path::PathBuf;
+
+#[cfg(feature = "camino")]
+pub(crate) type Component = Box<str>;
+#[cfg(not(feature = "camino"))]
+pub(crate) type Component = Box<std::ffi::OsStr>;
+
+/// Index used for the missing parent of the root and the missing name of the root
+pub(crate) const NONE: u32 = u32::MAX;
+
+/// A single node in the arena
+#[derive(Clone, Debug)]
+pub(crate) struct CompactNode {
+    /// Index of the parent node, [`NONE`] for the root
+    pub(crate) parent: u32,
+    /// Index of the interned name of the node, [`NONE`] for the root
+    pub(crate) name: u32,
+    /// Number of nodes in the subtree below this node
+    pub(crate) descendants: u32,
+    /// The hash of the node
+    pub(crate) hash: Digest,
+    /// Whether the node was a directory when it was indexed
+    pub(crate) is_dir: bool,
+    /// Index of the first chunk of the node in the chunk arena
+    #[cfg(feature = "fastcdc")]
+    pub(crate) chunks_start: u32,
+    /// Number of chunks of the node
+    #[cfg(feature = "fastcdc")]
+    pub(crate) chunks_len: u32,
+}
+
+/// Memory efficient representation of an indexed directory tree
+///
+/// Nodes live in a single arena in pre-order, path components are interned and stored once,
+/// hashes are stored inline as digests and paths are only reconstructed when they are requested.
+/// Hashes of additional algorithms and chunk listings live in flat arenas of their own.
+#[derive(Clone, Debug)]
+pub struct CompactTree {
+    pub(crate) root_path: PathBuf,
+    pub(crate) nodes: Vec<CompactNode>,
+    pub(crate) names: Vec<Component>,
+    /// Hashes of the additional algorithms, one per additional algorithm for every node in pre-order
+    pub(crate) additional_hashes: Vec<Digest>,
+    #[cfg(feature = "fastcdc")]
+    pub(crate) chunks: Vec<Chunk>,
+    /// The hashing algorithm the tree was built with
+    pub algorithm: Algorithm,
+    /// Other hashing algorithms the tree was built with, their hashes are kept alongside the primary ones
+    pub additional_algorithms: Vec<Algorithm>,
+    /// The chunk sizes files were split into, if they were split at all
+    pub chunking: Option<ChunkParams>,
+    /// Whether names were included in the hashes of files and directories
+    pub hash_names: bool,
+}
+
+/// Interns path components while indexing, shared between threads
+#[derive(Default)]
+struct Interner {
+    indices: HashMap<Component, u32>,
+    names: Vec<Component>,
+}
+
+impl Interner {
+    fn intern(&mut self, name: &Component) -> Result<u32, CompactTreeError> {
+        if let Some(index) = self.indices.get(name) {
+            return Ok(*index);
+        }
+        let index = arena_index(self.names.len(), CompactTreeError::TooManyNames)?;
+        self.names.push(name.clone());
+        self.indices.insert(name.clone(), index);
+        Ok(index)
+    }
+}
+
+/// Converts a position in an arena into an index, which must stay below [`NONE`]
+fn arena_index(position: usize, error: CompactTreeError) -> Result<u32, CompactTreeError> {
+    match u32::try_from(position) {
+        Ok(index) if index != NONE => Ok(index),
+        _ => Err(error),
+    }
+}
+
+/// Arenas of a subtree indexed on its own, with parent indices relative to its root
+struct Subtree {
+    nodes: Vec<CompactNode>,
+    additional_hashes: Vec<Digest>,
+    #[cfg(feature = "fastcdc")]
+    chunks: Vec<Chunk>,
+}
+
+impl Subtree {
+    /// Appends the arenas of a child subtree, making its root a child of this subtree's root
+    fn append(&mut self, child: Subtree) -> Result<(), CompactTreeError> {
+        // Checking the last index up front keeps every shifted index in range
+        arena_index(self.nodes.len() + child.nodes.len() - 1, CompactTreeError::TooManyNodes)?;
+        let offset = arena_index(self.nodes.len(), CompactTreeError::TooManyNodes)?;
+        #[cfg(feature = "fastcdc")]
+        arena_index(self.chunks.len() + child.chunks.len(), CompactTreeError::TooManyChunks)?;
+        #[cfg(feature = "fastcdc")]
+        let chunks_offset = arena_index(self.chunks.len(), CompactTreeError::TooManyChunks)?;
+
+        for mut node in child.nodes {
+            node.parent = match node.parent {
+                NONE => 0,
+                parent => parent + offset,
+            };
+            #[cfg(feature = "fastcdc")]
+            {
+                node.chunks_start += chunks_offset;
+            }
+            self.nodes.push(node);
+        }
+        self.additional_hashes.extend(child.additional_hashes);
+        #[cfg(feature = "fastcdc")]
+        self.chunks.extend(child.chunks);
+
+        self.nodes[0].descendants = arena_index(self.nodes.len() - 1, CompactTreeError::TooManyNodes)?;
+        Ok(())
+    }
+}
+
+impl CompactTree {
+    /// Creates a compact copy of a merkle tree, keeping the hashes of every algorithm and the chunk listings
+    ///
+    /// Outboards, UnixFS identities and BitTorrent pieces are not kept by compact trees,
+    /// so trees holding them are refused rather than silently stripped.
+    pub fn from_tree(tree: &MerkleTree) -> Result<Self, CompactTreeError> {
+        let mut compact = CompactTree {
+            root_path: tree.root.item.path.absolute.clone(),
+            nodes: Vec::new(),
+            names: Vec::new(),
+            additional_hashes: Vec::new(),
+            #[cfg(feature = "fastcdc")]
+            chunks: Vec::new(),
+            algorithm: tree.algorithm.clone(),
+            additional_algorithms: tree.additional_algorithms.clone(),
+            chunking: tree.chunking,
+            hash_names: tree.hash_names,
+        };
+        let mut interner = Interner::default();
+        compact.push(&tree.root, NONE, &mut interner)?;
+        compact.names = interner.names;
+        compact.nodes.shrink_to_fit();
+        compact.names.shrink_to_fit();
+        compact.additional_hashes.shrink_to_fit();
+        #[cfg(feature = "fastcdc")]
+        compact.chunks.shrink_to_fit();
+        Ok(compact)
+    }
+
+    fn push(&mut self, node: &MerkleNode, parent: u32, interner: &mut Interner) -> Result<(), CompactTreeError> {
+        let index = arena_index(self.nodes.len(), CompactTreeError::TooManyNodes)?;
+
+        let item = &node.item;
+        #[cfg(feature = "bao")]
+        let unsupported = item.outboard.is_some();
+        #[cfg(not(feature = "bao"))]
+        let unsupported = false;
+        #[cfg(feature = "unixfs")]
+        let unsupported = unsupported || item.unixfs.is_some();
+        #[cfg(feature = "bittorrent")]
+        let unsupported = unsupported || item.torrent.is_some();
+        if unsupported {
+            return Err(CompactTreeError::UnsupportedExtras(item.path.clone()));
+        }
+
+        for algorithm in &self.additional_algorithms {
+            match item.additional_hashes.get(&algorithm.kind()) {
+                Some(hash) => self.additional_hashes.push(*hash),
+                None => return Err(CompactTreeError::MissingHash(item.path.clone(), algorithm.kind())),
+            }
+        }
+
+        let name = match (parent, item.path.relative.file_name()) {
+            (NONE, _) | (_, None) => NONE,
+            (_, Some(name)) => interner.intern(&Component::from(name))?,
+        };
+
+        #[cfg(feature = "fastcdc")]
+        let chunks_start = arena_index(self.chunks.len(), CompactTreeError::TooManyChunks)?;
+        #[cfg(feature = "fastcdc")]
+        self.chunks.extend_from_slice(&item.chunks);
+        #[cfg(feature = "fastcdc")]
+        let chunks_len = arena_index(self.chunks.len(), CompactTreeError::TooManyChunks)? - chunks_start;
+
+        self.nodes.push(CompactNode {
+            parent,
+            name,
+            descendants: 0,
+            hash: item.hash,
+            is_dir: item.is_dir,
+            #[cfg(feature = "fastcdc")]
+            chunks_start,
+            #[cfg(feature = "fastcdc")]
+            chunks_len,
+        });
+
+        for child in &node.children {
+            self.push(child, index, interner)?;
+        }
+
+        self.nodes[index as usize].descendants = arena_index(self.nodes.len() - 1, CompactTreeError::TooManyNodes)? - index;
+        Ok(())
+    }
+
+    /// Indexes a directory tree straight into a compact tree, without building a merkle tree first
+    ///
+    /// Fails with [`IndexingError::CompactTree`] if the tree has more nodes, names or chunks than its arenas can index.
+    pub(crate) fn index_with(context: &IndexContext) -> Result<Self, IndexingError> {
+        let interner = Mutex::new(Interner::default());
+        let path = MerkleNode::root_path(context.root);
+        let root_path = path.absolute.clone();
+        let subtree = Self::index_subtree(context, &interner, path, NONE)?;
+
+        #[cfg(feature = "fastcdc")]
+        let chunking = context.chunking;
+        #[cfg(not(feature = "fastcdc"))]
+        let chunking = None;
+
+        let mut compact = CompactTree {
+            root_path,
+            nodes: subtree.nodes,
+            names: interner.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()).names,
+            additional_hashes: subtree.additional_hashes,
+            #[cfg(feature = "fastcdc")]
+            chunks: subtree.chunks,
+            algorithm: context.algorithm.clone(),
+            additional_algorithms: context.additional_algorithms.to_vec(),
+            chunking,
+            hash_names: context.hash_names,
+        };
+        compact.nodes.shrink_to_fit();
+        compact.names.shrink_to_fit();
+        compact.additional_hashes.shrink_to_fit();
+        #[cfg(feature = "fastcdc")]
+        compact.chunks.shrink_to_fit();
+        Ok(compact)
+    }
+
+    /// Indexes a file or directory and its descendants into the arenas of a subtree,
+    /// hashing them the same way [`MerkleNode`] does
+    fn index_subtree(
+        context: &IndexContext,
+        interner: &Mutex<Interner>,
+        path: MerklePath,
+        name: u32,
+    ) -> Result<Subtree, IndexingError> {
+        if !path.absolute.is_dir() {
+            let file_bytes = {
+                // Waits for its turn to read, if the number of concurrent reads is limited
+                let _permit = context.read_limiter.as_ref().map(ReadLimiter::acquire);
+                match fs::read(&path.absolute) {
+                    Ok(file_bytes) => file_bytes,
+                    Err(err) => return Err(IndexingError::UnableToReadFile(path.absolute, err)),
+                }
+            };
+
+            let file_hashes = MerkleNode::file_hashes(context, &file_bytes);
+            let (hash, additional_hashes) = MerkleNode::named_hashes(
+                &path,
+                (file_hashes.contents_hash, file_hashes.additional_contents_hashes),
+                context.hash_names,
+                context.algorithm,
+                context.additional_algorithms,
+            )?;
+
+            return Ok(Subtree {
+                nodes: vec![CompactNode {
+                    parent: NONE,
+                    name,
+                    descendants: 0,
+                    hash,
+                    is_dir: false,
+                    #[cfg(feature = "fastcdc")]
+                    chunks_start: 0,
+                    #[cfg(feature = "fastcdc")]
+                    chunks_len: arena_index(file_hashes.chunks.len(), CompactTreeError::TooManyChunks)?,
+                }],
+                additional_hashes: Self::ordered_hashes(context.additional_algorithms, additional_hashes),
+                #[cfg(feature = "fastcdc")]
+                chunks: file_hashes.chunks,
+            });
+        }
+
+        let read_dir = match fs::read_dir(&path.absolute) {
+            Ok(read_dir) => read_dir,
+            Err(err) => return Err(IndexingError::UnableToReadDir(path.absolute, err)),
+        };
+
+        let index_entry = |entry: std::io::Result<fs::DirEntry>| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => return Err(IndexingError::UnableToReadDirEntry(path.absolute.clone(), err)),
+            };
+
+            let child_path = MerkleNode::child_path(context.root, entry.path())?;
+            let child_name = match child_path.relative.file_name() {
+                Some(child_name) => Component::from(child_name),
+                None => return Err(IndexingError::UnableToReadFileName(child_path.absolute)),
+            };
+            let child_name = interner
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .intern(&child_name)?;
+
+            let relative = child_path.relative.clone();
+            Ok((relative, Self::index_subtree(context, interner, child_path, child_name)?))
+        };
+
+        #[cfg(feature = "parallel")]
+        let mut children = if context.parallel {
+            read_dir.par_bridge().map(index_entry).collect::<Result<Vec<_>, IndexingError>>()?
+        } else {
+            read_dir.map(index_entry).collect::<Result<Vec<_>, IndexingError>>()?
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let mut children = read_dir.map(index_entry).collect::<Result<Vec<_>, IndexingError>>()?;
+
+        // Orders the children the same way the children of a merkle node are ordered
+        children.sort_by(|(first, _), (second, _)| first.cmp(second));
+
+        let hashes: Vec<_> = children.iter().map(|(_, child)| child.nodes[0].hash).collect();
+        let contents_hash = MerkleNode::directory_hash_of(&hashes, context.algorithm, context.parallel);
+        let additional_contents_hashes = MerkleNode::additional_hashes(context.additional_algorithms, |algorithm| {
+            let position = context
+                .additional_algorithms
+                .iter()
+                .position(|additional| additional.kind() == algorithm.kind())
+                .unwrap_or_default();
+            let hashes: Vec<_> = children
+                .iter()
+                .map(|(_, child)| child.additional_hashes[position])
+                .collect();
+            MerkleNode::directory_hash_of(&hashes, algorithm, context.parallel)
+        });
+
+        let (hash, additional_hashes) = MerkleNode::named_hashes(
+            &path,
+            (contents_hash, additional_contents_hashes),
+            context.hash_names,
+            context.algorithm,
+            context.additional_algorithms,
+        )?;
+
+        let mut subtree = Subtree {
+            nodes: vec![CompactNode {
+                parent: NONE,
+                name,
+                descendants: 0,
+                hash,
+                is_dir: true,
+                #[cfg(feature = "fastcdc")]
+                chunks_start: 0,
+                #[cfg(feature = "fastcdc")]
+                chunks_len: 0,
+            }],
+            additional_hashes: Self::ordered_hashes(context.additional_algorithms, additional_hashes),
+            #[cfg(feature = "fastcdc")]
+            chunks: Vec::new(),
+        };
+        for (_, child) in children {
+            subtree.append(child)?;
+        }
+        Ok(subtree)
+    }
+
+    /// Lists the hashes of the additional algorithms in the order the algorithms were given
+    fn ordered_hashes(algorithms: &[Algorithm], mut hashes: BTreeMap<AlgorithmKind, Digest>) -> Vec<Digest> {
+        algorithms
+            .iter()
+            .filter_map(|algorithm| hashes.remove(&algorithm.kind()))
+            .collect()
+    }
+
+    /// Converts the compact tree back into a merkle tree, with every hash and chunk listing it holds
+    pub fn to_tree(&self) -> MerkleTree {
+        MerkleTree {
+            root: self.to_node(0),
+            algorithm: self.algorithm.clone(),
+            additional_algorithms: self.additional_algorithms.clone(),
+            chunking: self.chunking,
+            hash_names: self.hash_names,
+        }
+    }
+
+    fn to_node(&self, index: u32) -> MerkleNode {
+        let item = self.item(index);
+        let children: BTreeSet<MerkleNode> = item.children().map(|child| self.to_node(child.index())).collect();
+        MerkleNode {
+            item: item.to_item(),
+            children,
+        }
+    }
+
+    /// Returns the number of files and directories in the tree
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the tree has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the absolute path of the root of the tree
+    #[cfg(feature = "camino")]
+    pub fn root_path(&self) -> &camino::Utf8Path {
+        &self.root_path
+    }
+
+    /// Returns the absolute path of the root of the tree
+    #[cfg(not(feature = "camino"))]
+    pub fn root_path(&self) -> &std::path::Path {
+        &self.root_path
+    }
+
+    /// Returns a view of the root of the tree
+    pub fn root(&self) -> CompactItem<'_> {
+        self.item(0)
+    }
+
+    /// Returns a view of the node at the given pre-order index
+    pub fn get(&self, index: usize) -> Option<CompactItem<'_>> {
+        if index < self.nodes.len() {
+            Some(self.item(index as u32))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn item(&self, index: u32) -> CompactItem<'_> {
+        CompactItem::new(self, index)
+    }
+
+    /// Returns an iterator over each file and directory in the tree, in the same order as [`MerkleTree::iter`]
+    pub fn iter(&self) -> CompactTreeIter<'_> {
+        CompactTreeIter::new(self)
+    }
+}
+
+impl TryFrom<&MerkleTree> for CompactTree {
+    type Error = CompactTreeError;
+
+    fn try_from(tree: &MerkleTree) -> Result<Self, Self::Error> {
+        CompactTree::from_tree(tree)
+    }
+}
+
+impl TryFrom<MerkleTree> for CompactTree {
+    type Error = CompactTreeError;
+
+    fn try_from(tree: MerkleTree) -> Result<Self, Self::Error> {
+        CompactTree::from_tree(&tree)
+    }
+}
+
+impl From<&CompactTree> for MerkleTree {
+    fn from(tree: &CompactTree) -> Self {
+        tree.to_tree()
+    }
+}
+
+impl From<CompactTree> for MerkleTree {
+    fn from(tree: CompactTree) -> Self {
+        tree.to_tree()
+    }
+}
+
+impl<'a> IntoIterator for &'a CompactTree {
+    type Item = CompactItem<'a>;
+
+    type IntoIter = CompactTreeIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::algorithm::Blake3Key;
+    use crate::utils::fixture::{fixture, path};
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[("a/b", b"one"), ("a/c/d", b"two"), ("a/c/e", b"two"), ("f", b"three"), ("g/", b"")])
+    }
+
+    #[test]
+    fn arena_indices_stay_below_none() {
+        assert_eq!(arena_index(0, CompactTreeError::TooManyNames), Ok(0));
+        assert_eq!(arena_index(NONE as usize - 1, CompactTreeError::TooManyNames), Ok(NONE - 1));
+        assert_eq!(arena_index(NONE as usize, CompactTreeError::TooManyNames), Err(CompactTreeError::TooManyNames));
+        assert_eq!(
+            arena_index(usize::MAX, CompactTreeError::TooManyChunks),
+            Err(CompactTreeError::TooManyChunks)
+        );
+    }
+
+    #[test]
+    fn build_compact_matches_build() {
+        let dir = sample();
+        for hash_names in [false, true] {
+            let builder = || {
+                MerkleTree::builder(path(&dir))
+                    .hash_names(hash_names)
+                    .algorithms([Algorithm::Blake3, Algorithm::Blake3Keyed(Blake3Key::new([3; 32]))])
+            };
+            let tree = builder().build().unwrap();
+            let compact = builder().build_compact().unwrap();
+
+            assert_eq!(compact.len(), tree.iter().count());
+            assert_eq!(compact.names.len(), 7);
+            assert!(compact.iter().map(|item| item.to_item()).eq(tree.iter().cloned()));
+            assert_eq!(
+                compact.root().hash_for(AlgorithmKind::Blake3Keyed),
+                tree.root_hash_for(AlgorithmKind::Blake3Keyed)
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip_is_lossless() {
+        let dir = sample();
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithms([Algorithm::Blake3, Algorithm::Blake3Keyed(Blake3Key::new([3; 32]))])
+            .build()
+            .unwrap();
+
+        let compact = CompactTree::try_from(&tree).unwrap();
+        let restored = compact.to_tree();
+        assert!(restored.iter().eq(tree.iter()));
+        assert_eq!(restored.additional_algorithms, tree.additional_algorithms);
+    }
+
+    #[test]
+    fn views_reconstruct_paths_and_structure() {
+        let dir = sample();
+        let compact = MerkleTree::builder(path(&dir)).build_compact().unwrap();
+
+        let root = compact.root();
+        assert_eq!(root.name(), None);
+        assert_eq!(root.descendants(), compact.len() - 1);
+        let names: Vec<_> = root.children().map(|child| child.relative_path()).collect();
+        assert_eq!(names, ["a", "f", "g"].map(PathBuf::from));
+
+        let d = compact.iter().find(|item| item.relative_path().ends_with("a/c/d")).unwrap();
+        assert_eq!(d.parent().unwrap().relative_path(), PathBuf::from("a/c"));
+        assert_eq!(d.absolute_path(), PathBuf::from(path(&dir)).join("a/c/d"));
+    }
+
+    #[cfg(feature = "fastcdc")]
+    #[test]
+    fn keeps_chunk_listings() {
+        let contents: Vec<u8> = (0..200_000u32).flat_map(|value| value.wrapping_mul(2_654_435_761).to_le_bytes()).collect();
+        let dir = fixture(&[("big", &contents), ("small", b"tiny")]);
+        let builder = || MerkleTree::builder(path(&dir)).chunking(ChunkParams::new(4096, 16384, 65536).unwrap());
+        let tree = builder().build().unwrap();
+        let compact = builder().build_compact().unwrap();
+
+        let big = compact.iter().find(|item| item.relative_path().ends_with("big")).unwrap();
+        assert!(big.chunks().len() > 1);
+        assert!(compact.iter().map(|item| item.to_item()).eq(tree.iter().cloned()));
+        assert!(CompactTree::from_tree(&tree).unwrap().to_tree().iter().eq(tree.iter()));
+    }
+
+    #[cfg(feature = "bao")]
+    #[test]
+    fn refuses_trees_with_outboards() {
+        let dir = sample();
+        let tree = MerkleTree::builder(path(&dir)).outboards(true).build().unwrap();
+        assert!(matches!(CompactTree::from_tree(&tree), Err(CompactTreeError::UnsupportedExtras(_))));
+    }
+}
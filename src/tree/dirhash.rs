@@ -0,0 +1,246 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::components::merkle_item::MerkleItem;
+use crate::error::DirHashError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+use crate::utils::base64;
+
+/// Version control directories Go leaves out of module zips
+const GO_VCS_DIRS: [&str; 4] = [".bzr", ".git", ".hg", ".svn"];
+
+impl MerkleTree {
+    /// Computes Go's `dirhash.Hash1` of the files in the tree, as found in `go.sum`
+    ///
+    /// Every file is named by the prefix, usually `module@version`, followed by a slash and its relative path.
+    /// The hash is the SHA-256 of the sorted `sha256sum` style listing of those files, in the `h1:<base64>` form.
+    /// Only the files Go puts in a module zip count: regular files, outside of nested modules,
+    /// version control directories and vendored packages. Links below the root are skipped.
+    pub fn go_dirhash(&self, prefix: &str) -> Result<String, DirHashError> {
+        let mut items = Vec::new();
+        Self::go_module_files(&self.root, true, &mut items)?;
+
+        let mut files = Vec::new();
+        for item in items {
+            let relative = Self::go_relative_path(item)?;
+            if Self::go_is_vendored(&relative) {
+                continue;
+            }
+            files.push((format!("{}/{}", prefix, relative), item));
+        }
+        self.go_hash1(files)
+    }
+
+    /// Collects the regular files of a node the way Go's module zip walks a directory
+    fn go_module_files<'a>(
+        node: &'a MerkleNode,
+        root: bool,
+        files: &mut Vec<&'a MerkleItem>,
+    ) -> Result<(), DirHashError> {
+        let path: &Path = node.item.path.absolute.as_ref();
+        // The root is taken as given, links below it are neither followed nor included
+        let metadata = match root {
+            true => fs::metadata(path),
+            false => fs::symlink_metadata(path),
+        };
+        let metadata = metadata.map_err(|err| DirHashError::UnableToReadMetadata(path.to_path_buf(), err))?;
+
+        if metadata.is_file() {
+            files.push(&node.item);
+        } else if metadata.is_dir() {
+            if !root {
+                let is_vcs = path.file_name().is_some_and(|name| GO_VCS_DIRS.iter().any(|vcs| name == *vcs));
+                let is_module = fs::symlink_metadata(path.join("go.mod")).is_ok_and(|go_mod| !go_mod.is_dir());
+                if is_vcs || is_module {
+                    return Ok(());
+                }
+            }
+            for child in &node.children {
+                Self::go_module_files(child, false, files)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tells whether a file belongs to a vendored package, following `isVendoredPackage` of Go's module zip
+    ///
+    /// Files directly in a `vendor` directory, such as `vendor/modules.txt`, are kept. Go looks past a
+    /// nested `/vendor/` by its length from the start of the path rather than from where it was found,
+    /// and the hash must match that.
+    fn go_is_vendored(relative: &str) -> bool {
+        let start = match relative.strip_prefix("vendor/") {
+            Some(_) => "vendor/".len(),
+            None if relative.contains("/vendor/") => "/vendor/".len(),
+            None => return false,
+        };
+        relative.as_bytes()[start..].contains(&b'/')
+    }
+
+    /// Checks a `go.sum` line against the tree, which must be the module's directory or its `go.mod` file
+    ///
+    /// Lines of the form `<module> <version> h1:<hash>` are checked against the whole tree,
+    /// lines of the form `<module> <version>/go.mod h1:<hash>` against its `go.mod` file alone.
+    pub fn verify_go_sum(&self, line: &str) -> Result<bool, DirHashError> {
+        let (module, version, hash) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [module, version, hash] => (module, version, hash),
+            _ => return Err(DirHashError::MalformedGoSumLine(line.to_string())),
+        };
+        if !hash.starts_with("h1:") {
+            return Err(DirHashError::UnsupportedHashVersion(hash.to_string()));
+        }
+
+        let computed = match version.strip_suffix("/go.mod") {
+            Some(_) => self.go_mod_hash()?,
+            None => self.go_dirhash(&format!("{}@{}", module, version))?,
+        };
+        Ok(computed == hash)
+    }
+
+    /// Computes the `dirhash.Hash1` of the tree's `go.mod` file alone, named just `go.mod`
+    ///
+    /// Only the `go.mod` at the top of the module counts, or the root itself when the tree is that file.
+    fn go_mod_hash(&self) -> Result<String, DirHashError> {
+        let root = &self.root.item;
+        let go_mod = match root.path.absolute.is_file() {
+            true => Some(root).filter(|item| item.path.absolute.file_name().is_some_and(|name| name == "go.mod")),
+            false => self.iter().find(|item| {
+                let relative: &Path = item.path.relative.as_ref();
+                relative == Path::new("go.mod") && !item.is_dir
+            }),
+        };
+        match go_mod {
+            Some(item) => self.go_hash1(vec![("go.mod".to_string(), item)]),
+            None => Err(DirHashError::MissingGoMod),
+        }
+    }
+
+    fn go_hash1(&self, mut files: Vec<(String, &MerkleItem)>) -> Result<String, DirHashError> {
+        files.sort_by(|(name, _), (other, _)| name.cmp(other));
+
+        let mut listing = String::new();
+        for (name, item) in files {
+            if name.contains('\n') {
+                return Err(DirHashError::FileNameWithNewline(name));
+            }
+            let _ = writeln!(listing, "{}  {}", self.go_file_hash(item)?, name);
+        }
+
+        let summary = Algorithm::Sha256.compute_hash(listing.as_bytes());
+        Ok(format!("h1:{}", base64::encode(summary.as_bytes())))
+    }
+
+    /// Returns the hex SHA-256 of a file, reusing the tree's hash if it holds the plain contents hash
+    fn go_file_hash(&self, item: &MerkleItem) -> Result<String, DirHashError> {
+        if !self.hash_names && self.chunking.is_none() {
+            if let Some(hash) = item.hash_for(AlgorithmKind::Sha256) {
+                return Ok(hash.to_hex());
+            }
+        }
+
+        let path: &Path = item.path.absolute.as_ref();
+        match fs::read(path) {
+            Ok(bytes) => Ok(Algorithm::Sha256.compute_hash(&bytes).to_hex()),
+            Err(err) => Err(DirHashError::UnableToReadFile(path.to_path_buf(), err)),
+        }
+    }
+
+    /// Joins the components of a file's relative path with slashes, or names a root file by itself
+    fn go_relative_path(item: &MerkleItem) -> Result<String, DirHashError> {
+        let relative: &Path = item.path.relative.as_ref();
+        let relative = match relative.as_os_str().is_empty() {
+            true => {
+                let absolute: &Path = item.path.absolute.as_ref();
+                absolute.file_name().map(Path::new).unwrap_or(relative)
+            }
+            false => relative,
+        };
+
+        let mut components = Vec::new();
+        for component in relative.components() {
+            match component.as_os_str().to_str() {
+                Some(component) => components.push(component),
+                None => return Err(DirHashError::NonUtf8Path(relative.to_path_buf())),
+            }
+        }
+        Ok(components.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DirHashError;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path};
+
+    const GO_MOD: &[u8] = b"module example.com/m\n\ngo 1.21\n";
+
+    // Expected hashes follow golang.org/x/mod/sumdb/dirhash.Hash1 over the files of a module zip,
+    // computed independently of this crate with Python's hashlib
+    #[test]
+    fn hashes_modules_and_their_go_mod() {
+        let dir = fixture(&[
+            ("go.mod", GO_MOD),
+            ("main.go", b"package m\n"),
+            ("internal/util.go", b"package internal\n"),
+            ("sub/go.mod", b"module example.com/m/sub\n"),
+        ]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let dirhash = "h1:eZKOD3jXejpGhgmga/FUfDi/c6jF6I22LBZj8DOMi8k=";
+        assert_eq!(tree.go_dirhash("example.com/m@v1.0.0").unwrap(), dirhash);
+        assert!(tree.verify_go_sum(&format!("example.com/m v1.0.0 {}", dirhash)).unwrap());
+        assert!(!tree.verify_go_sum(&format!("example.com/m v1.0.1 {}", dirhash)).unwrap());
+
+        let go_mod = "example.com/m v1.0.0/go.mod h1:ONeDgCa5UF/jJRjGzpOKmUiezgFEk4IPFZ96frvroW0=";
+        assert!(tree.verify_go_sum(go_mod).unwrap());
+
+        let root_file = MerkleTree::builder(dir.path().join("go.mod").to_str().unwrap()).build().unwrap();
+        assert!(root_file.verify_go_sum(go_mod).unwrap());
+    }
+
+    #[test]
+    fn leaves_out_what_module_zips_leave_out() {
+        let dir = fixture(&[
+            ("go.mod", GO_MOD),
+            ("vendor/modules.txt", b"# example.com/dep v1.0.0\n"),
+            ("vendor/example.com/dep/dep.go", b"package dep\n"),
+            ("internal/vendor/x.go", b"package vendor\n"),
+            (".git/HEAD", b"ref: refs/heads/main\n"),
+            ("sub/go.mod", b"module example.com/m/sub\n"),
+            ("sub/sub.go", b"package sub\n"),
+        ]);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("go.mod"), dir.path().join("linked.go")).unwrap();
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let dirhash = "h1:Hp/N0/Qx9CpNqSXzysMLsLK1GmogX9MSrj2rTYQszcw=";
+        assert_eq!(tree.go_dirhash("example.com/m@v1.0.0").unwrap(), dirhash);
+    }
+
+    #[test]
+    fn ignores_nested_go_mod_files() {
+        let dir = fixture(&[("main.go", b"package m\n"), ("sub/go.mod", b"module example.com/m/sub\n")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let nested = "example.com/m v1.0.0/go.mod h1:32N0V5QLwG7HZqCpWGpbwU/yqtuDehV4rqIKlGLSqxY=";
+        assert!(matches!(tree.verify_go_sum(nested), Err(DirHashError::MissingGoMod)));
+
+        let file = MerkleTree::builder(dir.path().join("main.go").to_str().unwrap()).build().unwrap();
+        assert!(matches!(file.verify_go_sum(nested), Err(DirHashError::MissingGoMod)));
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let dir = fixture(&[("go.mod", GO_MOD)]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        assert!(matches!(tree.verify_go_sum("example.com/m v1.0.0"), Err(DirHashError::MalformedGoSumLine(_))));
+        assert!(matches!(
+            tree.verify_go_sum("example.com/m v1.0.0 h2:abc"),
+            Err(DirHashError::UnsupportedHashVersion(_))
+        ));
+    }
+}
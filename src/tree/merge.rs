@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::components::merge_plan::{ConflictKind, MergeAction, MergeEntry, MergePlan};
+use crate::error::MergeError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+
+/// The nodes at the same relative path in the base, ours and theirs, none where a tree does not have it
+type Sides<'a> = [Option<&'a MerkleNode>; 3];
+
+impl MerkleTree {
+    /// Merges the changes two trees made to a common base, classifying every path
+    ///
+    /// Subtrees with equal hashes, kinds and names on the compared sides are resolved without being descended into.
+    /// Names are compared on their own when they are not hashed, so renames are never mistaken for no change.
+    /// All three trees must be built with the same algorithm, chunking and name hashing setting.
+    pub fn merge(base: &MerkleTree, ours: &MerkleTree, theirs: &MerkleTree) -> Result<MergePlan, MergeError> {
+        if !base.merge_settings_match(ours) || !base.merge_settings_match(theirs) {
+            return Err(MergeError::SettingsMismatch);
+        }
+
+        let mut plan = MergePlan::default();
+        base.merge_nodes([Some(&base.root), Some(&ours.root), Some(&theirs.root)], &mut plan);
+        Ok(plan)
+    }
+
+    /// Returns whether the hashes of two trees are comparable
+    fn merge_settings_match(&self, other: &MerkleTree) -> bool {
+        self.algorithm.kind() == other.algorithm.kind()
+            && self.algorithm.context() == other.algorithm.context()
+            && self.algorithm.output_len() == other.algorithm.output_len()
+            && self.algorithm.key_fingerprint() == other.algorithm.key_fingerprint()
+            && self.chunking == other.chunking
+            && self.hash_names == other.hash_names
+    }
+
+    /// Resolves the nodes at one relative path, descending only when both sides changed a directory
+    fn merge_nodes(&self, sides: Sides<'_>, plan: &mut MergePlan) {
+        let [base, ours, theirs] = sides;
+        let action = if self.merge_same(ours, theirs) {
+            match self.merge_same(base, ours) {
+                true => MergeAction::Unchanged,
+                false => MergeAction::TakeOurs,
+            }
+        } else if self.merge_same(base, ours) {
+            MergeAction::TakeTheirs
+        } else if self.merge_same(base, theirs) {
+            MergeAction::TakeOurs
+        } else {
+            match (ours, theirs) {
+                (Some(ours), Some(theirs)) => match (ours.item.is_dir, theirs.item.is_dir) {
+                    (true, true) => return self.merge_children(sides, plan),
+                    (false, false) if base.is_none() => MergeAction::Conflict(ConflictKind::BothAdded),
+                    (false, false) => MergeAction::Conflict(ConflictKind::BothModified),
+                    _ => MergeAction::Conflict(ConflictKind::TypeClash),
+                },
+                _ => MergeAction::Conflict(ConflictKind::ModifyDelete),
+            }
+        };
+        plan.entries.push(Self::merge_entry(sides, action));
+    }
+
+    /// Resolves the children of a directory changed on both sides one by one
+    fn merge_children(&self, sides: Sides<'_>, plan: &mut MergePlan) {
+        let mut children: BTreeMap<&Path, Sides<'_>> = BTreeMap::new();
+        for (side, node) in sides.into_iter().enumerate() {
+            // A base file replaced by directories on both sides has no children to compare against
+            let node = match node {
+                Some(node) if node.item.is_dir => node,
+                _ => continue,
+            };
+            for child in &node.children {
+                let relative: &Path = child.item.path.relative.as_ref();
+                children.entry(relative).or_default()[side] = Some(child);
+            }
+        }
+
+        for child_sides in children.into_values() {
+            self.merge_nodes(child_sides, plan);
+        }
+    }
+
+    /// Returns whether two sides hold the same kind of node with the same hash and names below it, or both lack it
+    fn merge_same(&self, first: Option<&MerkleNode>, second: Option<&MerkleNode>) -> bool {
+        match (first, second) {
+            (Some(first), Some(second)) => {
+                first.item.hash == second.item.hash
+                    && first.item.is_dir == second.item.is_dir
+                    && (self.hash_names || Self::merge_same_names(first, second))
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether two directories have children of the same names and kinds, all the way down
+    fn merge_same_names(first: &MerkleNode, second: &MerkleNode) -> bool {
+        first.children.len() == second.children.len()
+            && first.children.iter().zip(&second.children).all(|(first, second)| {
+                first.item.path.relative == second.item.path.relative
+                    && first.item.is_dir == second.item.is_dir
+                    && Self::merge_same_names(first, second)
+            })
+    }
+
+    fn merge_entry(sides: Sides<'_>, action: MergeAction) -> MergeEntry {
+        let [base, ours, theirs] = sides.map(|node| node.map(|node| node.item.clone()));
+        let relative = base
+            .iter()
+            .chain(&ours)
+            .chain(&theirs)
+            .map(|item| item.path.relative.clone())
+            .next()
+            .unwrap_or_default();
+        MergeEntry {
+            relative,
+            base,
+            ours,
+            theirs,
+            action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::components::merge_plan::{ConflictKind, MergeAction, MergePlan};
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path};
+
+    type Entries<'a> = &'a [(&'a str, &'a [u8])];
+
+    fn plan(base: Entries, ours: Entries, theirs: Entries, hash_names: bool) -> MergePlan {
+        let dirs = [fixture(base), fixture(ours), fixture(theirs)];
+        let [base, ours, theirs] =
+            dirs.each_ref().map(|dir| MerkleTree::builder(path(dir)).hash_names(hash_names).build().unwrap());
+        MerkleTree::merge(&base, &ours, &theirs).unwrap()
+    }
+
+    fn action(plan: &MergePlan, relative: &str) -> MergeAction {
+        let entry = plan.entries.iter().find(|entry| {
+            let path: &Path = entry.relative.as_ref();
+            path == Path::new(relative)
+        });
+        entry.unwrap().action
+    }
+
+    #[test]
+    fn takes_the_changed_side() {
+        let base: Entries = &[("a", b"one"), ("b", b"one"), ("c", b"one"), ("d", b"one")];
+        let ours: Entries = &[("a", b"two"), ("b", b"one"), ("c", b"same"), ("d", b"one"), ("e", b"new")];
+        let theirs: Entries = &[("a", b"one"), ("b", b"two"), ("c", b"same")];
+        let plan = plan(base, ours, theirs, true);
+
+        assert!(plan.is_clean());
+        assert_eq!(action(&plan, "a"), MergeAction::TakeOurs);
+        assert_eq!(action(&plan, "b"), MergeAction::TakeTheirs);
+        assert_eq!(action(&plan, "c"), MergeAction::TakeOurs);
+        assert_eq!(action(&plan, "d"), MergeAction::TakeTheirs);
+        assert_eq!(action(&plan, "e"), MergeAction::TakeOurs);
+    }
+
+    #[test]
+    fn identical_trees_are_unchanged() {
+        let entries: Entries = &[("a", b"one"), ("sub/b", b"two")];
+        for hash_names in [false, true] {
+            let plan = plan(entries, entries, entries, hash_names);
+            assert!(plan.entries.iter().all(|entry| entry.action == MergeAction::Unchanged));
+            assert!(plan.is_clean() && plan.changes().next().is_none());
+        }
+    }
+
+    #[test]
+    fn reports_each_kind_of_conflict() {
+        let base: Entries = &[("modified", b"one"), ("deleted", b"one"), ("clash", b"one")];
+        let ours: Entries = &[
+            ("modified", b"two"),
+            ("deleted", b"two"),
+            ("clash", b"two"),
+            ("added", b"two"),
+        ];
+        let theirs: Entries = &[
+            ("modified", b"three"),
+            ("clash/inner", b"three"),
+            ("added", b"three"),
+        ];
+        let plan = plan(base, ours, theirs, true);
+
+        assert!(!plan.is_clean());
+        assert_eq!(action(&plan, "modified"), MergeAction::Conflict(ConflictKind::BothModified));
+        assert_eq!(action(&plan, "added"), MergeAction::Conflict(ConflictKind::BothAdded));
+        assert_eq!(action(&plan, "deleted"), MergeAction::Conflict(ConflictKind::ModifyDelete));
+        assert_eq!(action(&plan, "clash"), MergeAction::Conflict(ConflictKind::TypeClash));
+        assert_eq!(plan.conflicts().count(), 4);
+    }
+
+    #[test]
+    fn renames_are_not_unchanged_without_hashed_names() {
+        let base: Entries = &[("dir/a", b"one")];
+        let ours: Entries = &[("renamed/a", b"one")];
+        let renamed = plan(base, ours, base, false);
+        assert_eq!(renamed.entries.len(), 1);
+        assert_eq!(action(&renamed, ""), MergeAction::TakeOurs);
+
+        let theirs: Entries = &[("other/a", b"one")];
+        let both_renamed = plan(base, ours, theirs, false);
+        assert_eq!(action(&both_renamed, "dir"), MergeAction::TakeOurs);
+        assert_eq!(action(&both_renamed, "renamed"), MergeAction::TakeOurs);
+        assert_eq!(action(&both_renamed, "other"), MergeAction::TakeTheirs);
+    }
+
+    #[test]
+    fn empty_files_and_empty_directories_clash() {
+        let base: Entries = &[("a", b"one")];
+        let ours: Entries = &[("a", b"one"), ("empty", b"")];
+        let theirs: Entries = &[("a", b"one"), ("empty/", b"")];
+        for hash_names in [false, true] {
+            let plan = plan(base, ours, theirs, hash_names);
+            assert_eq!(action(&plan, "empty"), MergeAction::Conflict(ConflictKind::TypeClash));
+        }
+    }
+}
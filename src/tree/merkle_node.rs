@@ -1,14 +1,67 @@
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::ops::Range;
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+#[cfg(feature = "fastcdc")]
+use crate::components::chunk::Chunk;
+use crate::components::digest::Digest;
 use crate::components::merkle_item::MerkleItem;
 use crate::components::merkle_path::MerklePath;
+#[cfg(feature = "bao")]
+use crate::components::outboard::Outboard;
+#[cfg(feature = "bittorrent")]
+use crate::components::torrent_file::TorrentFile;
+#[cfg(feature = "unixfs")]
+use crate::components::unixfs_entry::UnixFsEntry;
 use crate::error::IndexingError;
-use crate::utils::algorithm::Algorithm;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+#[cfg(feature = "bittorrent")]
+use crate::utils::bittorrent::{self, PieceLength};
+#[cfg(feature = "fastcdc")]
+use crate::utils::chunking::ChunkParams;
+use crate::utils::read_limiter::ReadLimiter;
+#[cfg(feature = "unixfs")]
+use crate::utils::unixfs;
+
+/// Settings shared by every node of a single indexing run
+pub(crate) struct IndexContext<'a> {
+    /// Absolute root path of the tree
+    pub(crate) root: &'a str,
+    /// Whether to include names in the hashes of files and directories
+    pub(crate) hash_names: bool,
+    /// Which hashing algorithm to use
+    pub(crate) algorithm: &'a Algorithm,
+    /// Which other hashing algorithms to compute in the same traversal
+    pub(crate) additional_algorithms: &'a [Algorithm],
+    /// Whether to compute the BLAKE3 outboards of files
+    #[cfg(feature = "bao")]
+    pub(crate) outboards: bool,
+    /// Which chunk sizes to split files into, if they are split at all
+    #[cfg(feature = "fastcdc")]
+    pub(crate) chunking: Option<ChunkParams>,
+    /// Whether to compute the IPFS UnixFS identities of files and directories
+    #[cfg(feature = "unixfs")]
+    pub(crate) unixfs: bool,
+    /// Which piece length to compute the BitTorrent v2 pieces of files with, if they are computed at all
+    #[cfg(feature = "bittorrent")]
+    pub(crate) piece_length: Option<PieceLength>,
+    /// Whether directories are read and hashed with multiple threads
+    pub(crate) parallel: bool,
+    /// Limits how many files are read at the same time, if set
+    pub(crate) read_limiter: Option<ReadLimiter>,
+}
+
+/// Hashes of the contents of a single file
+pub(crate) struct FileHashes {
+    pub(crate) contents_hash: Digest,
+    pub(crate) additional_contents_hashes: BTreeMap<AlgorithmKind, Digest>,
+    #[cfg(feature = "fastcdc")]
+    pub(crate) chunks: Vec<Chunk>,
+}
 
 /// Represents a single node on the merkle tree
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -33,6 +86,36 @@ impl Ord for MerkleNode {
 impl MerkleNode {
     /// Creates a new root node
     pub fn root(root: &str, hash_names: bool, algorithm: Algorithm) -> Result<Self, IndexingError> {
+        let context = IndexContext {
+            root,
+            hash_names,
+            algorithm: &algorithm,
+            additional_algorithms: &[],
+            #[cfg(feature = "bao")]
+            outboards: false,
+            #[cfg(feature = "fastcdc")]
+            chunking: None,
+            #[cfg(feature = "unixfs")]
+            unixfs: false,
+            #[cfg(feature = "bittorrent")]
+            piece_length: None,
+            parallel: cfg!(feature = "parallel"),
+            read_limiter: None,
+        };
+
+        Self::root_with(&context)
+    }
+
+    /// Creates a new root node with the given indexing settings
+    pub(crate) fn root_with(context: &IndexContext) -> Result<Self, IndexingError> {
+        let path = Self::root_path(context.root);
+
+        // Indexes the newly created node and returns the result
+        Self::index(context, path)
+    }
+
+    /// Creates the merkle path of the root
+    pub(crate) fn root_path(root: &str) -> MerklePath {
         // Creates a new empty relative path, as this is the root
         #[cfg(not(feature = "camino"))]
         let relative_path = std::path::PathBuf::from("");
@@ -46,114 +129,317 @@ impl MerkleNode {
         let absolute_path = camino::Utf8PathBuf::from(root);
 
         // Creates a new merkle path based on them both
-        let path = MerklePath::new(relative_path, absolute_path);
-
-        // Indexes the newly created node and returns the result
-        Self::index(root, path, hash_names, &algorithm)
+        MerklePath::new(relative_path, absolute_path)
     }
 
     /// Indexes a new node, finding its relative and absolute paths, its file/directory hash
     /// and the same for all of its descendants
-    fn index(
-        root: &str,
-        path: MerklePath,
-        hash_names: bool,
-        algorithm: &Algorithm,
-    ) -> Result<MerkleNode, IndexingError> {
+    fn index(context: &IndexContext, path: MerklePath) -> Result<MerkleNode, IndexingError> {
+        let is_dir = path.absolute.is_dir();
+
         // Indexes its direct descendants for their hashes and paths
-        let children = if path.absolute.is_dir() {
+        let children = if is_dir {
             let read_dir = match fs::read_dir(&path.absolute) {
                 Ok(ok) => ok,
                 Err(err) => return Err(IndexingError::UnableToReadDir(path.absolute, err)),
             };
 
+            let index_entry = |entry: std::io::Result<fs::DirEntry>| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        return Err(IndexingError::UnableToReadDirEntry(
+                            path.absolute.clone(),
+                            err,
+                        ))
+                    }
+                };
+
+                let path = Self::child_path(context.root, entry.path())?;
+
+                let node = Self::index(context, path)?;
+
+                Ok(node)
+            };
+
             #[cfg(feature = "parallel")]
-            let read_dir = read_dir.par_bridge();
-
-            read_dir
-                .map(|entry| {
-                    let entry = match entry {
-                        Ok(entry) => entry,
-                        Err(err) => {
-                            return Err(IndexingError::UnableToReadDirEntry(
-                                path.absolute.clone(),
-                                err,
-                            ))
-                        }
-                    };
-
-                    #[cfg(not(feature = "camino"))]
-                    let absolute_path = entry.path();
-
-                    #[cfg(feature = "camino")]
-                    let absolute_path = camino::Utf8PathBuf::from_path_buf(entry.path())
-                        .map_err(IndexingError::PathIsNotValidUtf8)?;
-
-                    let relative_path = match absolute_path.strip_prefix(root) {
-                        Ok(relative_path) => relative_path.to_path_buf(),
-                        Err(err) => {
-                            return Err(IndexingError::UnableToStripRootPrefix(
-                                absolute_path,
-                                root.to_string(),
-                                err,
-                            ))
-                        }
-                    };
-
-                    let path = MerklePath::new(relative_path, absolute_path);
-
-                    let node = Self::index(root, path, hash_names, algorithm)?;
-
-                    Ok(node)
-                })
-                .collect::<Result<BTreeSet<MerkleNode>, IndexingError>>()?
+            let children = if context.parallel {
+                read_dir.par_bridge().map(index_entry).collect::<Result<BTreeSet<MerkleNode>, IndexingError>>()?
+            } else {
+                read_dir.map(index_entry).collect::<Result<BTreeSet<MerkleNode>, IndexingError>>()?
+            };
+
+            #[cfg(not(feature = "parallel"))]
+            let children = read_dir.map(index_entry).collect::<Result<BTreeSet<MerkleNode>, IndexingError>>()?;
+
+            children
         } else {
             BTreeSet::new()
         };
 
-        // Finds the node's contents hash
-        let contents_hash: Vec<u8> = if path.absolute.is_dir() {
-            let hashes: Vec<_> = children
-                .iter()
-                .map(|child| child.item.hash.as_slice())
-                .collect();
-
-            match algorithm.compute_merkle_hash(&hashes) {
-                Some(hash) => hash,
-                None => algorithm.compute_hash(b""),
+        #[cfg(feature = "bao")]
+        let mut outboard = None;
+        #[cfg(feature = "fastcdc")]
+        let mut chunks = Vec::new();
+        #[cfg(feature = "unixfs")]
+        let mut unixfs_entry = None;
+        #[cfg(feature = "bittorrent")]
+        let mut torrent = None;
+
+        // Finds the node's contents hashes
+        let (contents_hash, additional_contents_hashes) = if is_dir {
+            let contents_hash = Self::directory_hash(&children, context.algorithm, context.parallel);
+            let additional_contents_hashes = Self::additional_hashes(context.additional_algorithms, |algorithm| {
+                Self::directory_hash(&children, algorithm, context.parallel)
+            });
+
+            #[cfg(feature = "unixfs")]
+            if context.unixfs {
+                unixfs_entry = Some(Self::unixfs_directory(&children));
             }
+
+            (contents_hash, additional_contents_hashes)
         } else {
-            let file_bytes = match fs::read(&path.absolute) {
-                Ok(file_bytes) => file_bytes,
-                Err(err) => return Err(IndexingError::UnableToReadFile(path.absolute, err)),
+            let file_bytes = {
+                // Waits for its turn to read, if the number of concurrent reads is limited
+                // The permit only covers the read, so hashing is neither throttled nor blocks other readers
+                let _permit = context.read_limiter.as_ref().map(ReadLimiter::acquire);
+                match fs::read(&path.absolute) {
+                    Ok(file_bytes) => file_bytes,
+                    Err(err) => return Err(IndexingError::UnableToReadFile(path.absolute, err)),
+                }
             };
 
-            algorithm.compute_hash(&file_bytes)
+            let file_hashes = Self::file_hashes(context, &file_bytes);
+
+            #[cfg(feature = "fastcdc")]
+            {
+                chunks = file_hashes.chunks;
+            }
+
+            #[cfg(feature = "bao")]
+            if context.outboards {
+                outboard = Some(Outboard::new(&file_bytes));
+            }
+
+            #[cfg(feature = "unixfs")]
+            if context.unixfs {
+                unixfs_entry = Some(unixfs::file_entry(&file_bytes));
+            }
+
+            #[cfg(feature = "bittorrent")]
+            {
+                torrent = context.piece_length.map(|piece_length| bittorrent::file_pieces(&file_bytes, piece_length));
+            }
+
+            (file_hashes.contents_hash, file_hashes.additional_contents_hashes)
         };
 
-        // Check if names should be included in the hashing results and get the output hash
-        let hash: Vec<u8> = if hash_names {
-            // Gets the node path's name
-            let name = match path.absolute.file_name() {
-                None => return Err(IndexingError::UnableToReadFileName(path.absolute)),
-                Some(name) => name,
-            };
+        let node = Self::finish(
+            path,
+            (contents_hash, additional_contents_hashes),
+            children,
+            is_dir,
+            context.hash_names,
+            context.algorithm,
+            context.additional_algorithms,
+            context.parallel,
+        )?;
 
-            #[cfg(not(feature = "camino"))]
-            let name = match name.to_str() {
-                None => return Err(IndexingError::UnableToReadFileName(path.absolute)),
-                Some(name) => name,
-            };
+        #[cfg(feature = "bao")]
+        let node = node.with_outboard(outboard);
+
+        #[cfg(feature = "fastcdc")]
+        let node = node.with_chunks(chunks);
+
+        #[cfg(feature = "unixfs")]
+        let node = node.with_unixfs(unixfs_entry);
+
+        #[cfg(feature = "bittorrent")]
+        let node = node.with_torrent(torrent);
+
+        Ok(node)
+    }
+
+    /// Creates the merkle path of a directory entry
+    pub(crate) fn child_path(root: &str, entry_path: std::path::PathBuf) -> Result<MerklePath, IndexingError> {
+        #[cfg(not(feature = "camino"))]
+        let absolute_path = entry_path;
+
+        #[cfg(feature = "camino")]
+        let absolute_path = camino::Utf8PathBuf::from_path_buf(entry_path)
+            .map_err(IndexingError::PathIsNotValidUtf8)?;
+
+        let relative_path = match absolute_path.strip_prefix(root) {
+            Ok(relative_path) => relative_path.to_path_buf(),
+            Err(err) => {
+                return Err(IndexingError::UnableToStripRootPrefix(
+                    absolute_path,
+                    root.to_string(),
+                    err,
+                ))
+            }
+        };
 
-            // Create a hashing stack
-            algorithm.compute_hash_from_slices(name.as_bytes(), &contents_hash)
+        Ok(MerklePath::new(relative_path, absolute_path))
+    }
+
+    /// Finds the contents hash of a directory from the hashes of its children
+    pub(crate) fn directory_hash(children: &BTreeSet<MerkleNode>, algorithm: &Algorithm, parallel: bool) -> Digest {
+        let hashes: Vec<_> = children
+            .iter()
+            .filter_map(|child| child.item.hash_for(algorithm.kind()).copied())
+            .collect();
+
+        Self::directory_hash_of(&hashes, algorithm, parallel)
+    }
+
+    /// Finds the contents hash of a directory from the hashes of its children, in order
+    pub(crate) fn directory_hash_of(hashes: &[Digest], algorithm: &Algorithm, parallel: bool) -> Digest {
+        match algorithm.compute_merkle_hash_with(hashes, parallel) {
+            Some(hash) => hash,
+            None => algorithm.compute_hash(b""),
+        }
+    }
+
+    /// Hashes the bytes of a file once with every algorithm, splitting them into chunks if enabled
+    pub(crate) fn file_hashes(context: &IndexContext, file_bytes: &[u8]) -> FileHashes {
+        // Splits the file into content-defined chunks, if enabled
+        #[cfg(feature = "fastcdc")]
+        let chunk_ranges = context.chunking.map(|chunking| chunking.split(file_bytes)).unwrap_or_default();
+        #[cfg(not(feature = "fastcdc"))]
+        let chunk_ranges = Vec::new();
+
+        #[cfg_attr(not(feature = "fastcdc"), allow(unused_variables))]
+        let (contents_hash, chunk_hashes) = Self::file_hash(file_bytes, &chunk_ranges, context.algorithm, context.parallel);
+        let additional_contents_hashes = Self::additional_hashes(context.additional_algorithms, |algorithm| {
+            Self::file_hash(file_bytes, &chunk_ranges, algorithm, context.parallel).0
+        });
+
+        FileHashes {
+            contents_hash,
+            additional_contents_hashes,
+            #[cfg(feature = "fastcdc")]
+            chunks: Self::chunk_listing(&chunk_ranges, chunk_hashes),
+        }
+    }
+
+    /// Finds the contents hash of a file, the merkle hash of its chunk hashes if it was split into chunks
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    pub(crate) fn file_hash(
+        file_bytes: &[u8],
+        chunk_ranges: &[Range<usize>],
+        algorithm: &Algorithm,
+        parallel: bool,
+    ) -> (Digest, Vec<Digest>) {
+        let hash_chunk = |chunk_range: &Range<usize>| algorithm.compute_hash(&file_bytes[chunk_range.clone()]);
+
+        #[cfg(feature = "parallel")]
+        let chunk_hashes: Vec<_> = if parallel {
+            chunk_ranges.par_iter().map(hash_chunk).collect()
         } else {
-            contents_hash
+            chunk_ranges.iter().map(hash_chunk).collect()
         };
 
+        #[cfg(not(feature = "parallel"))]
+        let chunk_hashes: Vec<_> = chunk_ranges.iter().map(hash_chunk).collect();
+
+        match algorithm.compute_merkle_hash_with(&chunk_hashes, parallel) {
+            Some(hash) => (hash, chunk_hashes),
+            None => (algorithm.compute_hash(file_bytes), chunk_hashes),
+        }
+    }
+
+    /// Lists the chunks of a file from their ranges and hashes
+    #[cfg(feature = "fastcdc")]
+    pub(crate) fn chunk_listing(chunk_ranges: &[Range<usize>], chunk_hashes: Vec<Digest>) -> Vec<Chunk> {
+        chunk_ranges
+            .iter()
+            .zip(chunk_hashes)
+            .map(|(chunk_range, hash)| Chunk {
+                offset: chunk_range.start as u64,
+                len: chunk_range.len() as u64,
+                hash,
+            })
+            .collect()
+    }
+
+    /// Attaches the chunks of a file to its node
+    #[cfg(feature = "fastcdc")]
+    pub(crate) fn with_chunks(self, chunks: Vec<Chunk>) -> MerkleNode {
+        let item = MerkleItem { chunks, ..self.item };
+        MerkleNode { item, ..self }
+    }
+
+    /// Builds the UnixFS node of a directory from the UnixFS identities of its children
+    #[cfg(feature = "unixfs")]
+    pub(crate) fn unixfs_directory(children: &BTreeSet<MerkleNode>) -> UnixFsEntry {
+        unixfs::directory_entry(children.iter().filter_map(|child| {
+            let name = child.item.path.relative.file_name()?;
+            #[cfg(feature = "camino")]
+            let name = name.as_bytes();
+            #[cfg(not(feature = "camino"))]
+            let name = name.as_encoded_bytes();
+            Some((name, child.item.unixfs.as_ref()?))
+        }))
+    }
+
+    /// Attaches the UnixFS identity of a file or directory to its node
+    #[cfg(feature = "unixfs")]
+    pub(crate) fn with_unixfs(self, unixfs: Option<UnixFsEntry>) -> MerkleNode {
+        let item = MerkleItem { unixfs, ..self.item };
+        MerkleNode { item, ..self }
+    }
+
+    /// Attaches the BitTorrent v2 pieces of a file to its node
+    #[cfg(feature = "bittorrent")]
+    pub(crate) fn with_torrent(self, torrent: Option<TorrentFile>) -> MerkleNode {
+        let item = MerkleItem { torrent, ..self.item };
+        MerkleNode { item, ..self }
+    }
+
+    /// Attaches the outboard of a file to its node
+    #[cfg(feature = "bao")]
+    pub(crate) fn with_outboard(self, outboard: Option<Outboard>) -> MerkleNode {
+        let item = MerkleItem { outboard, ..self.item };
+        MerkleNode { item, ..self }
+    }
+
+    /// Computes a hash with each of the additional algorithms
+    pub(crate) fn additional_hashes(
+        additional_algorithms: &[Algorithm],
+        hash: impl Fn(&Algorithm) -> Digest,
+    ) -> BTreeMap<AlgorithmKind, Digest> {
+        additional_algorithms
+            .iter()
+            .map(|algorithm| (algorithm.kind(), hash(algorithm)))
+            .collect()
+    }
+
+    /// Creates a node from its contents hashes and children, including its name in the hashes if needed
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(not(feature = "retain"), allow(unused_variables))]
+    pub(crate) fn finish(
+        path: MerklePath,
+        (contents_hash, additional_contents_hashes): (Digest, BTreeMap<AlgorithmKind, Digest>),
+        children: BTreeSet<MerkleNode>,
+        is_dir: bool,
+        hash_names: bool,
+        algorithm: &Algorithm,
+        additional_algorithms: &[Algorithm],
+        parallel: bool,
+    ) -> Result<MerkleNode, IndexingError> {
+        let (hash, additional_hashes) = Self::named_hashes(
+            &path,
+            (contents_hash, additional_contents_hashes),
+            hash_names,
+            algorithm,
+            additional_algorithms,
+        )?;
+
         #[cfg(feature = "retain")]
         // Get the direct descendant paths
-        let children_paths = Self::get_children_paths(&children);
+        let children_paths = Self::get_children_paths(&children, parallel);
 
         // Returns the newly created node with its data
 
@@ -162,19 +448,88 @@ impl MerkleNode {
         #[cfg(not(feature = "retain"))]
         let item = MerkleItem::new(path, hash);
 
+        let item = MerkleItem {
+            additional_hashes,
+            is_dir,
+            ..item
+        };
+
         let node = MerkleNode { item, children };
 
         Ok(node)
     }
 
+    /// Includes the name of a node in its contents hashes, if names are hashed
+    pub(crate) fn named_hashes(
+        path: &MerklePath,
+        (contents_hash, additional_contents_hashes): (Digest, BTreeMap<AlgorithmKind, Digest>),
+        hash_names: bool,
+        algorithm: &Algorithm,
+        additional_algorithms: &[Algorithm],
+    ) -> Result<(Digest, BTreeMap<AlgorithmKind, Digest>), IndexingError> {
+        if !hash_names {
+            return Ok((contents_hash, additional_contents_hashes));
+        }
+
+        // Gets the node path's name
+        let name = match path.absolute.file_name() {
+            None => return Err(IndexingError::UnableToReadFileName(path.absolute.clone())),
+            Some(name) => name,
+        };
+
+        #[cfg(not(feature = "camino"))]
+        let name = match name.to_str() {
+            None => return Err(IndexingError::UnableToReadFileName(path.absolute.clone())),
+            Some(name) => name,
+        };
+
+        // Create a hashing stack
+        let hash = algorithm.compute_hash_from_slices(name.as_bytes(), contents_hash.as_ref());
+        let additional_hashes = Self::additional_hashes(additional_algorithms, |algorithm| {
+            let contents_hash = &additional_contents_hashes[&algorithm.kind()];
+            algorithm.compute_hash_from_slices(name.as_bytes(), contents_hash.as_ref())
+        });
+        Ok((hash, additional_hashes))
+    }
+
     #[cfg(feature = "retain")]
-    fn get_children_paths(children: &BTreeSet<MerkleNode>) -> BTreeSet<MerklePath> {
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    fn get_children_paths(children: &BTreeSet<MerkleNode>, parallel: bool) -> BTreeSet<MerklePath> {
         #[cfg(feature = "parallel")]
-        let children_iter = children.par_iter();
+        if parallel {
+            return children.par_iter().map(|child| child.item.path.clone()).collect();
+        }
 
-        #[cfg(not(feature = "parallel"))]
-        let children_iter = children.iter();
+        children.iter().map(|child| child.item.path.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fixture::{fixture, path};
+    use crate::MerkleTree;
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn limited_reads_do_not_starve_parallel_hashing() {
+        let large = vec![7u8; 4 << 20];
+        let dir = fixture(&[("a", &large), ("b", &large), ("c/d", &large), ("c/e", b"small")]);
+        let thread_pool = std::sync::Arc::new(rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+
+        let unlimited = MerkleTree::builder(path(&dir)).build().unwrap();
+        let limited = MerkleTree::builder(path(&dir))
+            .max_concurrent_reads(1)
+            .thread_pool(thread_pool)
+            .build()
+            .unwrap();
+        assert_eq!(limited.root.item.hash, unlimited.root.item.hash);
+    }
 
-        children_iter.map(|child| child.item.path.clone()).collect()
+    #[test]
+    fn limited_reads_match_unlimited_reads() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d/", b"")]);
+        let unlimited = MerkleTree::builder(path(&dir)).build().unwrap();
+        let limited = MerkleTree::builder(path(&dir)).max_concurrent_reads(1).build().unwrap();
+        assert!(limited.iter().eq(unlimited.iter()));
     }
 }
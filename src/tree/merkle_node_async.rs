@@ -0,0 +1,269 @@
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::task::{JoinError, JoinSet};
+
+use crate::components::merkle_path::MerklePath;
+#[cfg(feature = "bao")]
+use crate::components::outboard::Outboard;
+use crate::error::IndexingError;
+use crate::tree::merkle_node::{IndexContext, MerkleNode};
+use crate::utils::algorithm::Algorithm;
+#[cfg(feature = "bittorrent")]
+use crate::utils::bittorrent::{self, PieceLength};
+#[cfg(feature = "fastcdc")]
+use crate::utils::chunking::ChunkParams;
+#[cfg(feature = "unixfs")]
+use crate::utils::unixfs;
+
+/// Shared state of a single asynchronous indexing run
+struct AsyncIndexer {
+    root: String,
+    hash_names: bool,
+    algorithm: Algorithm,
+    additional_algorithms: Vec<Algorithm>,
+    #[cfg(feature = "bao")]
+    outboards: bool,
+    #[cfg(feature = "fastcdc")]
+    chunking: Option<ChunkParams>,
+    #[cfg(feature = "unixfs")]
+    unixfs: bool,
+    #[cfg(feature = "bittorrent")]
+    piece_length: Option<PieceLength>,
+    /// Limits how many files and directories are read at the same time
+    permits: Semaphore,
+}
+
+type IndexFuture = Pin<Box<dyn Future<Output = Result<MerkleNode, IndexingError>> + Send>>;
+
+impl MerkleNode {
+    /// Creates a new root node without blocking the async runtime
+    ///
+    /// Directories are walked and files are read with `tokio::fs`, at most `max_concurrent_reads` at a time,
+    /// while hashing happens on the blocking thread pool.
+    /// Dropping the returned future cancels all outstanding work.
+    pub async fn root_async(
+        root: &str,
+        hash_names: bool,
+        algorithm: Algorithm,
+        max_concurrent_reads: usize,
+    ) -> Result<Self, IndexingError> {
+        let context = IndexContext {
+            root,
+            hash_names,
+            algorithm: &algorithm,
+            additional_algorithms: &[],
+            #[cfg(feature = "bao")]
+            outboards: false,
+            #[cfg(feature = "fastcdc")]
+            chunking: None,
+            #[cfg(feature = "unixfs")]
+            unixfs: false,
+            #[cfg(feature = "bittorrent")]
+            piece_length: None,
+            parallel: false,
+            read_limiter: None,
+        };
+
+        Self::root_async_with(&context, max_concurrent_reads).await
+    }
+
+    /// Creates a new root node without blocking the async runtime, with the given indexing settings
+    ///
+    /// Reads are limited by `max_concurrent_reads` alone, the parallelism settings of the context are not used.
+    pub(crate) async fn root_async_with(
+        context: &IndexContext<'_>,
+        max_concurrent_reads: usize,
+    ) -> Result<Self, IndexingError> {
+        let indexer = Arc::new(AsyncIndexer {
+            root: context.root.to_string(),
+            hash_names: context.hash_names,
+            algorithm: context.algorithm.clone(),
+            additional_algorithms: context.additional_algorithms.to_vec(),
+            #[cfg(feature = "bao")]
+            outboards: context.outboards,
+            #[cfg(feature = "fastcdc")]
+            chunking: context.chunking,
+            #[cfg(feature = "unixfs")]
+            unixfs: context.unixfs,
+            #[cfg(feature = "bittorrent")]
+            piece_length: context.piece_length,
+            permits: Semaphore::new(max_concurrent_reads.max(1)),
+        });
+
+        let path = Self::root_path(context.root);
+
+        Self::index_async(indexer, path).await
+    }
+
+    /// Asynchronously indexes a new node and all of its descendants, mirroring the synchronous indexing
+    fn index_async(indexer: Arc<AsyncIndexer>, path: MerklePath) -> IndexFuture {
+        Box::pin(async move {
+            let is_dir = tokio::fs::metadata(&path.absolute)
+                .await
+                .map(|metadata| metadata.is_dir())
+                .unwrap_or(false);
+
+            if !is_dir {
+                // Holds the permit until the file is hashed, so at most that many files are held in memory
+                let _permit = indexer.permits.acquire().await.map_err(|_| IndexingError::Cancelled)?;
+
+                let file_bytes = match tokio::fs::read(&path.absolute).await {
+                    Ok(file_bytes) => file_bytes,
+                    Err(err) => return Err(IndexingError::UnableToReadFile(path.absolute, err)),
+                };
+
+                let task_indexer = indexer.clone();
+                return tokio::task::spawn_blocking(move || {
+                    let additional_algorithms = &task_indexer.additional_algorithms;
+
+                    #[cfg(feature = "fastcdc")]
+                    let chunk_ranges = task_indexer.chunking.map(|chunking| chunking.split(&file_bytes)).unwrap_or_default();
+                    #[cfg(not(feature = "fastcdc"))]
+                    let chunk_ranges = Vec::new();
+
+                    #[cfg_attr(not(feature = "fastcdc"), allow(unused_variables))]
+                    let (contents_hash, chunk_hashes) =
+                        Self::file_hash(&file_bytes, &chunk_ranges, &task_indexer.algorithm, false);
+                    let additional_contents_hashes = Self::additional_hashes(additional_algorithms, |algorithm| {
+                        Self::file_hash(&file_bytes, &chunk_ranges, algorithm, false).0
+                    });
+                    let node = Self::finish(
+                        path,
+                        (contents_hash, additional_contents_hashes),
+                        BTreeSet::new(),
+                        false,
+                        task_indexer.hash_names,
+                        &task_indexer.algorithm,
+                        additional_algorithms,
+                        false,
+                    )?;
+
+                    #[cfg(feature = "bao")]
+                    let node = node.with_outboard(task_indexer.outboards.then(|| Outboard::new(&file_bytes)));
+
+                    #[cfg(feature = "fastcdc")]
+                    let node = node.with_chunks(Self::chunk_listing(&chunk_ranges, chunk_hashes));
+
+                    #[cfg(feature = "unixfs")]
+                    let node = node.with_unixfs(task_indexer.unixfs.then(|| unixfs::file_entry(&file_bytes)));
+
+                    #[cfg(feature = "bittorrent")]
+                    let node = node.with_torrent(
+                        task_indexer.piece_length.map(|piece_length| bittorrent::file_pieces(&file_bytes, piece_length)),
+                    );
+
+                    Ok(node)
+                })
+                .await
+                .map_err(Self::join_error)?;
+            }
+
+            let entries = {
+                let _permit = indexer.permits.acquire().await.map_err(|_| IndexingError::Cancelled)?;
+
+                let mut read_dir = match tokio::fs::read_dir(&path.absolute).await {
+                    Ok(read_dir) => read_dir,
+                    Err(err) => return Err(IndexingError::UnableToReadDir(path.absolute, err)),
+                };
+
+                let mut entries = Vec::new();
+                loop {
+                    match read_dir.next_entry().await {
+                        Ok(Some(entry)) => entries.push(entry.path()),
+                        Ok(None) => break,
+                        Err(err) => return Err(IndexingError::UnableToReadDirEntry(path.absolute, err)),
+                    }
+                }
+                entries
+            };
+
+            // Dropping the set aborts the remaining tasks, on errors and on cancellation alike
+            let mut tasks = JoinSet::new();
+            for entry in entries {
+                let child_path = Self::child_path(&indexer.root, entry)?;
+                tasks.spawn(Self::index_async(indexer.clone(), child_path));
+            }
+
+            let mut children = BTreeSet::new();
+            while let Some(child) = tasks.join_next().await {
+                children.insert(child.map_err(Self::join_error)??);
+            }
+
+            let task_indexer = indexer.clone();
+            tokio::task::spawn_blocking(move || {
+                let additional_algorithms = &task_indexer.additional_algorithms;
+                let contents_hash = Self::directory_hash(&children, &task_indexer.algorithm, false);
+                let additional_contents_hashes =
+                    Self::additional_hashes(additional_algorithms, |algorithm| Self::directory_hash(&children, algorithm, false));
+
+                #[cfg(feature = "unixfs")]
+                let unixfs_entry = task_indexer.unixfs.then(|| Self::unixfs_directory(&children));
+
+                let node = Self::finish(
+                    path,
+                    (contents_hash, additional_contents_hashes),
+                    children,
+                    true,
+                    task_indexer.hash_names,
+                    &task_indexer.algorithm,
+                    additional_algorithms,
+                    false,
+                )?;
+
+                #[cfg(feature = "unixfs")]
+                let node = node.with_unixfs(unixfs_entry);
+
+                Ok(node)
+            })
+            .await
+            .map_err(Self::join_error)?
+        })
+    }
+
+    /// Propagates panics of indexing tasks, reporting any other failure as a cancellation
+    fn join_error(error: JoinError) -> IndexingError {
+        match error.try_into_panic() {
+            Ok(panic) => std::panic::resume_unwind(panic),
+            Err(_) => IndexingError::Cancelled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fixture::{fixture, path};
+    use crate::MerkleTree;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn matches_the_sync_build() {
+        let dir = fixture(&[("a/b", b"one"), ("a/c/d", b"two"), ("e", b""), ("f/", b"")]);
+        for hash_names in [false, true] {
+            let sync = MerkleTree::builder(path(&dir)).hash_names(hash_names).build().unwrap();
+            let async_tree = block_on(
+                MerkleTree::builder(path(&dir))
+                    .hash_names(hash_names)
+                    .max_concurrent_reads(1)
+                    .build_async(),
+            )
+            .unwrap();
+            assert_eq!(async_tree.root.item.hash, sync.root.item.hash);
+            assert!(async_tree.iter().eq(sync.iter()));
+        }
+    }
+
+    #[test]
+    fn reports_missing_roots() {
+        let dir = fixture(&[]);
+        let missing = dir.path().join("missing");
+        let result = block_on(MerkleTree::builder(missing.to_str().unwrap()).build_async());
+        assert!(result.is_err());
+    }
+}
@@ -1,14 +1,38 @@
+#[cfg(feature = "unixfs")]
+use crate::components::cid::Cid;
+use crate::components::digest::Digest;
 use crate::components::merkle_item::MerkleItem;
+use crate::components::merkle_path::MerklePath;
+use crate::iters::merkle_node_bfs_iter::MerkleNodeBfsIter;
+use crate::iters::merkle_node_depth_iter::MerkleNodeDepthIter;
+use crate::iters::merkle_node_into_bfs_iter::MerkleNodeIntoBfsIter;
+use crate::iters::merkle_node_into_depth_iter::MerkleNodeIntoDepthIter;
 use crate::iters::merkle_node_into_iter::MerkleNodeIntoIter;
+#[cfg(feature = "parallel")]
+use crate::iters::merkle_node_into_par_iter::MerkleNodeIntoParIter;
+use crate::iters::merkle_node_into_post_order_iter::MerkleNodeIntoPostOrderIter;
 use crate::iters::merkle_node_iter::MerkleNodeIter;
+#[cfg(feature = "parallel")]
+use crate::iters::merkle_node_par_iter::MerkleNodeParIter;
+use crate::iters::merkle_node_post_order_iter::MerkleNodePostOrderIter;
+use crate::iters::merkle_node_walk::WalkControl;
 use crate::tree::merkle_node::MerkleNode;
 use crate::tree::merkle_tree_builder::MerkleTreeBuilder;
-use crate::utils::algorithm::Algorithm;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+use crate::utils::chunking::ChunkParams;
 
 /// Represents an indexed directory tree
 #[cfg_attr(feature = "bincode", derive(bincode::Decode, bincode::Encode))]
 pub struct MerkleTree {
     pub root: MerkleNode,
+    /// The hashing algorithm the tree was built with
+    pub algorithm: Algorithm,
+    /// Other hashing algorithms the tree was built with, their hashes are kept alongside the primary ones
+    pub additional_algorithms: Vec<Algorithm>,
+    /// The chunk sizes files were split into, if they were split at all
+    pub chunking: Option<ChunkParams>,
+    /// Whether names were included in the hashes of files and directories
+    pub hash_names: bool,
 }
 
 impl MerkleTree {
@@ -16,18 +40,122 @@ impl MerkleTree {
     ///
     /// - Default hash_names is **false**
     /// - Default algorithm is **blake3**
+    /// - Default outboards are **false**
+    /// - Default chunking is **none**
+    /// - Default unixfs is **false**
+    /// - Default BitTorrent pieces are **none**
+    /// - Default concurrent reads are **unlimited**
+    /// - Default parallel is **true**, on the **global** rayon thread pool
     pub fn builder(root_absolute_path: impl AsRef<str>) -> MerkleTreeBuilder {
         let absolute_root_path = root_absolute_path.as_ref().to_owned();
         MerkleTreeBuilder {
             absolute_root_path,
             hash_names: false,
             algorithm: Algorithm::default(),
+            additional_algorithms: Vec::new(),
+            #[cfg(feature = "bao")]
+            outboards: false,
+            #[cfg(feature = "fastcdc")]
+            chunking: None,
+            #[cfg(feature = "unixfs")]
+            unixfs: false,
+            #[cfg(feature = "bittorrent")]
+            piece_length: None,
+            max_concurrent_reads: None,
+            #[cfg(feature = "parallel")]
+            parallel: true,
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
         }
     }
+    /// Returns the root hash computed with the given kind of algorithm, if the tree was built with it
+    pub fn root_hash_for(&self, algorithm: AlgorithmKind) -> Option<&Digest> {
+        self.root.item.hash_for(algorithm)
+    }
+
+    /// Returns the IPFS content identifier of the root, if the tree was built with UnixFS
+    #[cfg(feature = "unixfs")]
+    pub fn root_cid(&self) -> Option<&Cid> {
+        self.root.item.unixfs.as_ref().map(|unixfs| &unixfs.cid)
+    }
+
     /// Returns an iterator over each file and directory in the tree
-    pub fn iter(&self) -> MerkleNodeIter {
+    pub fn iter(&self) -> MerkleNodeIter<'_> {
         self.root.iter()
     }
+
+    /// Returns a parallel iterator over each file and directory in the tree
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> MerkleNodeParIter<'_> {
+        self.root.par_iter()
+    }
+
+    /// Returns an iterator over each file and directory in the tree with its depth, the root being at depth **0**
+    pub fn iter_with_depth(&self) -> MerkleNodeDepthIter<'_> {
+        self.root.iter_with_depth()
+    }
+
+    /// Returns an iterator over each file and directory in the tree, children before their parents
+    pub fn iter_post_order(&self) -> MerkleNodePostOrderIter<'_> {
+        self.root.iter_post_order()
+    }
+
+    /// Returns an iterator over each file and directory in the tree, level by level
+    pub fn iter_bfs(&self) -> MerkleNodeBfsIter<'_> {
+        self.root.iter_bfs()
+    }
+
+    /// Visits each file and directory in the tree in pre-order with its depth, letting the callback skip subtrees
+    pub fn walk(&self, visit: impl FnMut(usize, &MerkleItem) -> WalkControl) {
+        self.root.walk(visit)
+    }
+
+    /// Consumes the tree into an iterator over each file and directory with its depth, the root being at depth **0**
+    pub fn into_iter_with_depth(self) -> MerkleNodeIntoDepthIter {
+        self.root.into_iter_with_depth()
+    }
+
+    /// Consumes the tree into an iterator over each file and directory, children before their parents
+    pub fn into_iter_post_order(self) -> MerkleNodeIntoPostOrderIter {
+        self.root.into_iter_post_order()
+    }
+
+    /// Consumes the tree into an iterator over each file and directory, level by level
+    pub fn into_iter_bfs(self) -> MerkleNodeIntoBfsIter {
+        self.root.into_iter_bfs()
+    }
+
+    /// Hashes the contents of a file the way it was hashed when the tree was built
+    #[cfg(any(feature = "oci", feature = "apply"))]
+    pub(crate) fn file_item_hash(&self, item: &MerkleItem, contents: &[u8]) -> Digest {
+        self.file_path_hash(&item.path, contents)
+    }
+
+    /// Hashes the contents of a file at a path the way it was hashed when the tree was built
+    pub(crate) fn file_path_hash(&self, path: &MerklePath, contents: &[u8]) -> Digest {
+        self.named_file_hash(path, self.file_contents_hash(contents))
+    }
+
+    /// Hashes the contents of a file the way it was hashed when the tree was built, leaving out its name
+    pub(crate) fn file_contents_hash(&self, contents: &[u8]) -> Digest {
+        #[cfg(feature = "fastcdc")]
+        let chunk_ranges = self.chunking.map(|chunking| chunking.split(contents)).unwrap_or_default();
+        #[cfg(not(feature = "fastcdc"))]
+        let chunk_ranges = Vec::new();
+
+        MerkleNode::file_hash(contents, &chunk_ranges, &self.algorithm, false).0
+    }
+
+    /// Includes the name of a file at a path in its contents hash, if the tree hashes names
+    pub(crate) fn named_file_hash(&self, path: &MerklePath, contents_hash: Digest) -> Digest {
+        if !self.hash_names {
+            return contents_hash;
+        }
+
+        let absolute: &std::path::Path = path.absolute.as_ref();
+        let name = absolute.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        self.algorithm.compute_hash_from_slices(name.as_bytes(), contents_hash.as_ref())
+    }
 }
 
 impl<'a> IntoIterator for &'a MerkleTree {
@@ -49,3 +177,116 @@ impl IntoIterator for MerkleTree {
         self.root.into_iter()
     }
 }
+
+#[cfg(feature = "parallel")]
+impl<'a> rayon::iter::IntoParallelIterator for &'a MerkleTree {
+    type Iter = MerkleNodeParIter<'a>;
+
+    type Item = &'a MerkleItem;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.par_iter()
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl rayon::iter::IntoParallelIterator for MerkleTree {
+    type Iter = MerkleNodeIntoParIter;
+
+    type Item = MerkleItem;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.root.into_par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::components::merkle_item::MerkleItem;
+    use crate::iters::merkle_node_walk::WalkControl;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path};
+
+    fn tree() -> MerkleTree {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d/e", b"three"), ("f/", b"")]);
+        MerkleTree::builder(path(&dir)).build().unwrap()
+    }
+
+    fn name(item: &MerkleItem) -> &str {
+        let relative: &Path = item.path.relative.as_ref();
+        relative.to_str().unwrap()
+    }
+
+    #[test]
+    fn iterates_in_each_order() {
+        let tree = tree();
+        let pre_order = ["", "a", "b", "b/c", "b/d", "b/d/e", "f"];
+        let post_order = ["a", "b/c", "b/d/e", "b/d", "b", "f", ""];
+        let bfs = ["", "a", "b", "f", "b/c", "b/d", "b/d/e"];
+
+        assert!(tree.iter().map(name).eq(pre_order));
+        assert!(tree.iter_post_order().map(name).eq(post_order));
+        assert!(tree.iter_bfs().map(name).eq(bfs));
+
+        let with_depth: Vec<_> = tree.iter_with_depth().map(|(depth, item)| (depth, name(item))).collect();
+        assert_eq!(
+            with_depth,
+            [(0, ""), (1, "a"), (1, "b"), (2, "b/c"), (2, "b/d"), (3, "b/d/e"), (1, "f")]
+        );
+    }
+
+    #[test]
+    fn owned_iterators_match_borrowed_ones() {
+        let tree = tree();
+        let post_order: Vec<_> = tree.iter_post_order().cloned().collect();
+        let bfs: Vec<_> = tree.iter_bfs().cloned().collect();
+        let with_depth: Vec<_> = tree.iter_with_depth().map(|(depth, item)| (depth, item.clone())).collect();
+
+        assert!(tree.root.clone().into_iter_post_order().eq(post_order));
+        assert!(tree.root.clone().into_iter_bfs().eq(bfs));
+        assert!(tree.into_iter_with_depth().eq(with_depth));
+    }
+
+    #[test]
+    fn node_depths_start_at_the_node() {
+        let tree = tree();
+        let node = tree.root.children.iter().find(|child| name(&child.item) == "b").unwrap();
+        let depths: Vec<_> = node.iter_with_depth().map(|(depth, item)| (depth, name(item))).collect();
+        assert_eq!(depths, [(0, "b"), (1, "b/c"), (1, "b/d"), (2, "b/d/e")]);
+    }
+
+    #[test]
+    fn walks_skip_subtrees_and_stop() {
+        let tree = tree();
+
+        let mut visited = Vec::new();
+        tree.walk(|depth, item| {
+            visited.push((depth, name(item).to_owned()));
+            match name(item) {
+                "b" => WalkControl::SkipChildren,
+                _ => WalkControl::Continue,
+            }
+        });
+        let visited: Vec<_> = visited.iter().map(|(depth, name)| (*depth, name.as_str())).collect();
+        assert_eq!(visited, [(0, ""), (1, "a"), (1, "b"), (1, "f")]);
+
+        let mut visited = Vec::new();
+        tree.walk(|_, item| {
+            visited.push(name(item).to_owned());
+            match name(item) {
+                "b/c" => WalkControl::Stop,
+                _ => WalkControl::Continue,
+            }
+        });
+        assert_eq!(visited, ["", "a", "b", "b/c"]);
+
+        let mut visits = 0;
+        tree.walk(|_, _| {
+            visits += 1;
+            WalkControl::SkipChildren
+        });
+        assert_eq!(visits, 1);
+    }
+}
@@ -1,7 +1,20 @@
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
 use crate::error::IndexingError;
 use crate::MerkleTree;
-use crate::tree::merkle_node::MerkleNode;
+use crate::tree::compact_tree::CompactTree;
+use crate::tree::merkle_node::{IndexContext, MerkleNode};
 use crate::utils::algorithm::Algorithm;
+#[cfg(feature = "bittorrent")]
+use crate::utils::bittorrent::PieceLength;
+#[cfg(feature = "fastcdc")]
+use crate::utils::chunking::ChunkParams;
+use crate::utils::read_limiter::ReadLimiter;
+
+/// Default number of files and directories read at the same time by asynchronous builds
+#[cfg(feature = "async")]
+pub const DEFAULT_ASYNC_CONCURRENT_READS: usize = 64;
 
 /// Utility builder pattern
 pub struct MerkleTreeBuilder {
@@ -11,6 +24,28 @@ pub struct MerkleTreeBuilder {
     pub(crate) hash_names: bool,
     /// Which hashing algorithm to use, default is blake3
     pub(crate) algorithm: Algorithm,
+    /// Which other hashing algorithms to compute in the same traversal, default is none
+    pub(crate) additional_algorithms: Vec<Algorithm>,
+    /// Whether to compute the BLAKE3 outboards of files, default is false
+    #[cfg(feature = "bao")]
+    pub(crate) outboards: bool,
+    /// Which chunk sizes to split files into, default is no chunking
+    #[cfg(feature = "fastcdc")]
+    pub(crate) chunking: Option<ChunkParams>,
+    /// Whether to compute the IPFS UnixFS identities of files and directories, default is false
+    #[cfg(feature = "unixfs")]
+    pub(crate) unixfs: bool,
+    /// Which piece length to compute the BitTorrent v2 pieces of files with, default is none
+    #[cfg(feature = "bittorrent")]
+    pub(crate) piece_length: Option<PieceLength>,
+    /// How many files can be read at the same time, default depends on the build
+    pub(crate) max_concurrent_reads: Option<usize>,
+    /// Whether to use multiple threads, default is true
+    #[cfg(feature = "parallel")]
+    pub(crate) parallel: bool,
+    /// Which thread pool to use, default is the global rayon pool
+    #[cfg(feature = "parallel")]
+    pub(crate) thread_pool: Option<Arc<rayon::ThreadPool>>,
 }
 
 impl MerkleTreeBuilder {
@@ -26,9 +61,249 @@ impl MerkleTreeBuilder {
         self
     }
 
+    /// Sets several hashing algorithms to compute in a single traversal, the first one becomes the primary
+    ///
+    /// Files are read once and fed to every algorithm, repeated algorithms are ignored.
+    /// Hashes are told apart by their kind, so building fails with [`IndexingError::ConflictingAlgorithms`]
+    /// if two algorithms share a kind with different parameters, such as two output lengths or two keys.
+    /// Without any algorithm the default **blake3** is used.
+    pub fn algorithms(mut self, algorithms: impl IntoIterator<Item = Algorithm>) -> Self {
+        let mut algorithms = algorithms.into_iter();
+        self.algorithm = algorithms.next().unwrap_or_default();
+        self.additional_algorithms = Vec::new();
+        for algorithm in algorithms {
+            if algorithm != self.algorithm && !self.additional_algorithms.contains(&algorithm) {
+                self.additional_algorithms.push(algorithm);
+            }
+        }
+        self
+    }
+
+    /// Sets whether to compute the BLAKE3 outboard of every file, default is **false**
+    ///
+    /// Outboards are kept on the items of files and allow proving byte ranges without rehashing whole files.
+    #[cfg(feature = "bao")]
+    pub fn outboards(mut self, outboards: bool) -> Self {
+        self.outboards = outboards;
+        self
+    }
+
+    /// Sets the chunk sizes to split every file into with FastCDC content-defined chunking, default is **none**
+    ///
+    /// The chunks become leaves below their files, files are hashed as the merkle hash of their chunk hashes
+    /// and the chunks are listed on their items.
+    #[cfg(feature = "fastcdc")]
+    pub fn chunking(mut self, chunking: ChunkParams) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+
+    /// Sets whether to compute the IPFS UnixFS identity of every file and directory, default is **false**
+    ///
+    /// Reproduces `ipfs add -r --hidden --cid-version=1 --raw-leaves`: fixed-size 256 KiB chunks, raw leaves,
+    /// the balanced layout and dag-pb directories, so the root CID matches the one printed by kubo.
+    /// Large directories are never sharded, symbolic links are followed rather than stored as links.
+    #[cfg(feature = "unixfs")]
+    pub fn unixfs(mut self, unixfs: bool) -> Self {
+        self.unixfs = unixfs;
+        self
+    }
+
+    /// Sets the piece length to compute the BitTorrent v2 pieces of every file with, default is **none**
+    ///
+    /// Every file gets its pieces root and piece layer, which [`MerkleTree::to_torrent`] turns into a `.torrent` file.
+    #[cfg(feature = "bittorrent")]
+    pub fn bittorrent(mut self, piece_length: PieceLength) -> Self {
+        self.piece_length = Some(piece_length);
+        self
+    }
+
+    /// Sets how many files can be read at the same time,
+    /// default is **unlimited** for [`MerkleTreeBuilder::build`] and **64** for asynchronous builds
+    pub fn max_concurrent_reads(mut self, max_concurrent_reads: usize) -> Self {
+        self.max_concurrent_reads = Some(max_concurrent_reads);
+        self
+    }
+
+    /// Sets whether to read and hash using multiple threads, default is **true**
+    ///
+    /// When disabled the whole tree is indexed on the calling thread.
+    #[cfg(feature = "parallel")]
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Sets the rayon thread pool to index the tree on, default is the **global** pool
+    #[cfg(feature = "parallel")]
+    pub fn thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
     /// Builds the hash tree by indexing all of its descendants
     pub fn build(self) -> Result<MerkleTree, IndexingError> {
-        let root = MerkleNode::root(&self.absolute_root_path, self.hash_names, self.algorithm)?;
-        Ok(MerkleTree { root })
+        self.check_algorithms()?;
+        #[cfg(feature = "parallel")]
+        let parallel = self.parallel;
+        #[cfg(not(feature = "parallel"))]
+        let parallel = false;
+
+        let context = self.context(parallel, self.max_concurrent_reads.map(ReadLimiter::new));
+
+        #[cfg(feature = "parallel")]
+        let root = match (&self.thread_pool, parallel) {
+            (Some(thread_pool), true) => thread_pool.install(|| MerkleNode::root_with(&context))?,
+            _ => MerkleNode::root_with(&context)?,
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let root = MerkleNode::root_with(&context)?;
+
+        Ok(self.into_tree(root))
+    }
+
+    /// Builds the hash tree on a tokio runtime, producing the same hashes as [`MerkleTreeBuilder::build`]
+    ///
+    /// Dropping the returned future cancels the build.
+    #[cfg(feature = "async")]
+    pub async fn build_async(self) -> Result<MerkleTree, IndexingError> {
+        self.check_algorithms()?;
+        let max_concurrent_reads = self.max_concurrent_reads.unwrap_or(DEFAULT_ASYNC_CONCURRENT_READS);
+        let root = MerkleNode::root_async_with(&self.context(false, None), max_concurrent_reads).await?;
+        Ok(self.into_tree(root))
+    }
+
+    /// Builds a compact tree by indexing straight into its arenas, without building a merkle tree first
+    ///
+    /// Produces the same hashes and chunk listings as [`MerkleTreeBuilder::build`].
+    /// Compact trees do not keep outboards, UnixFS identities or BitTorrent pieces, so they are not computed.
+    /// Trees too large for the arenas of a compact tree fail with [`IndexingError::CompactTree`].
+    pub fn build_compact(self) -> Result<CompactTree, IndexingError> {
+        self.check_algorithms()?;
+        #[cfg(feature = "parallel")]
+        let parallel = self.parallel;
+        #[cfg(not(feature = "parallel"))]
+        let parallel = false;
+
+        let context = self.context(parallel, self.max_concurrent_reads.map(ReadLimiter::new));
+
+        #[cfg(feature = "parallel")]
+        if let (Some(thread_pool), true) = (&self.thread_pool, parallel) {
+            return thread_pool.install(|| CompactTree::index_with(&context));
+        }
+
+        CompactTree::index_with(&context)
+    }
+
+    /// Checks that no two algorithms share a kind, as their hashes would be kept under the same one
+    fn check_algorithms(&self) -> Result<(), IndexingError> {
+        let mut kinds = vec![self.algorithm.kind()];
+        for algorithm in &self.additional_algorithms {
+            if kinds.contains(&algorithm.kind()) {
+                return Err(IndexingError::ConflictingAlgorithms(algorithm.kind()));
+            }
+            kinds.push(algorithm.kind());
+        }
+        Ok(())
+    }
+
+    /// Collects the indexing settings shared by every node
+    fn context(&self, parallel: bool, read_limiter: Option<ReadLimiter>) -> IndexContext<'_> {
+        IndexContext {
+            root: &self.absolute_root_path,
+            hash_names: self.hash_names,
+            algorithm: &self.algorithm,
+            additional_algorithms: &self.additional_algorithms,
+            #[cfg(feature = "bao")]
+            outboards: self.outboards,
+            #[cfg(feature = "fastcdc")]
+            chunking: self.chunking,
+            #[cfg(feature = "unixfs")]
+            unixfs: self.unixfs,
+            #[cfg(feature = "bittorrent")]
+            piece_length: self.piece_length,
+            parallel,
+            read_limiter,
+        }
+    }
+
+    /// Creates the tree from its indexed root and the settings it was built with
+    fn into_tree(self, root: MerkleNode) -> MerkleTree {
+        #[cfg(feature = "fastcdc")]
+        let chunking = self.chunking;
+        #[cfg(not(feature = "fastcdc"))]
+        let chunking = None;
+
+        MerkleTree {
+            root,
+            algorithm: self.algorithm,
+            additional_algorithms: self.additional_algorithms,
+            chunking,
+            hash_names: self.hash_names,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::IndexingError;
+    use crate::utils::algorithm::{Blake3Key, Blake3Mode, OutputLength};
+    use crate::utils::fixture::{fixture, path};
+    use crate::{Algorithm, AlgorithmKind, MerkleTree};
+
+    #[test]
+    fn additional_algorithms_match_separate_builds() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d/", b"")]);
+        let derive_key = Algorithm::Blake3DeriveKey("context".to_string());
+        for hash_names in [false, true] {
+            let combined = MerkleTree::builder(path(&dir))
+                .hash_names(hash_names)
+                .algorithms([Algorithm::Blake3, derive_key.clone(), Algorithm::Blake3])
+                .build()
+                .unwrap();
+            assert_eq!(combined.additional_algorithms, vec![derive_key.clone()]);
+
+            let separate = MerkleTree::builder(path(&dir))
+                .hash_names(hash_names)
+                .algorithm(derive_key.clone())
+                .build()
+                .unwrap();
+            assert_eq!(combined.root_hash_for(AlgorithmKind::Blake3DeriveKey), Some(&separate.root.item.hash));
+            for (item, other) in combined.iter().zip(separate.iter()) {
+                assert_eq!(item.hash_for(AlgorithmKind::Blake3DeriveKey), Some(&other.hash));
+                assert_eq!(item.hashes().count(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_algorithms_sharing_a_kind() {
+        let dir = fixture(&[("a", b"one")]);
+        let xof = |length| Algorithm::Blake3Xof(Blake3Mode::Plain, OutputLength::new(length).unwrap());
+        let keyed = |byte| Algorithm::Blake3Keyed(Blake3Key::new([byte; 32]));
+
+        let lengths = MerkleTree::builder(path(&dir)).algorithms([Algorithm::Blake3, xof(48), xof(32)]).build();
+        assert!(matches!(lengths, Err(IndexingError::ConflictingAlgorithms(AlgorithmKind::Blake3Xof))));
+        let keys = MerkleTree::builder(path(&dir)).algorithms([keyed(1), keyed(2)]).build_compact();
+        assert!(matches!(keys, Err(IndexingError::ConflictingAlgorithms(AlgorithmKind::Blake3Keyed))));
+
+        let repeated = MerkleTree::builder(path(&dir)).algorithms([keyed(1), xof(48), keyed(1), xof(48)]).build();
+        assert_eq!(repeated.unwrap().additional_algorithms, vec![xof(48)]);
+    }
+
+    #[cfg(feature = "sha")]
+    #[test]
+    fn computes_sha256_alongside_blake3() {
+        let dir = fixture(&[("a", b"abc")]);
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithms([Algorithm::Blake3, Algorithm::Sha256])
+            .build()
+            .unwrap();
+        assert_eq!(
+            tree.root_hash_for(AlgorithmKind::Sha256).unwrap().to_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(tree.root_hash_for(AlgorithmKind::Blake3), Some(&Algorithm::Blake3.compute_hash(b"abc")));
     }
 }
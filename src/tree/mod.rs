@@ -1,3 +1,30 @@
+#[cfg(feature = "apply")]
+pub mod apply;
+#[cfg(feature = "attestation")]
+pub mod attestation;
+#[cfg(feature = "cas")]
+pub mod cas;
+pub mod compact_tree;
+#[cfg(feature = "dirhash")]
+pub mod dirhash;
+pub mod merge;
 pub mod merkle_node;
+#[cfg(feature = "async")]
+pub mod merkle_node_async;
 pub mod merkle_tree;
 pub mod merkle_tree_builder;
+#[cfg(feature = "nar")]
+pub mod nar;
+#[cfg(feature = "oci")]
+pub mod oci;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "bao")]
+pub mod slice;
+#[cfg(feature = "bincode")]
+pub mod snapshot;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "bittorrent")]
+pub mod torrent;
+pub mod verification;
@@ -0,0 +1,135 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::components::nar_hash::NarHash;
+use crate::error::NarError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::nar::{NarNode, NarWriter, Sha256Writer, NAR_MAGIC};
+
+impl MerkleTree {
+    /// Serializes the tree into the canonical Nix archive (NAR) stream, as `nix-store --dump` does
+    ///
+    /// Entries are sorted by name, the executable bit of files is kept and symbolic links are stored
+    /// as links rather than followed. File contents are streamed from disk again.
+    pub fn write_nar(&self, writer: impl Write) -> Result<(), NarError> {
+        let mut writer = NarWriter::new(writer);
+        writer.string(NAR_MAGIC.as_bytes())?;
+        Self::write_nar_node(&self.root, &mut writer)?;
+        writer.flush()
+    }
+
+    /// Computes the NAR hash of the tree, the SHA-256 hash of its Nix archive
+    ///
+    /// Matches `nix hash path` in its SRI form and `nix-hash --type sha256 --base32` in its nix32 form.
+    pub fn nar_hash(&self) -> Result<NarHash, NarError> {
+        let mut hasher = Sha256Writer::default();
+        self.write_nar(&mut hasher)?;
+        Ok(NarHash::new(hasher.finalize()))
+    }
+
+    fn write_nar_node<W: Write>(node: &MerkleNode, writer: &mut NarWriter<W>) -> Result<(), NarError> {
+        let path: &Path = node.item.path.absolute.as_ref();
+
+        writer.strings(&[b"(", b"type"])?;
+        match NarNode::of(path)? {
+            NarNode::Regular { executable, size } => {
+                writer.string(b"regular")?;
+                if executable {
+                    writer.strings(&[b"executable", b""])?;
+                }
+                writer.string(b"contents")?;
+                writer.contents(path, size)?;
+            }
+            NarNode::Symlink(target) => {
+                writer.strings(&[b"symlink", b"target", &target])?;
+            }
+            NarNode::Directory => {
+                writer.string(b"directory")?;
+                for child in &node.children {
+                    let name = match child.item.path.relative.file_name() {
+                        Some(name) => name,
+                        None => continue,
+                    };
+                    #[cfg(feature = "camino")]
+                    let name = name.as_bytes();
+                    #[cfg(not(feature = "camino"))]
+                    let name = name.as_encoded_bytes();
+
+                    writer.strings(&[b"entry", b"(", b"name", name, b"node"])?;
+                    Self::write_nar_node(child, writer)?;
+                    writer.string(b")")?;
+                }
+            }
+        }
+        writer.string(b")")
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use std::path::Path;
+
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path, write};
+
+    fn chmod(path: &Path, mode: u32) {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn nix32(root: &str) -> String {
+        MerkleTree::builder(root).build().unwrap().nar_hash().unwrap().to_nix32()
+    }
+
+    // Expected hashes are in the `nix-hash --type sha256 --base32` form, computed from the NAR format
+    // independently of this crate; the empty directory hash is the one Nix reports for `mkdir $out`
+    #[test]
+    fn hashes_regular_and_executable_files() {
+        let dir = fixture(&[("file", b"hello\n"), ("exe", b"#!/bin/sh\necho hi\n")]);
+        chmod(&dir.path().join("file"), 0o644);
+        chmod(&dir.path().join("exe"), 0o755);
+
+        let file = dir.path().join("file");
+        let exe = dir.path().join("exe");
+        assert_eq!(nix32(file.to_str().unwrap()), "04zwf782yjwnh3q6hz5izfd6jyip8kgw6g6yj43fiqhbyhdd0dqw");
+        assert_eq!(nix32(exe.to_str().unwrap()), "183p8jhjfcpk6kac6hxwp4gzp9brkvkibylz27jfbvgd5kqcq2jy");
+    }
+
+    #[test]
+    fn hashes_empty_directories() {
+        let dir = fixture(&[]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let hash = tree.nar_hash().unwrap();
+
+        assert_eq!(hash.to_nix32(), "0sjjj9z1dhilhpc8pq4154czrb79z9cm044jvn75kxcjv6v5l2m5");
+        assert_eq!(hash.to_string(), "sha256-pQpattmS9VmO3ZIQUFn66az8GSmB4IvYhTTCFn6SUmo=");
+    }
+
+    #[test]
+    fn hashes_trees_with_symlinks_executables_and_empty_directories() {
+        let dir = fixture(&[("hello.txt", b"hello\n"), ("run.sh", b"#!/bin/sh\necho hi\n"), ("empty/", b"")]);
+        chmod(&dir.path().join("hello.txt"), 0o644);
+        chmod(&dir.path().join("run.sh"), 0o755);
+        symlink("hello.txt", dir.path().join("link")).unwrap();
+
+        assert_eq!(nix32(path(&dir)), "0xjfah61af5fsli8m02iziip60y7hyvkrz271d9sxbbbl8zaqbln");
+
+        write(dir.path(), "hello.txt", b"hello!\n");
+        assert_ne!(nix32(path(&dir)), "0xjfah61af5fsli8m02iziip60y7hyvkrz271d9sxbbbl8zaqbln");
+    }
+
+    #[test]
+    fn archives_start_with_the_magic_and_keep_the_executable_bit() {
+        let dir = fixture(&[("exe", b"")]);
+        chmod(&dir.path().join("exe"), 0o755);
+
+        let mut nar = Vec::new();
+        MerkleTree::builder(path(&dir)).build().unwrap().write_nar(&mut nar).unwrap();
+
+        assert_eq!(&nar[..8], &13u64.to_le_bytes());
+        assert_eq!(&nar[8..21], b"nix-archive-1");
+        assert!(nar.windows(10).any(|window| window == b"executable"));
+    }
+}
@@ -0,0 +1,271 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::components::digest::Digest;
+use crate::components::oci_layer::{LayerCompression, OciDescriptor, OciLayer};
+use crate::error::OciError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::AlgorithmKind;
+use crate::utils::tar::{EntryKind, Sha256Tee, TarWriter};
+
+impl MerkleTree {
+    /// Writes the tree as a reproducible OCI image layer and returns its diffID and descriptor
+    ///
+    /// Entries are sorted by path, owned by root, have a zero modification time and are either
+    /// `0644` or `0755` depending on their executable bit. Symbolic links are followed, as they are when indexing.
+    /// Every file is checked against its hash in the tree before it is written.
+    pub fn write_oci_layer(&self, writer: impl Write, compression: LayerCompression) -> Result<OciLayer, OciError> {
+        let (diff_id, digest, size) = match compression {
+            LayerCompression::None => {
+                let (_, hash, size) = self.write_tar(Sha256Tee::new(writer))?.finish();
+                (hash, hash, size)
+            }
+            #[cfg(feature = "gzip")]
+            LayerCompression::Gzip => {
+                let encoder = flate2::GzBuilder::new()
+                    .mtime(0)
+                    .write(Sha256Tee::new(writer), flate2::Compression::default());
+                let (encoder, diff_id, _) = self.write_tar(Sha256Tee::new(encoder))?.finish();
+                let compressed = encoder.finish().map_err(OciError::UnableToWrite)?;
+                let (_, digest, size) = compressed.finish();
+                (diff_id, digest, size)
+            }
+        };
+
+        Ok(OciLayer {
+            diff_id: Digest::from_output(AlgorithmKind::Sha256, &diff_id),
+            descriptor: OciDescriptor {
+                media_type: compression.media_type(),
+                digest: Digest::from_output(AlgorithmKind::Sha256, &digest),
+                size,
+            },
+        })
+    }
+
+    /// Computes the diffID and descriptor of the tree's OCI image layer without keeping the layer
+    pub fn oci_layer(&self, compression: LayerCompression) -> Result<OciLayer, OciError> {
+        self.write_oci_layer(io::sink(), compression)
+    }
+
+    fn write_tar<W: Write>(&self, writer: W) -> Result<W, OciError> {
+        let mut entries = Vec::new();
+        match self.root.children.is_empty() && !self.root.item.path.absolute.is_dir() {
+            true => {
+                let absolute: &Path = self.root.item.path.absolute.as_ref();
+                let name = absolute.file_name().map(Self::path_bytes).unwrap_or_default();
+                entries.push((name, &self.root));
+            }
+            false => Self::collect_entries(&self.root, &mut entries),
+        }
+        entries.sort_by(|(path, _), (other, _)| path.cmp(other));
+
+        let mut tar = TarWriter::new(writer);
+        for (path, node) in entries {
+            let absolute: &Path = node.item.path.absolute.as_ref();
+            let metadata = match fs::metadata(absolute) {
+                Ok(metadata) => metadata,
+                Err(err) => return Err(OciError::UnableToReadFile(absolute.to_path_buf(), err)),
+            };
+
+            if metadata.is_dir() {
+                tar.entry(&path, EntryKind::Directory, &[]).map_err(OciError::UnableToWrite)?;
+                continue;
+            }
+
+            let contents = match fs::read(absolute) {
+                Ok(contents) => contents,
+                Err(err) => return Err(OciError::UnableToReadFile(absolute.to_path_buf(), err)),
+            };
+            if self.file_item_hash(&node.item, &contents) != node.item.hash {
+                return Err(OciError::FileChanged(absolute.to_path_buf()));
+            }
+
+            #[cfg(unix)]
+            let executable = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o100 != 0;
+            #[cfg(not(unix))]
+            let executable = false;
+
+            tar.entry(&path, EntryKind::File { executable }, &contents)
+                .map_err(OciError::UnableToWrite)?;
+        }
+        tar.finish().map_err(OciError::UnableToWrite)
+    }
+
+    /// Collects every descendant of a node with its slash separated relative path
+    fn collect_entries<'a>(node: &'a MerkleNode, entries: &mut Vec<(Vec<u8>, &'a MerkleNode)>) {
+        for child in &node.children {
+            let relative: &Path = child.item.path.relative.as_ref();
+            let path = relative
+                .components()
+                .map(|component| Self::path_bytes(component.as_os_str()))
+                .collect::<Vec<_>>()
+                .join(&b'/');
+            entries.push((path, child));
+            Self::collect_entries(child, entries);
+        }
+    }
+
+    fn path_bytes(name: &std::ffi::OsStr) -> Vec<u8> {
+        #[cfg(unix)]
+        return std::os::unix::ffi::OsStrExt::as_bytes(name).to_vec();
+        #[cfg(not(unix))]
+        return name.to_string_lossy().into_owned().into_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::oci_layer::{LayerCompression, OCI_LAYER_MEDIA_TYPE};
+    use crate::error::OciError;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path, write};
+
+    /// Reads the path, mode, typeflag and size of every ustar header, checking their checksums
+    fn headers(tar: &[u8]) -> Vec<(String, u64, u8, u64)> {
+        let field = |block: &[u8]| {
+            let end = block.iter().position(|&byte| byte == 0).unwrap_or(block.len());
+            String::from_utf8(block[..end].to_vec()).unwrap()
+        };
+        let octal = |block: &[u8]| u64::from_str_radix(field(block).trim(), 8).unwrap();
+
+        let mut headers = Vec::new();
+        let mut offset = 0;
+        while tar[offset..offset + 512].iter().any(|&byte| byte != 0) {
+            let header = &tar[offset..offset + 512];
+            let checksum: u64 = header
+                .iter()
+                .enumerate()
+                .map(|(index, &byte)| if (148..156).contains(&index) { b' ' } else { byte } as u64)
+                .sum();
+            assert_eq!(octal(&header[148..156]), checksum);
+
+            let prefix = field(&header[345..500]);
+            let name = field(&header[..100]);
+            let path = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+            let size = octal(&header[124..136]);
+            headers.push((path, octal(&header[100..108]), header[156], size));
+            offset += 512 + size.div_ceil(512) as usize * 512;
+        }
+        assert!(tar[offset..].iter().all(|&byte| byte == 0));
+        assert_eq!(tar.len() - offset, 1024);
+        headers
+    }
+
+    fn layer_fixture() -> tempfile::TempDir {
+        let dir = fixture(&[
+            ("etc/motd", b"hello\n"),
+            ("bin/run", b"#!/bin/sh\necho hi\n"),
+            ("empty/", b""),
+        ]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = std::fs::Permissions::from_mode(0o700);
+            std::fs::set_permissions(dir.path().join("bin/run"), permissions).unwrap();
+            let permissions = std::fs::Permissions::from_mode(0o600);
+            std::fs::set_permissions(dir.path().join("etc/motd"), permissions).unwrap();
+        }
+        dir
+    }
+
+    // The expected diffID is that of the same entries written by Python's `tarfile` in ustar format,
+    // with the device numbers of every header zero filled as Go's `archive/tar` writes them
+    #[cfg(unix)]
+    #[test]
+    fn writes_reproducible_sorted_layers() {
+        let dir = layer_fixture();
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let mut tar = Vec::new();
+        let layer = tree.write_oci_layer(&mut tar, LayerCompression::None).unwrap();
+        let diff_id = "sha256:1d0ba7e290a03c99a8ca6e6175c755d704668f89c84c492a2fcd95c613cc1187";
+        assert_eq!(layer.diff_id.to_string(), diff_id);
+        assert_eq!(layer.descriptor.digest, layer.diff_id);
+        assert_eq!(layer.descriptor.size, tar.len() as u64);
+        assert_eq!(
+            layer.descriptor.to_json(),
+            format!(r#"{{"mediaType":"{}","digest":"{}","size":4608}}"#, OCI_LAYER_MEDIA_TYPE, diff_id)
+        );
+
+        let headers = headers(&tar);
+        let expected = [
+            ("bin/", 0o755, b'5', 0),
+            ("bin/run", 0o755, b'0', 18),
+            ("empty/", 0o755, b'5', 0),
+            ("etc/", 0o755, b'5', 0),
+            ("etc/motd", 0o644, b'0', 6),
+        ];
+        assert_eq!(headers.len(), expected.len());
+        for (header, (path, mode, typeflag, size)) in headers.iter().zip(expected) {
+            assert_eq!(*header, (path.to_string(), mode, typeflag, size));
+        }
+
+        // The same entries elsewhere and written at another time give the same layer
+        let other = layer_fixture();
+        let other_tree = MerkleTree::builder(path(&other)).build().unwrap();
+        assert_eq!(other_tree.oci_layer(LayerCompression::None).unwrap(), layer);
+    }
+
+    #[test]
+    fn writes_single_files_under_their_name() {
+        let dir = fixture(&[("motd", b"hello\n")]);
+        let tree = MerkleTree::builder(dir.path().join("motd").to_str().unwrap()).build().unwrap();
+
+        let mut tar = Vec::new();
+        tree.write_oci_layer(&mut tar, LayerCompression::None).unwrap();
+        assert_eq!(headers(&tar), vec![("motd".to_string(), 0o644, b'0', 6)]);
+    }
+
+    #[test]
+    fn splits_long_paths_and_falls_back_to_pax() {
+        let long_dir = "d".repeat(120);
+        let split = format!("{}/{}", long_dir, "f".repeat(90));
+        let pax = format!("{}/{}", long_dir, "g".repeat(110));
+        let dir = fixture(&[(&split, b"split"), (&pax, b"pax")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let mut tar = Vec::new();
+        tree.write_oci_layer(&mut tar, LayerCompression::None).unwrap();
+        let paths = headers(&tar).into_iter().map(|(path, ..)| path).collect::<Vec<_>>();
+
+        // The directory path is too long for the name field and has no slash to split at but its last
+        assert_eq!(paths.iter().filter(|path| *path == "././@PaxHeader").count(), 2);
+        assert!(paths.contains(&split));
+        for record in [format!("path={}/\n", long_dir), format!("path={}\n", pax)] {
+            assert!(tar.windows(record.len()).any(|window| window == record.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn refuses_files_changed_since_indexing() {
+        let dir = fixture(&[("a", b"before")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        write(dir.path(), "a", b"after");
+        assert!(matches!(tree.oci_layer(LayerCompression::None), Err(OciError::FileChanged(_))));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compressed_layers_keep_the_uncompressed_diff_id() {
+        use std::io::Read;
+
+        use crate::utils::algorithm::Algorithm;
+
+        let dir = layer_fixture();
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        let mut blob = Vec::new();
+        let layer = tree.write_oci_layer(&mut blob, LayerCompression::Gzip).unwrap();
+        assert_eq!(layer, tree.oci_layer(LayerCompression::Gzip).unwrap());
+        assert_eq!(layer.descriptor.size, blob.len() as u64);
+        assert_eq!(layer.descriptor.digest.to_hex(), Algorithm::Sha256.compute_hash(&blob).to_hex());
+
+        let mut tar = Vec::new();
+        flate2::read::GzDecoder::new(&blob[..]).read_to_end(&mut tar).unwrap();
+        assert_eq!(layer.diff_id, tree.oci_layer(LayerCompression::None).unwrap().diff_id);
+        assert_eq!(layer.diff_id.to_hex(), Algorithm::Sha256.compute_hash(&tar).to_hex());
+    }
+}
@@ -0,0 +1,274 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{SigningKey, VerifyingKey};
+
+use crate::components::signed_root::{RootOptions, SignedRoot};
+use crate::error::SigningError;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::{Algorithm, Blake3Key};
+
+impl MerkleTree {
+    /// Signs the root hash of the tree and the settings it was built with, at the current time
+    pub fn sign(&self, key: &SigningKey) -> SignedRoot {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        self.sign_at(key, timestamp)
+    }
+
+    /// Signs the root hash of the tree and the settings it was built with, at the given seconds since the unix epoch
+    pub fn sign_at(&self, key: &SigningKey, timestamp: u64) -> SignedRoot {
+        SignedRoot::sign(self.root.item.hash, self.root_options(&self.algorithm), timestamp, key)
+    }
+
+    /// Collects the settings the hashes of the given algorithm were built with
+    fn root_options(&self, algorithm: &Algorithm) -> RootOptions {
+        RootOptions {
+            hash_names: self.hash_names,
+            chunking: self.chunking,
+            context: algorithm.context().map(str::to_string),
+            output_len: algorithm.output_len(),
+            key_fingerprint: algorithm.key_fingerprint(),
+        }
+    }
+}
+
+impl SignedRoot {
+    /// Checks the signature and that the tree, for example one loaded from a snapshot, has the signed root hash
+    ///
+    /// The signed algorithm can be either the primary or one of the additional algorithms of the tree.
+    pub fn verify_tree(&self, tree: &MerkleTree, key: &VerifyingKey) -> Result<(), SigningError> {
+        self.verify(key)?;
+
+        let algorithm = match std::iter::once(&tree.algorithm)
+            .chain(&tree.additional_algorithms)
+            .find(|algorithm| algorithm.kind() == self.algorithm)
+        {
+            Some(algorithm) => algorithm,
+            None => return Err(SigningError::AlgorithmMismatch),
+        };
+        if tree.root_options(algorithm) != self.options {
+            return Err(SigningError::OptionsMismatch);
+        }
+        if tree.root_hash_for(self.algorithm) != Some(&self.root_hash) {
+            return Err(SigningError::RootMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks the signature and rebuilds a directory with the signed settings to compare its root hash
+    ///
+    /// Roots signed with a keyed algorithm need [`SignedRoot::verify_dir_keyed`].
+    pub fn verify_dir(&self, path: impl AsRef<str>, key: &VerifyingKey) -> Result<MerkleTree, SigningError> {
+        self.rebuild_and_verify(path, key, None)
+    }
+
+    /// Checks the signature and rebuilds a directory with the signed settings and the given hashing key
+    pub fn verify_dir_keyed(
+        &self,
+        path: impl AsRef<str>,
+        key: &VerifyingKey,
+        hash_key: Blake3Key,
+    ) -> Result<MerkleTree, SigningError> {
+        self.rebuild_and_verify(path, key, Some(hash_key))
+    }
+
+    /// Checks the signature and that a snapshot holds the signed root hash, returning the loaded tree
+    #[cfg(feature = "bincode")]
+    pub fn verify_snapshot(&self, reader: impl std::io::Read, key: &VerifyingKey) -> Result<MerkleTree, SigningError> {
+        let tree = MerkleTree::load_snapshot(reader)?;
+        self.verify_tree(&tree, key)?;
+        Ok(tree)
+    }
+
+    fn rebuild_and_verify(
+        &self,
+        path: impl AsRef<str>,
+        key: &VerifyingKey,
+        hash_key: Option<Blake3Key>,
+    ) -> Result<MerkleTree, SigningError> {
+        // Checks the signature before spending time on indexing
+        self.verify(key)?;
+
+        // Only keyed algorithms sign a fingerprint, the key must not turn any other algorithm into a keyed one
+        let hash_key = match self.options.key_fingerprint {
+            Some(_) => Some(hash_key.ok_or(SigningError::KeyRequired)?),
+            None => None,
+        };
+        let algorithm = Algorithm::from_parameters(
+            self.algorithm,
+            self.options.context.clone(),
+            self.options.output_len,
+            hash_key,
+        );
+        let algorithm = match algorithm {
+            Some(algorithm) => algorithm,
+            None => return Err(SigningError::OptionsMismatch),
+        };
+
+        let builder = MerkleTree::builder(path)
+            .algorithm(algorithm)
+            .hash_names(self.options.hash_names);
+        #[cfg(feature = "fastcdc")]
+        let builder = match self.options.chunking {
+            Some(chunking) => builder.chunking(chunking),
+            None => builder,
+        };
+        #[cfg(not(feature = "fastcdc"))]
+        if self.options.chunking.is_some() {
+            return Err(SigningError::OptionsMismatch);
+        }
+
+        let tree = builder.build()?;
+        self.verify_tree(&tree, key)?;
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use crate::components::signed_root::{SignedRoot, SIGNED_ROOT_MAGIC};
+    use crate::error::SigningError;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::algorithm::{Algorithm, Blake3Key};
+    use crate::utils::fixture::{fixture, path, write};
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    #[test]
+    fn payloads_follow_the_documented_layout() {
+        let dir = fixture(&[("a", b"one")]);
+        let tree = MerkleTree::builder(path(&dir)).hash_names(true).build().unwrap();
+        let signed = tree.sign_at(&signing_key(1), 0x0102030405060708);
+
+        let mut expected = SIGNED_ROOT_MAGIC.to_vec();
+        expected.extend_from_slice(b"\x06blake3\x20");
+        expected.extend_from_slice(tree.root.item.hash.as_bytes());
+        expected.extend_from_slice(&[1, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(signed.payload(), expected);
+
+        let bytes = signed.to_bytes();
+        assert_eq!(bytes.len(), expected.len() + 64);
+        assert_eq!(SignedRoot::from_bytes(&bytes).unwrap(), signed);
+    }
+
+    #[test]
+    fn rejects_tampered_and_malformed_roots() {
+        let dir = fixture(&[("a", b"one")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let key = signing_key(1);
+        let signed = tree.sign_at(&key, 1);
+        signed.verify(&key.verifying_key()).unwrap();
+
+        let other = signing_key(2).verifying_key();
+        assert!(matches!(signed.verify(&other), Err(SigningError::InvalidSignature)));
+
+        let mut bytes = signed.to_bytes();
+        let timestamp = bytes.len() - 65;
+        bytes[timestamp] ^= 1;
+        let tampered = SignedRoot::from_bytes(&bytes).unwrap();
+        assert!(matches!(tampered.verify(&key.verifying_key()), Err(SigningError::InvalidSignature)));
+
+        let bytes = signed.to_bytes();
+        assert!(matches!(SignedRoot::from_bytes(&bytes[..bytes.len() - 1]), Err(SigningError::Truncated)));
+        let trailing = [bytes.as_slice(), &[0]].concat();
+        assert!(matches!(SignedRoot::from_bytes(&trailing), Err(SigningError::TrailingBytes(1))));
+        assert!(matches!(SignedRoot::from_bytes(b"NOTASIG\x01"), Err(SigningError::InvalidMagic)));
+
+        // Flags follow the magic, the algorithm name and the root hash
+        let flags = SIGNED_ROOT_MAGIC.len() + 1 + 6 + 1 + 32;
+        let mut flag = bytes.clone();
+        flag[flags] = 2;
+        assert!(matches!(SignedRoot::from_bytes(&flag), Err(SigningError::InvalidFlag(2))));
+        let context = [&bytes[..flags + 1], &[1, 0, 0, 0, 1, 0xff], &bytes[flags + 2..]].concat();
+        assert!(matches!(SignedRoot::from_bytes(&context), Err(SigningError::InvalidUtf8)));
+    }
+
+    #[test]
+    fn verifies_directories_with_the_signed_settings() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two")]);
+        let key = signing_key(1);
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithm(Algorithm::Blake3DeriveKey("release".to_string()))
+            .hash_names(true)
+            .build()
+            .unwrap();
+        let signed = tree.sign_at(&key, 1);
+
+        let rebuilt = signed.verify_dir(path(&dir), &key.verifying_key()).unwrap();
+        assert_eq!(rebuilt.algorithm, Algorithm::Blake3DeriveKey("release".to_string()));
+        assert!(rebuilt.hash_names);
+        signed.verify_tree(&tree, &key.verifying_key()).unwrap();
+
+        let unnamed = MerkleTree::builder(path(&dir))
+            .algorithm(Algorithm::Blake3DeriveKey("release".to_string()))
+            .build()
+            .unwrap();
+        assert!(matches!(signed.verify_tree(&unnamed, &key.verifying_key()), Err(SigningError::OptionsMismatch)));
+
+        write(dir.path(), "b/c", b"changed");
+        assert!(matches!(signed.verify_dir(path(&dir), &key.verifying_key()), Err(SigningError::RootMismatch)));
+    }
+
+    #[test]
+    fn signs_additional_algorithms_too() {
+        let dir = fixture(&[("a", b"one")]);
+        let key = signing_key(1);
+        let derived = Algorithm::Blake3DeriveKey("release".to_string());
+        let alone = MerkleTree::builder(path(&dir)).algorithm(derived.clone()).build().unwrap();
+        let both = MerkleTree::builder(path(&dir))
+            .algorithms([Algorithm::Blake3, derived])
+            .build()
+            .unwrap();
+
+        alone.sign_at(&key, 1).verify_tree(&both, &key.verifying_key()).unwrap();
+        let blake3 = both.sign_at(&key, 1);
+        assert!(matches!(blake3.verify_tree(&alone, &key.verifying_key()), Err(SigningError::AlgorithmMismatch)));
+    }
+
+    #[test]
+    fn keyed_roots_need_the_hashing_key() {
+        let dir = fixture(&[("a", b"one")]);
+        let key = signing_key(1);
+        let hash_key = Blake3Key::new([7; 32]);
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithm(Algorithm::Blake3Keyed(hash_key.clone()))
+            .build()
+            .unwrap();
+        let signed = tree.sign_at(&key, 1);
+        assert_eq!(signed.options.key_fingerprint, Some(hash_key.fingerprint()));
+
+        let verifying = key.verifying_key();
+        assert!(matches!(signed.verify_dir(path(&dir), &verifying), Err(SigningError::KeyRequired)));
+        let wrong = Blake3Key::new([8; 32]);
+        assert!(matches!(
+            signed.verify_dir_keyed(path(&dir), &verifying, wrong),
+            Err(SigningError::OptionsMismatch)
+        ));
+        signed.verify_dir_keyed(path(&dir), &verifying, hash_key).unwrap();
+
+        // A key must not turn an unkeyed root into a keyed one
+        let plain = MerkleTree::builder(path(&dir)).build().unwrap().sign_at(&key, 1);
+        plain.verify_dir_keyed(path(&dir), &verifying, Blake3Key::new([7; 32])).unwrap();
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn verifies_snapshots() {
+        let dir = fixture(&[("a", b"one")]);
+        let key = signing_key(1);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        let signed = tree.sign_at(&key, 1);
+
+        let mut bytes = Vec::new();
+        tree.save_snapshot(&mut bytes).unwrap();
+        let loaded = signed.verify_snapshot(bytes.as_slice(), &key.verifying_key()).unwrap();
+        assert_eq!(loaded.root.item.hash, tree.root.item.hash);
+    }
+}
@@ -0,0 +1,243 @@
+use std::path::Path;
+
+use crate::components::digest::Digest;
+use crate::components::inclusion_path::{InclusionPath, InclusionStep};
+use crate::components::slice_proof::SliceProof;
+use crate::error::BaoError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+
+impl MerkleTree {
+    /// Produces a proof for `len` bytes of the file at the given relative path starting at `start`,
+    /// which verifies against the BLAKE3 root hash of the tree with [`SliceProof::verify_root`]
+    ///
+    /// The tree must be built with outboards and without chunking, with plain BLAKE3 as one of its algorithms.
+    pub fn slice(&self, relative_path: impl AsRef<str>, start: u64, len: u64) -> Result<SliceProof, BaoError> {
+        let root_hash = match self.root_hash_for(AlgorithmKind::Blake3) {
+            Some(root_hash) => *root_hash,
+            None => return Err(BaoError::UnsupportedAlgorithm(self.algorithm.kind())),
+        };
+        if self.chunking.is_some() {
+            return Err(BaoError::ChunkedContents);
+        }
+
+        let relative_path = relative_path.as_ref();
+        let nodes = match Self::slice_nodes(&self.root, Path::new(relative_path)) {
+            Some(nodes) => nodes,
+            None => return Err(BaoError::PathNotFound(relative_path.to_string())),
+        };
+
+        let file = nodes[nodes.len() - 1];
+        let proof = file.item.slice(start, len)?;
+        let file_hash = match &file.item.outboard {
+            Some(outboard) => *outboard.hash(),
+            None => return Err(BaoError::MissingOutboard),
+        };
+
+        // Walks up from the file, naming every node if names are hashed and pairing it with its siblings
+        let mut steps = Vec::new();
+        for depth in (0..nodes.len()).rev() {
+            if self.hash_names {
+                steps.push(InclusionStep::Name(Self::slice_name(nodes[depth], relative_path)?));
+            }
+            if depth > 0 {
+                Self::sibling_steps(nodes[depth - 1], nodes[depth], &mut steps);
+            }
+        }
+
+        // Catches trees whose hashes do not derive from their outboards
+        let inclusion = InclusionPath { file_hash, steps };
+        if inclusion.root_hash() != root_hash {
+            return Err(BaoError::HashMismatch);
+        }
+
+        Ok(proof.with_inclusion(inclusion))
+    }
+
+    /// Finds the nodes from the root down to the node with the given relative path
+    fn slice_nodes<'a>(root: &'a MerkleNode, relative_path: &Path) -> Option<Vec<&'a MerkleNode>> {
+        let mut nodes = vec![root];
+        let mut node = root;
+        while AsRef::<Path>::as_ref(&node.item.path.relative) != relative_path {
+            node = node
+                .children
+                .iter()
+                .find(|child| relative_path.starts_with(&child.item.path.relative))?;
+            nodes.push(node);
+        }
+        Some(nodes)
+    }
+
+    /// Returns the name hashed into a node, as the builder does
+    fn slice_name(node: &MerkleNode, relative_path: &str) -> Result<String, BaoError> {
+        let name = node.item.path.absolute.file_name();
+        #[cfg(not(feature = "camino"))]
+        let name = name.and_then(|name| name.to_str());
+        match name {
+            Some(name) => Ok(name.to_string()),
+            None => Err(BaoError::PathNotFound(relative_path.to_string())),
+        }
+    }
+
+    /// Lists the siblings pairing a child's hash up to its parent's merkle hash, as in [`Algorithm::compute_merkle_hash`]
+    fn sibling_steps(parent: &MerkleNode, child: &MerkleNode, steps: &mut Vec<InclusionStep>) {
+        let algorithm = Algorithm::Blake3;
+        let mut level: Vec<Digest> = parent
+            .children
+            .iter()
+            .filter_map(|child| child.item.hash_for(AlgorithmKind::Blake3).copied())
+            .collect();
+        let mut index = parent.children.iter().position(|other| other.item.path == child.item.path).unwrap_or(0);
+
+        while level.len() > 1 {
+            // An odd hash at the end of a level is paired with itself
+            let step = match index % 2 {
+                0 => InclusionStep::Right(*level.get(index + 1).unwrap_or(&level[index])),
+                _ => InclusionStep::Left(level[index - 1]),
+            };
+            steps.push(step);
+
+            level = level
+                .chunks(2)
+                .map(|pair| algorithm.compute_hash_from_slices(pair[0].as_ref(), pair.get(1).unwrap_or(&pair[0]).as_ref()))
+                .collect();
+            index /= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixture::{fixture, path};
+
+    fn contents(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn proves_ranges_against_the_root_hash() {
+        let large = contents(5000);
+        let dir = fixture(&[
+            ("a", b"one"),
+            ("b/c", &large),
+            ("b/d", b"two"),
+            ("b/e/f", b"three"),
+            ("b/g", b""),
+            ("h", b"four"),
+        ]);
+        for hash_names in [false, true] {
+            let tree = MerkleTree::builder(path(&dir)).hash_names(hash_names).outboards(true).build().unwrap();
+            for (relative_path, expected) in [
+                ("b/c", &large[..]),
+                ("a", b"one"),
+                ("b/e/f", b"three"),
+                ("b/g", b""),
+                ("h", b"four"),
+            ] {
+                let proof = tree.slice(relative_path, 0, expected.len() as u64).unwrap();
+                assert_eq!(proof.verify_root(&tree.root.item.hash, relative_path).unwrap(), expected);
+            }
+
+            let proof = tree.slice("b/c", 1500, 2000).unwrap();
+            assert_eq!(proof.verify_root(&tree.root.item.hash, "b/c").unwrap(), &large[1500..3500]);
+        }
+    }
+
+    #[test]
+    fn proves_ranges_when_blake3_is_an_additional_algorithm() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d", b"three")]);
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithms([Algorithm::Blake3DeriveKey("context".to_string()), Algorithm::Blake3])
+            .outboards(true)
+            .build()
+            .unwrap();
+        let proof = tree.slice("b/d", 0, 5).unwrap();
+        let root_hash = tree.root_hash_for(AlgorithmKind::Blake3).unwrap();
+        assert_eq!(proof.verify_root(root_hash, "b/d").unwrap(), b"three");
+    }
+
+    #[test]
+    fn rejects_broken_chains() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d", b"three")]);
+        let tree = MerkleTree::builder(path(&dir)).outboards(true).build().unwrap();
+        let proof = tree.slice("b/c", 0, 3).unwrap();
+
+        let other_root = Algorithm::Blake3.compute_hash(b"other");
+        assert!(matches!(proof.verify_root(&other_root, "b/c"), Err(BaoError::HashMismatch)));
+
+        let mut inclusion = proof.inclusion().unwrap().clone();
+        inclusion.steps.swap(0, 1);
+        let swapped = proof.clone().with_inclusion(inclusion);
+        assert!(matches!(swapped.verify_root(&tree.root.item.hash, "b/c"), Err(BaoError::HashMismatch)));
+
+        let file_level = tree.root.children.first().unwrap().item.slice(0, 3).unwrap();
+        assert!(matches!(
+            file_level.verify_root(&tree.root.item.hash, "a"),
+            Err(BaoError::MissingInclusionPath)
+        ));
+    }
+
+    #[test]
+    fn refuses_trees_it_cannot_prove() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two")]);
+        let tree = MerkleTree::builder(path(&dir)).outboards(true).build().unwrap();
+        assert!(matches!(tree.slice("missing", 0, 1), Err(BaoError::PathNotFound(_))));
+        assert!(matches!(tree.slice("b", 0, 1), Err(BaoError::MissingOutboard)));
+
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        assert!(matches!(tree.slice("a", 0, 1), Err(BaoError::MissingOutboard)));
+
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithm(Algorithm::Blake3DeriveKey("context".to_string()))
+            .outboards(true)
+            .build()
+            .unwrap();
+        assert!(matches!(tree.slice("a", 0, 1), Err(BaoError::UnsupportedAlgorithm(_))));
+
+        #[cfg(feature = "fastcdc")]
+        {
+            let chunking = crate::utils::chunking::ChunkParams::new(4096, 16384, 65536).unwrap();
+            let tree = MerkleTree::builder(path(&dir)).outboards(true).chunking(chunking).build().unwrap();
+            assert!(matches!(tree.slice("a", 0, 1), Err(BaoError::ChunkedContents)));
+        }
+    }
+
+    #[test]
+    fn files_changed_since_indexing_fail_to_verify() {
+        let dir = fixture(&[("a", b"one"), ("b", b"two")]);
+        let tree = MerkleTree::builder(path(&dir)).outboards(true).build().unwrap();
+        crate::utils::fixture::write(dir.path(), "a", b"six");
+        let proof = tree.slice("a", 0, 3).unwrap();
+        assert!(matches!(proof.verify_root(&tree.root.item.hash, "a"), Err(BaoError::HashMismatch)));
+    }
+
+    #[test]
+    fn proofs_are_bound_to_their_path_with_hashed_names() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"one"), ("b/d", b"two")]);
+        let tree = MerkleTree::builder(path(&dir)).hash_names(true).outboards(true).build().unwrap();
+        let root_hash = &tree.root.item.hash;
+
+        let proof = tree.slice("b/c", 0, 3).unwrap();
+        assert_eq!(proof.verify_root(root_hash, "b/c").unwrap(), b"one");
+        for other in ["a", "b/d", "b", "c", "b/c/e"] {
+            assert!(matches!(proof.verify_root(root_hash, other), Err(BaoError::PathMismatch(_))), "{}", other);
+        }
+
+        // The pairwise hash of `c` and `d` poses as a 64 byte file, but its names only spell the directory `b`
+        let b = tree.root.children.iter().find(|child| child.item.path.relative.ends_with("b")).unwrap();
+        let [c, d] = [0, 1].map(|index| *b.children.iter().nth(index).unwrap().item.hash_for(AlgorithmKind::Blake3).unwrap());
+        let pair = [c.as_ref(), d.as_ref()].concat();
+        let mut inclusion = tree.slice("b/c", 0, 0).unwrap().inclusion().unwrap().clone();
+        inclusion.file_hash = Algorithm::Blake3.compute_hash(&pair);
+        inclusion.steps.drain(..2);
+        assert_eq!(inclusion.root_hash(), *root_hash);
+
+        let mut encoded = (pair.len() as u64).to_le_bytes().to_vec();
+        encoded.extend_from_slice(&pair);
+        let forged = SliceProof::new(0, 64, encoded).with_inclusion(inclusion);
+        assert!(matches!(forged.verify_root(root_hash, "b/c"), Err(BaoError::PathMismatch(_))));
+        assert!(matches!(forged.verify_root(root_hash, "b/d"), Err(BaoError::PathMismatch(_))));
+    }
+}
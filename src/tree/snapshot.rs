@@ -0,0 +1,399 @@
+use std::io::{Read, Write};
+
+use crate::error::SnapshotError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind, Blake3Key, OutputLength};
+use crate::utils::chunking::ChunkParams;
+
+/// Magic bytes every snapshot starts with
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"MRKLSNAP";
+
+/// Current version of the snapshot layout
+pub const SNAPSHOT_FORMAT_VERSION: u16 = 1;
+
+/// Version of the hashing scheme, follows the major version of this crate
+pub const HASH_SCHEME_VERSION: u16 = 4;
+
+/// Describes how the tree stored in a snapshot was produced
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotHeader {
+    /// Version of the snapshot layout
+    pub format_version: u16,
+    /// Version of the hashing scheme the tree was built with
+    pub hash_scheme: u16,
+    /// Version of this crate that wrote the snapshot
+    pub crate_version: String,
+    /// The kind of hashing algorithm the tree was built with
+    pub algorithm: AlgorithmKind,
+    /// The context string of the algorithm, if it has one
+    pub context: Option<String>,
+    /// The output length of the algorithm in bytes, if it is variable
+    pub output_len: Option<OutputLength>,
+    /// The fingerprint of the key of the algorithm, if it has one, the key itself is never stored
+    pub key_fingerprint: Option<[u8; 32]>,
+    /// The other hashing algorithms the tree was built with
+    pub additional_algorithms: Vec<SnapshotAlgorithm>,
+    /// The chunk sizes files were split into, if they were split at all
+    pub chunking: Option<ChunkParams>,
+    /// Whether names were included in the hashes of files and directories
+    pub hash_names: bool,
+}
+
+/// Describes an additional hashing algorithm recorded in a snapshot
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotAlgorithm {
+    /// The kind of hashing algorithm
+    pub algorithm: AlgorithmKind,
+    /// The context string of the algorithm, if it has one
+    pub context: Option<String>,
+    /// The output length of the algorithm in bytes, if it is variable
+    pub output_len: Option<OutputLength>,
+    /// The fingerprint of the key of the algorithm, if it has one, the key itself is never stored
+    pub key_fingerprint: Option<[u8; 32]>,
+}
+
+impl SnapshotAlgorithm {
+    /// Recreates the algorithm, using the given key if it is keyed
+    fn to_algorithm(&self, key: Option<Blake3Key>) -> Result<Algorithm, SnapshotError> {
+        // Only keyed algorithms record a fingerprint, the key must not turn any other algorithm into a keyed one
+        let key = match (self.key_fingerprint, key) {
+            (Some(_), Some(key)) => Some(key),
+            (Some(_), None) => return Err(SnapshotError::KeyRequired),
+            (None, _) => None,
+        };
+        let algorithm = match Algorithm::from_parameters(self.algorithm, self.context.clone(), self.output_len, key) {
+            Some(algorithm) => algorithm,
+            None => return Err(SnapshotError::MissingAlgorithmParameters(self.algorithm)),
+        };
+        if algorithm.key_fingerprint() != self.key_fingerprint {
+            return Err(SnapshotError::KeyMismatch);
+        }
+        Ok(algorithm)
+    }
+}
+
+/// Header fields as they are laid out after the magic bytes and format version
+#[derive(bincode::Encode, bincode::Decode)]
+struct RawHeader {
+    hash_scheme: u16,
+    crate_version: String,
+    algorithm: RawAlgorithm,
+    additional_algorithms: Vec<RawAlgorithm>,
+    chunking: Option<ChunkParams>,
+    hash_names: bool,
+}
+
+/// Algorithm fields as they are laid out in the header
+#[derive(bincode::Encode, bincode::Decode)]
+struct RawAlgorithm {
+    id: u8,
+    context: Option<String>,
+    output_len: Option<u8>,
+    key_fingerprint: Option<[u8; 32]>,
+}
+
+impl RawAlgorithm {
+    fn new(algorithm: &Algorithm) -> Self {
+        Self {
+            id: algorithm.kind().id(),
+            context: algorithm.context().map(str::to_string),
+            output_len: algorithm.output_len().map(|output_len| output_len.get() as u8),
+            key_fingerprint: algorithm.key_fingerprint(),
+        }
+    }
+
+    fn parse(self) -> Result<SnapshotAlgorithm, SnapshotError> {
+        let algorithm = match AlgorithmKind::from_id(self.id) {
+            Some(algorithm) => algorithm,
+            None => return Err(SnapshotError::UnsupportedAlgorithm(self.id)),
+        };
+
+        let output_len = match self.output_len.map(|output_len| OutputLength::new(output_len as usize)).transpose() {
+            Ok(output_len) => output_len,
+            Err(error) => return Err(SnapshotError::InvalidAlgorithm(error)),
+        };
+
+        Ok(SnapshotAlgorithm {
+            algorithm,
+            context: self.context,
+            output_len,
+            key_fingerprint: self.key_fingerprint,
+        })
+    }
+}
+
+/// A versioned, self-describing merkle tree snapshot
+///
+/// Layout: magic bytes, format version, header and finally the tree body, all bincode encoded.
+pub struct Snapshot {
+    pub header: SnapshotHeader,
+    pub tree: MerkleTree,
+}
+
+impl Snapshot {
+    /// Writes a tree as a snapshot
+    pub fn write(tree: &MerkleTree, mut writer: impl Write) -> Result<(), SnapshotError> {
+        let config = bincode::config::standard();
+
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        bincode::encode_into_std_write(SNAPSHOT_FORMAT_VERSION, &mut writer, config)?;
+
+        let header = RawHeader {
+            hash_scheme: HASH_SCHEME_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            algorithm: RawAlgorithm::new(&tree.algorithm),
+            additional_algorithms: tree.additional_algorithms.iter().map(RawAlgorithm::new).collect(),
+            chunking: tree.chunking,
+            hash_names: tree.hash_names,
+        };
+        bincode::encode_into_std_write(&header, &mut writer, config)?;
+        bincode::encode_into_std_write(&tree.root, &mut writer, config)?;
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads only the header of a snapshot, validating that it can be loaded
+    pub fn read_header(mut reader: impl Read) -> Result<SnapshotHeader, SnapshotError> {
+        Self::read_header_from(&mut reader)
+    }
+
+    /// Reads a whole snapshot, header and tree
+    pub fn read(reader: impl Read) -> Result<Self, SnapshotError> {
+        Self::read_with(reader, None)
+    }
+
+    /// Reads a whole snapshot of a tree built with a keyed algorithm, checking that the key matches
+    ///
+    /// The key is used for whichever of the primary or additional algorithms is keyed.
+    pub fn read_keyed(reader: impl Read, key: Blake3Key) -> Result<Self, SnapshotError> {
+        Self::read_with(reader, Some(key))
+    }
+
+    fn read_with(mut reader: impl Read, key: Option<Blake3Key>) -> Result<Self, SnapshotError> {
+        let header = Self::read_header_from(&mut reader)?;
+
+        let primary = SnapshotAlgorithm {
+            algorithm: header.algorithm,
+            context: header.context.clone(),
+            output_len: header.output_len,
+            key_fingerprint: header.key_fingerprint,
+        };
+        let algorithm = primary.to_algorithm(key.clone())?;
+        let additional_algorithms = header
+            .additional_algorithms
+            .iter()
+            .map(|additional| additional.to_algorithm(key.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let root: MerkleNode = bincode::decode_from_std_read(&mut reader, bincode::config::standard())?;
+
+        let tree = MerkleTree {
+            root,
+            algorithm,
+            additional_algorithms,
+            chunking: header.chunking,
+            hash_names: header.hash_names,
+        };
+
+        Ok(Self { header, tree })
+    }
+
+    fn read_header_from(reader: &mut impl Read) -> Result<SnapshotHeader, SnapshotError> {
+        let config = bincode::config::standard();
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidMagic(magic));
+        }
+
+        let format_version: u16 = bincode::decode_from_std_read(reader, config)?;
+        if format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(SnapshotError::UnsupportedFormatVersion(format_version));
+        }
+
+        let raw: RawHeader = bincode::decode_from_std_read(reader, config)?;
+        if raw.hash_scheme != HASH_SCHEME_VERSION {
+            return Err(SnapshotError::UnsupportedHashScheme(raw.hash_scheme));
+        }
+
+        let primary = raw.algorithm.parse()?;
+        let additional_algorithms = raw
+            .additional_algorithms
+            .into_iter()
+            .map(RawAlgorithm::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(SnapshotHeader {
+            format_version,
+            hash_scheme: raw.hash_scheme,
+            crate_version: raw.crate_version,
+            algorithm: primary.algorithm,
+            context: primary.context,
+            output_len: primary.output_len,
+            key_fingerprint: primary.key_fingerprint,
+            additional_algorithms,
+            chunking: raw.chunking,
+            hash_names: raw.hash_names,
+        })
+    }
+}
+
+impl MerkleTree {
+    /// Saves the tree as a versioned snapshot that records how it was built
+    pub fn save_snapshot(&self, writer: impl Write) -> Result<(), SnapshotError> {
+        Snapshot::write(self, writer)
+    }
+
+    /// Loads a tree from a snapshot previously written with [`MerkleTree::save_snapshot`]
+    pub fn load_snapshot(reader: impl Read) -> Result<MerkleTree, SnapshotError> {
+        Snapshot::read(reader).map(|snapshot| snapshot.tree)
+    }
+
+    /// Loads a tree built with a keyed algorithm from a snapshot, checking that the key matches
+    pub fn load_snapshot_keyed(reader: impl Read, key: Blake3Key) -> Result<MerkleTree, SnapshotError> {
+        Snapshot::read_keyed(reader, key).map(|snapshot| snapshot.tree)
+    }
+
+    /// Loads a tree from a snapshot, refusing it if it is not comparable to the given tree
+    pub fn load_snapshot_comparable_to(reader: impl Read, other: &MerkleTree) -> Result<MerkleTree, SnapshotError> {
+        let key = std::iter::once(&other.algorithm)
+            .chain(&other.additional_algorithms)
+            .find_map(|algorithm| algorithm.key().cloned());
+        let snapshot = Snapshot::read_with(reader, key)?;
+        let tree = snapshot.tree;
+        other.ensure_comparable(&tree)?;
+        Ok(tree)
+    }
+
+    /// Checks whether the hashes of two trees can be meaningfully compared,
+    /// meaning they were built with the same algorithm, chunking and name hashing setting
+    pub fn ensure_comparable(&self, other: &MerkleTree) -> Result<(), SnapshotError> {
+        if self.algorithm != other.algorithm {
+            return Err(SnapshotError::AlgorithmMismatch(self.algorithm.clone(), other.algorithm.clone()));
+        }
+        if self.chunking != other.chunking {
+            return Err(SnapshotError::ChunkingMismatch(self.chunking, other.chunking));
+        }
+        if self.hash_names != other.hash_names {
+            return Err(SnapshotError::HashNamesMismatch(self.hash_names, other.hash_names));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::algorithm::Blake3Mode;
+    use crate::utils::fixture::{fixture, path};
+
+    fn tree_bytes(tree: &MerkleTree) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        tree.save_snapshot(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip_keeps_hashes_and_settings() {
+        let dir = fixture(&[("a/b", b"one"), ("a/c", b"two"), ("d", b"three"), ("e/", b"")]);
+        let tree = MerkleTree::builder(path(&dir)).hash_names(true).build().unwrap();
+
+        let bytes = tree_bytes(&tree);
+        let header = Snapshot::read_header(bytes.as_slice()).unwrap();
+        assert_eq!(header.format_version, SNAPSHOT_FORMAT_VERSION);
+        assert_eq!(header.hash_scheme, HASH_SCHEME_VERSION);
+        assert_eq!(header.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(header.algorithm, AlgorithmKind::Blake3);
+        assert!(header.hash_names);
+
+        let loaded = MerkleTree::load_snapshot(bytes.as_slice()).unwrap();
+        assert!(loaded.hash_names);
+        assert_eq!(loaded.root.item.hash, tree.root.item.hash);
+        assert!(loaded.iter().eq(tree.iter()));
+        assert!(loaded.iter().find(|item| item.path.relative.ends_with("e")).unwrap().is_dir);
+    }
+
+    #[test]
+    fn rejects_foreign_and_future_files() {
+        let dir = fixture(&[("a", b"one")]);
+        let mut bytes = tree_bytes(&MerkleTree::builder(path(&dir)).build().unwrap());
+
+        let mut foreign = bytes.clone();
+        foreign[0] = b'X';
+        assert!(matches!(MerkleTree::load_snapshot(foreign.as_slice()), Err(SnapshotError::InvalidMagic(_))));
+
+        bytes[SNAPSHOT_MAGIC.len()] = 99;
+        assert!(matches!(
+            MerkleTree::load_snapshot(bytes.as_slice()),
+            Err(SnapshotError::UnsupportedFormatVersion(99))
+        ));
+    }
+
+    #[test]
+    fn refuses_incomparable_trees() {
+        let dir = fixture(&[("a", b"one")]);
+        let plain = MerkleTree::builder(path(&dir)).build().unwrap();
+        let named = MerkleTree::builder(path(&dir)).hash_names(true).build().unwrap();
+
+        let bytes = tree_bytes(&named);
+        assert!(matches!(
+            MerkleTree::load_snapshot_comparable_to(bytes.as_slice(), &plain),
+            Err(SnapshotError::HashNamesMismatch(false, true))
+        ));
+        assert!(MerkleTree::load_snapshot_comparable_to(bytes.as_slice(), &named).is_ok());
+    }
+
+    #[cfg(feature = "sha")]
+    #[test]
+    fn refuses_sha256_snapshot_against_blake3_tree() {
+        let dir = fixture(&[("a", b"one")]);
+        let blake3 = MerkleTree::builder(path(&dir)).build().unwrap();
+        let sha256 = MerkleTree::builder(path(&dir)).algorithm(Algorithm::Sha256).build().unwrap();
+
+        let bytes = tree_bytes(&sha256);
+        assert!(matches!(
+            MerkleTree::load_snapshot_comparable_to(bytes.as_slice(), &blake3),
+            Err(SnapshotError::AlgorithmMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn keyed_snapshots_need_the_matching_key() {
+        let dir = fixture(&[("a", b"one")]);
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithm(Algorithm::Blake3Keyed(Blake3Key::new([7; 32])))
+            .build()
+            .unwrap();
+
+        let bytes = tree_bytes(&tree);
+        assert!(matches!(MerkleTree::load_snapshot(bytes.as_slice()), Err(SnapshotError::KeyRequired)));
+        assert!(matches!(
+            MerkleTree::load_snapshot_keyed(bytes.as_slice(), Blake3Key::new([8; 32])),
+            Err(SnapshotError::KeyMismatch)
+        ));
+        let loaded = MerkleTree::load_snapshot_keyed(bytes.as_slice(), Blake3Key::new([7; 32])).unwrap();
+        assert_eq!(loaded.root.item.hash, tree.root.item.hash);
+    }
+
+    #[test]
+    fn extended_outputs_keep_their_mode() {
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two")]);
+        let output_len = OutputLength::new(48).unwrap();
+        let keyed = Algorithm::Blake3Xof(Blake3Mode::Keyed(Blake3Key::new([7; 32])), output_len);
+        let tree = MerkleTree::builder(path(&dir))
+            .algorithms([keyed, Algorithm::Blake3DeriveKey("context".to_string())])
+            .build()
+            .unwrap();
+
+        let bytes = tree_bytes(&tree);
+        assert!(matches!(MerkleTree::load_snapshot(bytes.as_slice()), Err(SnapshotError::KeyRequired)));
+        let loaded = MerkleTree::load_snapshot_keyed(bytes.as_slice(), Blake3Key::new([7; 32])).unwrap();
+        assert_eq!(loaded.algorithm, tree.algorithm);
+        assert_eq!(loaded.additional_algorithms, tree.additional_algorithms);
+        assert!(loaded.iter().eq(tree.iter()));
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+
+use crate::components::digest::Digest;
+use crate::components::sync_diff::SyncDiff;
+use crate::error::SyncError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::sync_wire::{Frame, SyncEntry, SyncSettings, LIST_HEADER_LEN, MAX_FRAME_LEN};
+
+/// A directory whose children are requested from the responder, with the local node at its path if any
+type Pending<'a> = (String, Option<&'a MerkleNode>);
+
+/// Hashes sent to the peer by slash separated relative path, see [`MerkleTree::sync_hashes`]
+type SyncHashes = HashMap<String, Digest>;
+
+impl MerkleTree {
+    /// Runs the initiating side of a sync session over any transport, returning the differences it found
+    ///
+    /// Both peers exchange their settings and root hashes, then the initiator requests the children of
+    /// differing directories level by level, so only listings of changed directories cross the transport.
+    /// Directories are compared by hashes that cover the names and kinds of everything below them,
+    /// so renames are found even when the tree does not hash names.
+    /// Requests are split into batches that fit in a frame, and listed names that are not single path
+    /// segments are refused, so the reported paths never leave the tree.
+    /// The session ends by sending the differences to the responder.
+    pub fn sync_initiate(&self, mut stream: impl Read + Write) -> Result<SyncDiff, SyncError> {
+        let hashes = self.sync_hashes()?;
+        let (remote_root_hash, remote_root_is_dir) = self.sync_handshake(&mut stream, &hashes)?;
+
+        let mut diff = SyncDiff::default();
+        if remote_root_is_dir != self.root.item.is_dir || remote_root_hash != hashes[""] {
+            let root_name = Self::sync_name(&self.root)?;
+            match (self.root.item.is_dir, remote_root_is_dir) {
+                (true, true) => {
+                    let pending = vec![(String::new(), Some(&self.root))];
+                    self.sync_descend(&mut stream, &hashes, pending, &mut diff)?
+                }
+                (false, false) => diff.changed.push(root_name),
+                (true, false) => {
+                    Self::sync_local_files(&self.root, "", &mut diff.only_local)?;
+                    diff.only_remote.push(root_name);
+                }
+                (false, true) => {
+                    diff.only_local.push(root_name);
+                    self.sync_descend(&mut stream, &hashes, vec![(String::new(), None)], &mut diff)?;
+                }
+            }
+        }
+
+        diff.changed.sort();
+        diff.only_local.sort();
+        diff.only_remote.sort();
+
+        let done = Frame::Done {
+            changed: diff.changed.clone(),
+            only_local: diff.only_local.clone(),
+            only_remote: diff.only_remote.clone(),
+        };
+        done.write(&mut stream)?;
+        Ok(diff)
+    }
+
+    /// Runs the responding side of a sync session over any transport, answering listings until the initiator is done
+    ///
+    /// Returns the differences found by the initiator, as seen from this side.
+    pub fn sync_respond(&self, mut stream: impl Read + Write) -> Result<SyncDiff, SyncError> {
+        let hashes = self.sync_hashes()?;
+        self.sync_handshake(&mut stream, &hashes)?;
+
+        loop {
+            match Frame::read(&mut stream, self.algorithm.kind())? {
+                Frame::List(paths) => {
+                    // Answers as many paths as fit in a frame, the initiator asks again for the rest
+                    let mut listings = Vec::new();
+                    let mut len = LIST_HEADER_LEN;
+                    for path in &paths {
+                        let listing = self.sync_listing(&hashes, path)?;
+                        len += Frame::listing_len(&listing);
+                        if len > MAX_FRAME_LEN as usize && !listings.is_empty() {
+                            break;
+                        }
+                        listings.push(listing);
+                    }
+                    Frame::Listing(listings).write(&mut stream)?;
+                }
+                Frame::Done {
+                    changed,
+                    only_local,
+                    only_remote,
+                } => {
+                    let diff = SyncDiff {
+                        changed,
+                        only_local,
+                        only_remote,
+                    };
+                    return Ok(diff.mirrored());
+                }
+                _ => return Err(SyncError::UnexpectedFrame),
+            }
+        }
+    }
+
+    /// Connects to a peer serving its tree over TCP and initiates a sync session
+    pub fn sync_tcp(&self, addr: impl ToSocketAddrs) -> Result<SyncDiff, SyncError> {
+        let stream = TcpStream::connect(addr).map_err(SyncError::Io)?;
+        stream.set_nodelay(true).map_err(SyncError::Io)?;
+        self.sync_initiate(&stream)
+    }
+
+    /// Accepts a single TCP connection and responds to its sync session
+    pub fn serve_sync_tcp(&self, listener: &TcpListener) -> Result<SyncDiff, SyncError> {
+        let (stream, _) = listener.accept().map_err(SyncError::Io)?;
+        stream.set_nodelay(true).map_err(SyncError::Io)?;
+        self.sync_respond(&stream)
+    }
+
+    /// Connects to a peer serving its tree over a Unix socket and initiates a sync session
+    #[cfg(unix)]
+    pub fn sync_unix(&self, path: impl AsRef<Path>) -> Result<SyncDiff, SyncError> {
+        let stream = std::os::unix::net::UnixStream::connect(path).map_err(SyncError::Io)?;
+        self.sync_initiate(&stream)
+    }
+
+    /// Accepts a single Unix socket connection and responds to its sync session
+    #[cfg(unix)]
+    pub fn serve_sync_unix(&self, listener: &std::os::unix::net::UnixListener) -> Result<SyncDiff, SyncError> {
+        let (stream, _) = listener.accept().map_err(SyncError::Io)?;
+        self.sync_respond(&stream)
+    }
+
+    /// Exchanges settings and roots with the peer, returning the peer's root hash and whether it is a directory
+    fn sync_handshake(&self, stream: &mut (impl Read + Write), hashes: &SyncHashes) -> Result<(Digest, bool), SyncError> {
+        let settings = SyncSettings {
+            algorithm: self.algorithm.kind(),
+            context: self.algorithm.context().map(str::to_string),
+            output_len: self.algorithm.output_len(),
+            key_fingerprint: self.algorithm.key_fingerprint(),
+            hash_names: self.hash_names,
+            chunking: self.chunking,
+        };
+        let hello = Frame::Hello {
+            settings: settings.clone(),
+            root_hash: hashes[""],
+            root_is_dir: self.root.item.is_dir,
+        };
+        hello.write(stream)?;
+
+        match Frame::read(stream, self.algorithm.kind())? {
+            Frame::Hello {
+                settings: remote_settings,
+                root_hash,
+                root_is_dir,
+            } => match remote_settings == settings {
+                true => Ok((root_hash, root_is_dir)),
+                false => Err(SyncError::SettingsMismatch),
+            },
+            _ => Err(SyncError::UnexpectedFrame),
+        }
+    }
+
+    /// Requests the children of differing directories level by level and compares them with the local ones
+    fn sync_descend<'a>(
+        &'a self,
+        stream: &mut (impl Read + Write),
+        hashes: &SyncHashes,
+        mut pending: Vec<Pending<'a>>,
+        diff: &mut SyncDiff,
+    ) -> Result<(), SyncError> {
+        let mut next = Vec::new();
+        while !pending.is_empty() {
+            let batch_len = Self::sync_batch_len(&pending, MAX_FRAME_LEN as usize);
+            Frame::List(pending[..batch_len].iter().map(|(path, _)| path.clone()).collect()).write(stream)?;
+            let listings = match Frame::read(stream, self.algorithm.kind())? {
+                Frame::Listing(listings) if !listings.is_empty() && listings.len() <= batch_len => listings,
+                _ => return Err(SyncError::UnexpectedFrame),
+            };
+
+            let answered: Vec<_> = pending.drain(..listings.len()).collect();
+            for ((path, local), listing) in answered.into_iter().zip(listings) {
+                let entries = match listing {
+                    Some(entries) => entries,
+                    None => return Err(SyncError::UnexpectedFrame),
+                };
+
+                let mut names = HashSet::new();
+                for entry in &entries {
+                    if !Self::sync_is_segment(&entry.name) || !names.insert(entry.name.as_str()) {
+                        return Err(SyncError::Malformed);
+                    }
+                }
+
+                let mut local_children = BTreeMap::new();
+                if let Some(local) = local {
+                    for child in &local.children {
+                        local_children.insert(Self::sync_name(child)?, child);
+                    }
+                }
+
+                for entry in entries {
+                    let full = Self::sync_join(&path, &entry.name);
+                    let child = match local_children.remove(&entry.name) {
+                        Some(child) => child,
+                        None => {
+                            match entry.is_dir {
+                                true => next.push((full, None)),
+                                false => diff.only_remote.push(full),
+                            }
+                            continue;
+                        }
+                    };
+
+                    if child.item.is_dir == entry.is_dir && hashes.get(&full) == Some(&entry.hash) {
+                        continue;
+                    }
+                    match (child.item.is_dir, entry.is_dir) {
+                        (true, true) => next.push((full, Some(child))),
+                        (false, false) => diff.changed.push(full),
+                        (true, false) => {
+                            Self::sync_local_files(child, &full, &mut diff.only_local)?;
+                            diff.only_remote.push(full);
+                        }
+                        (false, true) => {
+                            diff.only_local.push(full.clone());
+                            next.push((full, None));
+                        }
+                    }
+                }
+
+                for (name, child) in local_children {
+                    Self::sync_local_files(child, &Self::sync_join(&path, &name), &mut diff.only_local)?;
+                }
+            }
+
+            if pending.is_empty() {
+                pending = std::mem::take(&mut next);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how many of the first pending paths fit in a list request of at most the given length, at least one
+    fn sync_batch_len(pending: &[Pending<'_>], max_len: usize) -> usize {
+        let mut len = LIST_HEADER_LEN;
+        let fitting = pending
+            .iter()
+            .take_while(|(path, _)| {
+                len += Frame::list_item_len(path);
+                len <= max_len
+            })
+            .count();
+        fitting.max(1).min(pending.len())
+    }
+
+    /// Returns whether a listed name is a single path segment, so joining it cannot leave its directory
+    fn sync_is_segment(name: &str) -> bool {
+        !name.is_empty() && name != "." && name != ".." && !name.contains('/')
+    }
+
+    /// Lists the children of the directory at a relative path, none if it is not a directory
+    ///
+    /// Empty segments are skipped, so `/` and `a//b/` are read as the root and `a/b`.
+    fn sync_listing(&self, hashes: &SyncHashes, path: &str) -> Result<Option<Vec<SyncEntry>>, SyncError> {
+        let segments: Vec<_> = path.split('/').filter(|name| !name.is_empty()).collect();
+        let mut node = &self.root;
+        for name in &segments {
+            let child = node
+                .children
+                .iter()
+                .find(|child| Self::sync_name(child).is_ok_and(|child_name| child_name == *name));
+            node = match child {
+                Some(child) => child,
+                None => return Ok(None),
+            };
+        }
+        if !node.item.is_dir {
+            return Ok(None);
+        }
+
+        let path = segments.join("/");
+        let mut entries = Vec::new();
+        for child in &node.children {
+            let name = Self::sync_name(child)?;
+            let hash = match hashes.get(&Self::sync_join(&path, &name)) {
+                Some(hash) => *hash,
+                None => return Err(SyncError::Malformed),
+            };
+            entries.push(SyncEntry {
+                hash,
+                name,
+                is_dir: child.item.is_dir,
+            });
+        }
+        Ok(Some(entries))
+    }
+
+    /// Collects the paths of a local node's files, the node itself if it is a file
+    fn sync_local_files(node: &MerkleNode, path: &str, files: &mut Vec<String>) -> Result<(), SyncError> {
+        if !node.item.is_dir {
+            files.push(path.to_string());
+            return Ok(());
+        }
+        for child in &node.children {
+            Self::sync_local_files(child, &Self::sync_join(path, &Self::sync_name(child)?), files)?;
+        }
+        Ok(())
+    }
+
+    /// Computes the hash sent to the peer for every node, by slash separated relative path
+    ///
+    /// Files are sent with their own hash. Directories are sent with the hash of the name, kind and sent hash
+    /// of each child in order, as directory hashes built without names do not change when children are renamed.
+    fn sync_hashes(&self) -> Result<SyncHashes, SyncError> {
+        let mut hashes = HashMap::new();
+        self.sync_hash(&self.root, String::new(), &mut hashes)?;
+        Ok(hashes)
+    }
+
+    fn sync_hash(&self, node: &MerkleNode, path: String, hashes: &mut SyncHashes) -> Result<Digest, SyncError> {
+        let hash = match node.item.is_dir {
+            true => {
+                let mut listing = Vec::new();
+                for child in &node.children {
+                    let name = Self::sync_name(child)?;
+                    let child_hash = self.sync_hash(child, Self::sync_join(&path, &name), hashes)?;
+                    listing.extend_from_slice(&(name.len() as u32).to_be_bytes());
+                    listing.extend_from_slice(name.as_bytes());
+                    listing.push(child.item.is_dir as u8);
+                    listing.extend_from_slice(child_hash.as_bytes());
+                }
+                self.algorithm.compute_hash(&listing)
+            }
+            false => node.item.hash,
+        };
+        hashes.insert(path, hash);
+        Ok(hash)
+    }
+
+    /// Returns the name of a node as it is sent to the peer
+    fn sync_name(node: &MerkleNode) -> Result<String, SyncError> {
+        let absolute: &Path = node.item.path.absolute.as_ref();
+        match absolute.file_name().map(|name| name.to_str()) {
+            Some(Some(name)) => Ok(name.to_string()),
+            Some(None) => Err(SyncError::NonUtf8Path(absolute.to_path_buf())),
+            None => Ok(String::new()),
+        }
+    }
+
+    fn sync_join(path: &str, name: &str) -> String {
+        match path.is_empty() {
+            true => name.to_string(),
+            false => format!("{}/{}", path, name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::components::sync_diff::SyncDiff;
+    use crate::error::SyncError;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path};
+    use crate::utils::sync_wire::{Frame, SyncEntry};
+
+    fn tree(entries: &[(&str, &[u8])]) -> (tempfile::TempDir, MerkleTree) {
+        let dir = fixture(entries);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        (dir, tree)
+    }
+
+    /// Runs a session between two trees over a pair of connected streams, returning both sides' differences
+    fn session<S: Read + Write + Send>(local: &MerkleTree, remote: &MerkleTree, streams: (S, S)) -> (SyncDiff, SyncDiff) {
+        let (initiator, responder) = streams;
+        thread::scope(|scope| {
+            let responding = scope.spawn(|| remote.sync_respond(responder).unwrap());
+            let diff = local.sync_initiate(initiator).unwrap();
+            (diff, responding.join().unwrap())
+        })
+    }
+
+    #[cfg(unix)]
+    fn unix_session(local: &MerkleTree, remote: &MerkleTree) -> (SyncDiff, SyncDiff) {
+        session(local, remote, std::os::unix::net::UnixStream::pair().unwrap())
+    }
+
+    fn diff(changed: &[&str], only_local: &[&str], only_remote: &[&str]) -> SyncDiff {
+        let strings = |paths: &[&str]| paths.iter().map(|path| path.to_string()).collect();
+        SyncDiff {
+            changed: strings(changed),
+            only_local: strings(only_local),
+            only_remote: strings(only_remote),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_modified_added_deleted_and_type_changed_files() {
+        let (_local_dir, local) = tree(&[("a", b"1"), ("b", b"2"), ("c", b"3"), ("d/e", b"4"), ("f", b"5")]);
+        let (_remote_dir, remote) = tree(&[("a", b"1"), ("b", b"two"), ("d", b"4"), ("f/g", b"5"), ("new", b"6")]);
+
+        let (initiated, responded) = unix_session(&local, &remote);
+        let expected = diff(&["b"], &["c", "d/e", "f"], &["d", "f/g", "new"]);
+        assert_eq!(initiated, expected);
+        assert_eq!(responded, expected.mirrored());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finds_renames_without_hashed_names() {
+        let (_local_dir, local) = tree(&[("dir/x", b"same"), ("y", b"y"), ("z/a", b"q"), ("empty/", b"")]);
+        let (_remote_dir, remote) = tree(&[("renamed/x", b"same"), ("y", b"y"), ("z/b", b"q"), ("empty", b"")]);
+        assert!(!local.hash_names);
+
+        let (initiated, _) = unix_session(&local, &remote);
+        assert_eq!(initiated, diff(&[], &["dir/x", "z/a"], &["empty", "renamed/x", "z/b"]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn identical_trees_only_exchange_roots() {
+        let (_local_dir, local) = tree(&[("a/b", b"1"), ("c", b"2")]);
+        let (_remote_dir, remote) = tree(&[("a/b", b"1"), ("c", b"2")]);
+
+        let (initiated, responded) = unix_session(&local, &remote);
+        assert!(initiated.is_empty() && responded.is_empty());
+    }
+
+    #[test]
+    fn syncs_over_tcp() {
+        let (_local_dir, local) = tree(&[("a", b"1"), ("sub/b", b"2"), ("sub/c", b"3")]);
+        let (_remote_dir, remote) = tree(&[("a", b"one"), ("sub/b", b"2"), ("sub/d", b"3")]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (initiated, responded) = thread::scope(|scope| {
+            let responding = scope.spawn(|| remote.serve_sync_tcp(&listener).unwrap());
+            let diff = local.sync_tcp(addr).unwrap();
+            (diff, responding.join().unwrap())
+        });
+
+        let expected = diff(&["a"], &["sub/c"], &["sub/d"]);
+        assert_eq!(initiated, expected);
+        assert_eq!(responded, expected.mirrored());
+    }
+
+    /// Runs a session against a peer following a script instead of the protocol, returning the initiator's result
+    #[cfg(unix)]
+    fn scripted_session(
+        local: &MerkleTree,
+        remote: &MerkleTree,
+        script: impl FnOnce(&mut std::os::unix::net::UnixStream) + Send,
+    ) -> Result<SyncDiff, SyncError> {
+        let (initiator, mut responder) = std::os::unix::net::UnixStream::pair().unwrap();
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                remote.sync_handshake(&mut responder, &remote.sync_hashes().unwrap()).unwrap();
+                script(&mut responder);
+            });
+            local.sync_initiate(initiator)
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn responds_to_hostile_paths_without_panicking() {
+        let (_remote_dir, remote) = tree(&[("a/b/c", b"1"), ("d", b"2")]);
+        let hashes = remote.sync_hashes().unwrap();
+        let (mut initiator, responder) = std::os::unix::net::UnixStream::pair().unwrap();
+
+        thread::scope(|scope| {
+            let responding = scope.spawn(|| remote.sync_respond(responder));
+            remote.sync_handshake(&mut initiator, &hashes).unwrap();
+
+            let paths = ["/", "a/", "a//b", "../a", "d"].map(str::to_string).to_vec();
+            Frame::List(paths).write(&mut initiator).unwrap();
+            let expected = ["", "a", "a/b"].map(|path| remote.sync_listing(&hashes, path).unwrap());
+            let listing = Frame::read(&mut initiator, remote.algorithm.kind()).unwrap();
+            assert_eq!(listing, Frame::Listing([expected.to_vec(), vec![None, None]].concat()));
+
+            let done = Frame::Done {
+                changed: Vec::new(),
+                only_local: Vec::new(),
+                only_remote: Vec::new(),
+            };
+            done.write(&mut initiator).unwrap();
+            assert!(responding.join().unwrap().unwrap().is_empty());
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_listed_names_that_leave_their_directory() {
+        let (_local_dir, local) = tree(&[("a", b"1")]);
+        let (_remote_dir, remote) = tree(&[("a", b"2")]);
+        let hash = remote.root.item.hash;
+
+        let unsafe_names: &[&[&str]] = &[&[".."], &["."], &[""], &["a/b"], &["../../etc/x"], &["a", "a"]];
+        for names in unsafe_names {
+            let entries: Vec<_> = names
+                .iter()
+                .map(|name| SyncEntry {
+                    name: name.to_string(),
+                    is_dir: false,
+                    hash,
+                })
+                .collect();
+            let result = scripted_session(&local, &remote, |stream| {
+                Frame::read(stream, remote.algorithm.kind()).unwrap();
+                Frame::Listing(vec![Some(entries)]).write(stream).unwrap();
+            });
+            assert!(matches!(result, Err(SyncError::Malformed)), "{:?}", names);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn asks_again_for_paths_left_unanswered() {
+        let (_local_dir, local) = tree(&[("a/x", b"1"), ("b/y", b"2"), ("c/z", b"3")]);
+        let (_remote_dir, remote) = tree(&[("a/x", b"one"), ("b/y", b"two"), ("c/z", b"three")]);
+        let expected = unix_session(&local, &remote).0;
+        assert_eq!(expected, diff(&["a/x", "b/y", "c/z"], &[], &[]));
+
+        // Answers a single path of every request, as a responder does when the full answer would not fit
+        let hashes = remote.sync_hashes().unwrap();
+        let initiated = scripted_session(&local, &remote, |stream| {
+            while let Frame::List(paths) = Frame::read(stream, remote.algorithm.kind()).unwrap() {
+                let listing = remote.sync_listing(&hashes, &paths[0]).unwrap();
+                Frame::Listing(vec![listing]).write(stream).unwrap();
+            }
+        });
+        assert_eq!(initiated.unwrap(), expected);
+    }
+
+    #[test]
+    fn batches_list_requests_to_fit_in_a_frame() {
+        let pending: Vec<_> = ["a", "bb", "ccc"].iter().map(|path| (path.to_string(), None)).collect();
+        // The tag and count take 5 bytes, every path its length and 4 more
+        assert_eq!(MerkleTree::sync_batch_len(&pending, 5 + 5 + 6), 2);
+        assert_eq!(MerkleTree::sync_batch_len(&pending, 5 + 5 + 6 + 7), 3);
+        assert_eq!(MerkleTree::sync_batch_len(&pending, 1), 1);
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use crate::components::digest::Digest;
+use crate::error::TorrentError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+use crate::utils::algorithm::Algorithm;
+use crate::utils::bencode::Bencode;
+use crate::utils::bittorrent::PieceLength;
+
+/// An entry of a BitTorrent v2 file tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileTreeNode {
+    Directory(BTreeMap<String, FileTreeNode>),
+    File { length: u64, pieces_root: Option<Digest> },
+}
+
+/// A BitTorrent v2 torrent of a directory or file
+///
+/// Empty directories are left out, as torrents cannot represent them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Torrent {
+    /// Suggested name of the root directory or file
+    pub name: String,
+    /// Number of bytes covered by each piece
+    pub piece_length: PieceLength,
+    /// Files and directories below the root, a single file torrent holds just the file under its name
+    pub file_tree: BTreeMap<String, FileTreeNode>,
+    /// Piece layers of the files larger than a single piece, by their pieces roots
+    pub piece_layers: BTreeMap<Digest, Vec<Digest>>,
+    /// Tracker URL, if any
+    pub announce: Option<String>,
+}
+
+impl Torrent {
+    /// Sets the tracker URL of the torrent
+    pub fn announce(mut self, announce: impl Into<String>) -> Self {
+        self.announce = Some(announce.into());
+        self
+    }
+
+    /// Returns the bencoded info dictionary
+    pub fn info_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.info().encode(&mut output);
+        output
+    }
+
+    /// Returns the v2 info hash, the SHA-256 hash of the bencoded info dictionary
+    pub fn info_hash(&self) -> Digest {
+        Algorithm::Sha256.compute_hash(&self.info_bytes())
+    }
+
+    /// Returns the bencoded `.torrent` file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let piece_layers = self
+            .piece_layers
+            .iter()
+            .map(|(pieces_root, piece_layer)| {
+                let hashes = piece_layer.iter().flat_map(Digest::as_bytes).copied().collect();
+                (pieces_root.as_bytes(), Bencode::Bytes(hashes))
+            });
+
+        let mut entries = vec![
+            (&b"info"[..], self.info()),
+            (&b"piece layers"[..], Bencode::dictionary(piece_layers)),
+        ];
+        if let Some(announce) = &self.announce {
+            entries.push((&b"announce"[..], Bencode::Bytes(announce.as_bytes().to_vec())));
+        }
+
+        let mut output = Vec::new();
+        Bencode::dictionary(entries).encode(&mut output);
+        output
+    }
+
+    fn info(&self) -> Bencode {
+        Bencode::dictionary([
+            (&b"file tree"[..], Self::file_tree(&self.file_tree)),
+            (&b"meta version"[..], Bencode::Integer(2)),
+            (&b"name"[..], Bencode::Bytes(self.name.as_bytes().to_vec())),
+            (&b"piece length"[..], Bencode::Integer(self.piece_length.get() as i64)),
+        ])
+    }
+
+    fn file_tree(entries: &BTreeMap<String, FileTreeNode>) -> Bencode {
+        Bencode::dictionary(entries.iter().map(|(name, node)| {
+            let value = match node {
+                FileTreeNode::Directory(entries) => Self::file_tree(entries),
+                FileTreeNode::File { length, pieces_root } => {
+                    let mut file = vec![(&b"length"[..], Bencode::Integer(*length as i64))];
+                    if let Some(pieces_root) = pieces_root {
+                        file.push((&b"pieces root"[..], Bencode::Bytes(pieces_root.to_vec())));
+                    }
+                    Bencode::dictionary([(&b""[..], Bencode::dictionary(file))])
+                }
+            };
+            (name.as_bytes(), value)
+        }))
+    }
+}
+
+impl MerkleTree {
+    /// Creates a BitTorrent v2 torrent of the tree, which must be built with BitTorrent pieces
+    pub fn to_torrent(&self) -> Result<Torrent, TorrentError> {
+        let piece_length = match self.iter().find_map(|item| item.torrent.as_ref()) {
+            Some(torrent) => torrent.piece_length,
+            None => return Err(TorrentError::MissingPieces),
+        };
+
+        let name = match self.root.item.path.absolute.file_name() {
+            Some(name) => Self::torrent_name(name)?,
+            None => return Err(TorrentError::MissingName),
+        };
+
+        let mut piece_layers = BTreeMap::new();
+        let file_tree = match Self::file_tree_node(&self.root, &mut piece_layers)? {
+            Some(FileTreeNode::Directory(entries)) => entries,
+            Some(file) => BTreeMap::from([(name.clone(), file)]),
+            None => BTreeMap::new(),
+        };
+
+        Ok(Torrent {
+            name,
+            piece_length,
+            file_tree,
+            piece_layers,
+            announce: None,
+        })
+    }
+
+    /// Creates the file tree entry of a node, none for directories without files
+    fn file_tree_node(
+        node: &MerkleNode,
+        piece_layers: &mut BTreeMap<Digest, Vec<Digest>>,
+    ) -> Result<Option<FileTreeNode>, TorrentError> {
+        if let Some(file) = &node.item.torrent {
+            if let (Some(pieces_root), false) = (file.pieces_root, file.piece_layer.is_empty()) {
+                piece_layers.insert(pieces_root, file.piece_layer.clone());
+            }
+            return Ok(Some(FileTreeNode::File {
+                length: file.length,
+                pieces_root: file.pieces_root,
+            }));
+        }
+
+        let mut entries = BTreeMap::new();
+        for child in &node.children {
+            let name = match child.item.path.relative.file_name() {
+                Some(name) => Self::torrent_name(name)?,
+                None => continue,
+            };
+            if let Some(entry) = Self::file_tree_node(child, piece_layers)? {
+                entries.insert(name, entry);
+            }
+        }
+
+        match entries.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(FileTreeNode::Directory(entries))),
+        }
+    }
+
+    #[cfg(feature = "camino")]
+    fn torrent_name(name: &str) -> Result<String, TorrentError> {
+        Ok(name.to_string())
+    }
+
+    #[cfg(not(feature = "camino"))]
+    fn torrent_name(name: &std::ffi::OsStr) -> Result<String, TorrentError> {
+        match name.to_str() {
+            Some(name) => Ok(name.to_string()),
+            None => Err(TorrentError::NonUtf8Name(name.to_string_lossy().into_owned())),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::components::digest::Digest;
+use crate::components::merkle_path::MerklePath;
+use crate::components::verification_report::VerificationReport;
+use crate::error::IndexingError;
+use crate::tree::merkle_node::MerkleNode;
+use crate::tree::merkle_tree::MerkleTree;
+
+/// An entry of a verified directory, hashed the way the tree was built
+struct DiskNode {
+    path: MerklePath,
+    kind: DiskKind,
+    /// None when the entry or one of its descendants cannot be hashed
+    hash: Option<Digest>,
+    /// Sorted by relative path, like the children of a node
+    children: Vec<DiskNode>,
+}
+
+/// Kinds of entries of a verified directory
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DiskKind {
+    File,
+    Dir,
+    /// Links to directories, dangling links and special files, which are never read
+    Other,
+}
+
+impl MerkleTree {
+    /// Verifies a directory on disk against the tree, such as a deployment against its release snapshot
+    ///
+    /// The directory is hashed the way the tree was built, with its primary algorithm, chunking and name hashing
+    /// setting, then compared from the root down. Subtrees whose hashes, names and kinds all match are not
+    /// descended into, so only the differing paths are visited. Entries are matched by name and kind, not hash.
+    /// Links below the root are only followed to files, links to directories and dangling links are never read
+    /// and count as entries of another type.
+    pub fn verify_dir(&self, path: impl AsRef<str>) -> Result<VerificationReport, IndexingError> {
+        let root = path.as_ref();
+        let actual = self.disk_node(root, MerkleNode::root_path(root), true)?;
+        let mut report = VerificationReport::default();
+        Self::verify_node(&self.root, actual, &mut report);
+        Ok(report)
+    }
+
+    /// Hashes an entry on disk and its descendants, following a link at the entry itself only if asked to
+    fn disk_node(&self, root: &str, path: MerklePath, follow: bool) -> Result<DiskNode, IndexingError> {
+        let metadata = match follow {
+            true => fs::metadata(&path.absolute),
+            false => fs::symlink_metadata(&path.absolute),
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            Err(err) => return Err(IndexingError::UnableToReadFile(path.absolute, err)),
+        };
+        let kind = if metadata.is_dir() {
+            DiskKind::Dir
+        } else if metadata.is_file() {
+            DiskKind::File
+        } else {
+            match metadata.is_symlink() && fs::metadata(&path.absolute).is_ok_and(|target| target.is_file()) {
+                true => DiskKind::File,
+                false => DiskKind::Other,
+            }
+        };
+
+        match kind {
+            DiskKind::File => {
+                let contents = match fs::read(&path.absolute) {
+                    Ok(contents) => contents,
+                    Err(err) => return Err(IndexingError::UnableToReadFile(path.absolute, err)),
+                };
+                let hash = Some(self.file_path_hash(&path, &contents));
+                Ok(DiskNode {
+                    path,
+                    kind,
+                    hash,
+                    children: Vec::new(),
+                })
+            }
+            DiskKind::Dir => self.disk_dir(root, path),
+            DiskKind::Other => Ok(DiskNode {
+                path,
+                kind,
+                hash: None,
+                children: Vec::new(),
+            }),
+        }
+    }
+
+    /// Hashes a directory on disk from the hashes of its children
+    fn disk_dir(&self, root: &str, path: MerklePath) -> Result<DiskNode, IndexingError> {
+        let read_dir = match fs::read_dir(&path.absolute) {
+            Ok(read_dir) => read_dir,
+            Err(err) => return Err(IndexingError::UnableToReadDir(path.absolute, err)),
+        };
+        let mut child_paths = Vec::new();
+        for entry in read_dir {
+            match entry {
+                Ok(entry) => child_paths.push(MerkleNode::child_path(root, entry.path())?),
+                Err(err) => return Err(IndexingError::UnableToReadDirEntry(path.absolute, err)),
+            }
+        }
+
+        let disk_child = |child: MerklePath| self.disk_node(root, child, false);
+        #[cfg(feature = "parallel")]
+        let children = child_paths
+            .into_par_iter()
+            .map(disk_child)
+            .collect::<Result<Vec<_>, IndexingError>>();
+        #[cfg(not(feature = "parallel"))]
+        let children = child_paths
+            .into_iter()
+            .map(disk_child)
+            .collect::<Result<Vec<_>, IndexingError>>();
+        let mut children = children?;
+        children.sort_by(|first, second| first.path.cmp(&second.path));
+
+        let hashes: Option<Vec<_>> = children.iter().map(|child| child.hash).collect();
+        let hash = match hashes {
+            Some(hashes) => {
+                let contents_hash = MerkleNode::directory_hash_of(&hashes, &self.algorithm, cfg!(feature = "parallel"));
+                let named = MerkleNode::named_hashes(
+                    &path,
+                    (contents_hash, BTreeMap::new()),
+                    self.hash_names,
+                    &self.algorithm,
+                    &[],
+                )?;
+                Some(named.0)
+            }
+            None => None,
+        };
+
+        Ok(DiskNode {
+            path,
+            kind: DiskKind::Dir,
+            hash,
+            children,
+        })
+    }
+
+    /// Compares a stored node with the hashed entry at the same relative path on disk
+    fn verify_node(expected: &MerkleNode, actual: DiskNode, report: &mut VerificationReport) {
+        let is_dir = match actual.kind {
+            DiskKind::Dir => true,
+            DiskKind::File => false,
+            DiskKind::Other => {
+                report.type_changed.push(actual.path);
+                return;
+            }
+        };
+        if is_dir != expected.item.is_dir {
+            report.type_changed.push(actual.path);
+            return;
+        }
+
+        // Hashes leave out kinds, and names too if they are not hashed, so both are compared on their own
+        if actual.hash == Some(expected.item.hash) && Self::verify_same_shape(expected, &actual) {
+            return;
+        }
+        if !is_dir {
+            report.modified.push(actual.path);
+            return;
+        }
+
+        // Pairs children by name, a stored child without a counterpart is missing
+        let mut actual_children: BTreeMap<_, _> = actual
+            .children
+            .into_iter()
+            .map(|child| (child.path.relative.clone(), child))
+            .collect();
+        for expected_child in &expected.children {
+            match actual_children.remove(&expected_child.item.path.relative) {
+                Some(actual_child) => Self::verify_node(expected_child, actual_child, report),
+                None => {
+                    let relative = expected_child.item.path.relative.clone();
+                    let absolute = actual.path.absolute.join(relative.file_name().unwrap_or_default());
+                    report.missing.push(MerklePath::new(relative, absolute));
+                }
+            }
+        }
+        report.extra.extend(actual_children.into_values().map(|child| child.path));
+    }
+
+    /// Returns whether a stored node and an entry on disk have descendants of the same names and kinds
+    fn verify_same_shape(expected: &MerkleNode, actual: &DiskNode) -> bool {
+        expected.children.len() == actual.children.len()
+            && expected.children.iter().zip(&actual.children).all(|(expected, actual)| {
+                expected.item.path.relative == actual.path.relative
+                    && match actual.kind {
+                        DiskKind::Dir => expected.item.is_dir,
+                        DiskKind::File => !expected.item.is_dir,
+                        DiskKind::Other => false,
+                    }
+                    && Self::verify_same_shape(expected, actual)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::components::merkle_path::MerklePath;
+    use crate::tree::merkle_node::MerkleNode;
+    use crate::tree::merkle_tree::MerkleTree;
+    use crate::utils::fixture::{fixture, path, write};
+
+    fn relative(paths: &[MerklePath]) -> Vec<String> {
+        let relative = |path: &MerklePath| AsRef::<std::path::Path>::as_ref(&path.relative).to_string_lossy().into_owned();
+        paths.iter().map(relative).collect()
+    }
+
+    #[test]
+    fn reports_each_kind_of_difference() {
+        let dir = fixture(&[("same", b"same"), ("changed", b"before"), ("gone", b"gone"), ("sub/file", b"file")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+        assert!(tree.verify_dir(path(&dir)).unwrap().is_clean());
+
+        write(dir.path(), "changed", b"after");
+        fs::remove_file(dir.path().join("gone")).unwrap();
+        write(dir.path(), "new", b"new");
+        fs::remove_dir_all(dir.path().join("sub")).unwrap();
+        write(dir.path(), "sub", b"file");
+
+        let report = tree.verify_dir(path(&dir)).unwrap();
+        assert_eq!(relative(&report.modified), ["changed"]);
+        assert_eq!(relative(&report.missing), ["gone"]);
+        assert_eq!(relative(&report.extra), ["new"]);
+        assert_eq!(relative(&report.type_changed), ["sub"]);
+    }
+
+    #[test]
+    fn renames_are_reported_without_hashed_names() {
+        for hash_names in [false, true] {
+            let dir = fixture(&[("a", b"same"), ("sub/b", b"other")]);
+            let tree = MerkleTree::builder(path(&dir)).hash_names(hash_names).build().unwrap();
+
+            fs::rename(dir.path().join("a"), dir.path().join("z")).unwrap();
+            fs::rename(dir.path().join("sub/b"), dir.path().join("sub/c")).unwrap();
+
+            let report = tree.verify_dir(path(&dir)).unwrap();
+            assert_eq!(relative(&report.missing), ["a", "sub/b"]);
+            assert_eq!(relative(&report.extra), ["sub/c", "z"]);
+            assert!(report.modified.is_empty() && report.type_changed.is_empty());
+        }
+    }
+
+    #[test]
+    fn empty_directories_and_empty_files_differ() {
+        let dir = fixture(&[("empty/", b""), ("blank", b"")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        fs::remove_dir(dir.path().join("empty")).unwrap();
+        write(dir.path(), "empty", b"");
+        fs::remove_file(dir.path().join("blank")).unwrap();
+        write(dir.path(), "blank/", b"");
+
+        let report = tree.verify_dir(path(&dir)).unwrap();
+        assert_eq!(relative(&report.type_changed), ["blank", "empty"]);
+        assert!(report.modified.is_empty() && report.missing.is_empty() && report.extra.is_empty());
+    }
+
+    // Dangling links cannot be read, so they must be reported without being followed
+    #[cfg(unix)]
+    #[test]
+    fn dangling_links_are_not_read() {
+        use std::os::unix::fs::symlink;
+
+        let dir = fixture(&[("file", b"file")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        fs::remove_file(dir.path().join("file")).unwrap();
+        write(dir.path(), "file/", b"");
+        symlink("nowhere", dir.path().join("file/dangling")).unwrap();
+        write(dir.path(), "extra/", b"");
+        symlink("nowhere", dir.path().join("extra/dangling")).unwrap();
+
+        let report = tree.verify_dir(path(&dir)).unwrap();
+        assert_eq!(relative(&report.type_changed), ["file"]);
+        assert_eq!(relative(&report.extra), ["extra"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_to_directories_are_type_changes() {
+        use std::os::unix::fs::symlink;
+
+        let dir = fixture(&[("sub/file", b"file"), ("linked", b"linked")]);
+        let elsewhere = fixture(&[("file", b"file")]);
+        let tree = MerkleTree::builder(path(&dir)).build().unwrap();
+
+        fs::remove_dir_all(dir.path().join("sub")).unwrap();
+        symlink(elsewhere.path(), dir.path().join("sub")).unwrap();
+        fs::remove_file(dir.path().join("linked")).unwrap();
+        symlink(elsewhere.path().join("file"), dir.path().join("linked")).unwrap();
+
+        // Links to files are read through, only the contents of the target count
+        let report = tree.verify_dir(path(&dir)).unwrap();
+        assert_eq!(relative(&report.type_changed), ["sub"]);
+        assert_eq!(relative(&report.modified), ["linked"]);
+        assert!(report.missing.is_empty() && report.extra.is_empty());
+    }
+
+    #[test]
+    fn unchanged_directories_hash_like_the_tree() {
+        for hash_names in [false, true] {
+            let dir = fixture(&[("a/b/c", b"one"), ("a/d", b"two"), ("e", b"three")]);
+            let tree = MerkleTree::builder(path(&dir)).hash_names(hash_names).build().unwrap();
+            let actual = tree.disk_node(path(&dir), MerkleNode::root_path(path(&dir)), true).unwrap();
+            assert_eq!(actual.hash, Some(tree.root.item.hash));
+
+            write(dir.path(), "e", b"changed");
+            let report = tree.verify_dir(path(&dir)).unwrap();
+            assert_eq!(relative(&report.modified), ["e"]);
+        }
+    }
+}
@@ -1,102 +1,842 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
 use blake3::Hasher;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-#[cfg(feature = "sha")]
-use sha2::{Digest, Sha256, Sha512};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::components::digest::{Digest, MAX_DIGEST_LEN};
+use crate::error::AlgorithmError;
+
+/// Minimum output length of variable length algorithms in bytes, shorter outputs are not collision resistant enough
+pub const MIN_OUTPUT_LEN: usize = 16;
+
+/// Validated output length of a variable length algorithm,
+/// between [`MIN_OUTPUT_LEN`] and [`MAX_DIGEST_LEN`] bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OutputLength(u8);
+
+impl OutputLength {
+    /// Validates an output length in bytes
+    pub fn new(len: usize) -> Result<Self, AlgorithmError> {
+        if len < MIN_OUTPUT_LEN {
+            return Err(AlgorithmError::OutputLengthTooShort(len, MIN_OUTPUT_LEN));
+        }
+        if len > MAX_DIGEST_LEN {
+            return Err(AlgorithmError::OutputLengthTooLong(len, MAX_DIGEST_LEN));
+        }
+        Ok(Self(len as u8))
+    }
+
+    /// Returns the output length in bytes
+    pub fn get(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Secret 32 byte key of keyed BLAKE3
+///
+/// The key lives in a single allocation that is zeroized once the last clone is dropped,
+/// clones share it instead of copying the key, and it is never printed or persisted.
+#[derive(Clone)]
+pub struct Blake3Key(Arc<Zeroizing<[u8; 32]>>);
+
+impl Blake3Key {
+    /// Moves a key into its zeroizing allocation, zeroizing the copy this function received
+    ///
+    /// Arrays are copied when passed by value, so the caller's array is left untouched and should be zeroized by the caller.
+    pub fn new(mut key: [u8; 32]) -> Self {
+        let inner = Arc::new(Zeroizing::new(key));
+        key.zeroize();
+        Self(inner)
+    }
+
+    /// Returns a public fingerprint of the key, which allows checking that the same key is used without revealing it
+    pub fn fingerprint(&self) -> [u8; 32] {
+        *blake3::keyed_hash(self.as_bytes(), b"merkle_hash key fingerprint").as_bytes()
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Blake3Key {
+    fn from(key: [u8; 32]) -> Self {
+        Self::new(key)
+    }
+}
+
+impl PartialEq for Blake3Key {
+    fn eq(&self, other: &Self) -> bool {
+        // Compares keys in constant time
+        let difference = self
+            .as_bytes()
+            .iter()
+            .zip(other.as_bytes().iter())
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b));
+        std::hint::black_box(difference) == 0
+    }
+}
+
+impl Eq for Blake3Key {}
+
+impl Debug for Blake3Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Never prints the key
+        f.write_str("Blake3Key(..)")
+    }
+}
+
+/// Mode of a BLAKE3 hasher, independent of its output length
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Blake3Mode {
+    /// Plain hashing
+    #[default]
+    Plain,
+    /// Keyed hashing with a secret key
+    Keyed(Blake3Key),
+    /// Key derivation with a hardcoded, globally unique and application specific context string
+    DeriveKey(String),
+}
+
+impl Blake3Mode {
+    fn hasher(&self) -> Hasher {
+        match self {
+            Blake3Mode::Plain => Hasher::new(),
+            Blake3Mode::Keyed(key) => Hasher::new_keyed(key.as_bytes()),
+            Blake3Mode::DeriveKey(context) => Hasher::new_derive_key(context),
+        }
+    }
+}
 
 /// Hashing algorithms to choose from
-#[derive(Default)]
+///
+/// Every algorithm other than the blake3 family is behind a cargo feature of the same family,
+/// use [`Algorithm::is_cryptographic`] to tell apart the ones only suitable for change detection.
+#[derive(Default, Clone)]
 pub enum Algorithm {
+    /// BLAKE3, the default
     #[default]
     Blake3,
+    /// BLAKE3 keyed with a secret 32 byte key, so hashes cannot be precomputed without it
+    Blake3Keyed(Blake3Key),
+    /// BLAKE3 in key derivation mode with the given context string, which should be
+    /// hardcoded, globally unique and application specific
+    Blake3DeriveKey(String),
+    /// BLAKE3 in any mode with an extended output of the given length, applied to every node hash
+    Blake3Xof(Blake3Mode, OutputLength),
+    /// SHA-256
     #[cfg(feature = "sha")]
     Sha256,
+    /// SHA-512
     #[cfg(feature = "sha")]
     Sha512,
+    /// SHA-384
+    #[cfg(feature = "sha")]
+    Sha384,
+    /// SHA3-256
+    #[cfg(feature = "sha3")]
+    Sha3_256,
+    /// SHA-1, only for interoperability with legacy tooling, it is not collision resistant
+    #[cfg(feature = "sha1")]
+    Sha1,
+    /// BLAKE2b with a 512 bit output
+    #[cfg(feature = "blake2")]
+    Blake2b,
+    /// BLAKE2s with a 256 bit output
+    #[cfg(feature = "blake2")]
+    Blake2s,
+    /// XXH3 with a 128 bit output, fast but only suitable for change detection
+    #[cfg(feature = "xxhash")]
+    Xxh3_128,
+    /// CRC-32C, fast but only suitable for change detection
+    #[cfg(feature = "crc32c")]
+    Crc32c,
 }
 
-impl Algorithm {
-    /// Computes a merkle hash from a slice of bytes
-    pub fn compute_merkle_hash(&self, hashes: &[&[u8]]) -> Option<Vec<u8>> {
-        let len = hashes.len();
+/// Identifies which algorithm produced a digest, without carrying any of its parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AlgorithmKind {
+    Blake3,
+    Blake3Keyed,
+    Blake3DeriveKey,
+    Blake3Xof,
+    #[cfg(feature = "sha")]
+    Sha256,
+    #[cfg(feature = "sha")]
+    Sha512,
+    #[cfg(feature = "sha")]
+    Sha384,
+    #[cfg(feature = "sha3")]
+    Sha3_256,
+    #[cfg(feature = "sha1")]
+    Sha1,
+    #[cfg(feature = "blake2")]
+    Blake2b,
+    #[cfg(feature = "blake2")]
+    Blake2s,
+    #[cfg(feature = "xxhash")]
+    Xxh3_128,
+    #[cfg(feature = "crc32c")]
+    Crc32c,
+}
 
-        if len < 1 {
-            return None;
+impl AlgorithmKind {
+    /// Returns the human readable name of the algorithm
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlgorithmKind::Blake3 => "blake3",
+            AlgorithmKind::Blake3Keyed => "blake3-keyed",
+            AlgorithmKind::Blake3DeriveKey => "blake3-derive-key",
+            AlgorithmKind::Blake3Xof => "blake3-xof",
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha256 => "sha256",
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha512 => "sha512",
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha384 => "sha384",
+            #[cfg(feature = "sha3")]
+            AlgorithmKind::Sha3_256 => "sha3-256",
+            #[cfg(feature = "sha1")]
+            AlgorithmKind::Sha1 => "sha1",
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2b => "blake2b",
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2s => "blake2s",
+            #[cfg(feature = "xxhash")]
+            AlgorithmKind::Xxh3_128 => "xxh3-128",
+            #[cfg(feature = "crc32c")]
+            AlgorithmKind::Crc32c => "crc32c",
+        }
+    }
+
+    /// Finds the algorithm with the given human readable name, if it is enabled
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "blake3" => Some(AlgorithmKind::Blake3),
+            "blake3-keyed" => Some(AlgorithmKind::Blake3Keyed),
+            "blake3-derive-key" => Some(AlgorithmKind::Blake3DeriveKey),
+            "blake3-xof" => Some(AlgorithmKind::Blake3Xof),
+            #[cfg(feature = "sha")]
+            "sha256" => Some(AlgorithmKind::Sha256),
+            #[cfg(feature = "sha")]
+            "sha512" => Some(AlgorithmKind::Sha512),
+            #[cfg(feature = "sha")]
+            "sha384" => Some(AlgorithmKind::Sha384),
+            #[cfg(feature = "sha3")]
+            "sha3-256" => Some(AlgorithmKind::Sha3_256),
+            #[cfg(feature = "sha1")]
+            "sha1" => Some(AlgorithmKind::Sha1),
+            #[cfg(feature = "blake2")]
+            "blake2b" => Some(AlgorithmKind::Blake2b),
+            #[cfg(feature = "blake2")]
+            "blake2s" => Some(AlgorithmKind::Blake2s),
+            #[cfg(feature = "xxhash")]
+            "xxh3-128" => Some(AlgorithmKind::Xxh3_128),
+            #[cfg(feature = "crc32c")]
+            "crc32c" => Some(AlgorithmKind::Crc32c),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the algorithm is collision resistant and therefore suitable for security purposes,
+    /// false if it should only be used for detecting accidental changes
+    pub fn is_cryptographic(&self) -> bool {
+        match self {
+            AlgorithmKind::Blake3 => true,
+            AlgorithmKind::Blake3Keyed => true,
+            AlgorithmKind::Blake3DeriveKey => true,
+            AlgorithmKind::Blake3Xof => true,
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha256 => true,
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha512 => true,
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha384 => true,
+            #[cfg(feature = "sha3")]
+            AlgorithmKind::Sha3_256 => true,
+            #[cfg(feature = "sha1")]
+            AlgorithmKind::Sha1 => false,
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2b => true,
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2s => true,
+            #[cfg(feature = "xxhash")]
+            AlgorithmKind::Xxh3_128 => false,
+            #[cfg(feature = "crc32c")]
+            AlgorithmKind::Crc32c => false,
+        }
+    }
+
+    /// Returns the stable numeric identifier of the algorithm, used when persisting trees
+    #[cfg(feature = "bincode")]
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            AlgorithmKind::Blake3 => 0,
+            AlgorithmKind::Blake3Keyed => 10,
+            AlgorithmKind::Blake3DeriveKey => 11,
+            AlgorithmKind::Blake3Xof => 12,
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha256 => 1,
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha512 => 2,
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha384 => 3,
+            #[cfg(feature = "sha3")]
+            AlgorithmKind::Sha3_256 => 4,
+            #[cfg(feature = "sha1")]
+            AlgorithmKind::Sha1 => 5,
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2b => 6,
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2s => 7,
+            #[cfg(feature = "xxhash")]
+            AlgorithmKind::Xxh3_128 => 8,
+            #[cfg(feature = "crc32c")]
+            AlgorithmKind::Crc32c => 9,
+        }
+    }
+
+    /// Finds the algorithm with the given numeric identifier, if it is enabled
+    #[cfg(feature = "bincode")]
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(AlgorithmKind::Blake3),
+            10 => Some(AlgorithmKind::Blake3Keyed),
+            11 => Some(AlgorithmKind::Blake3DeriveKey),
+            12 => Some(AlgorithmKind::Blake3Xof),
+            #[cfg(feature = "sha")]
+            1 => Some(AlgorithmKind::Sha256),
+            #[cfg(feature = "sha")]
+            2 => Some(AlgorithmKind::Sha512),
+            #[cfg(feature = "sha")]
+            3 => Some(AlgorithmKind::Sha384),
+            #[cfg(feature = "sha3")]
+            4 => Some(AlgorithmKind::Sha3_256),
+            #[cfg(feature = "sha1")]
+            5 => Some(AlgorithmKind::Sha1),
+            #[cfg(feature = "blake2")]
+            6 => Some(AlgorithmKind::Blake2b),
+            #[cfg(feature = "blake2")]
+            7 => Some(AlgorithmKind::Blake2s),
+            #[cfg(feature = "xxhash")]
+            8 => Some(AlgorithmKind::Xxh3_128),
+            #[cfg(feature = "crc32c")]
+            9 => Some(AlgorithmKind::Crc32c),
+            _ => None,
         }
+    }
+}
 
-        if len == 1 {
-            return hashes.first().copied().map(|first| first.to_vec());
+impl Algorithm {
+    /// Returns the kind of the algorithm, which digests produced by it are tagged with
+    pub fn kind(&self) -> AlgorithmKind {
+        match self {
+            Algorithm::Blake3 => AlgorithmKind::Blake3,
+            Algorithm::Blake3Keyed(_) => AlgorithmKind::Blake3Keyed,
+            Algorithm::Blake3DeriveKey(_) => AlgorithmKind::Blake3DeriveKey,
+            Algorithm::Blake3Xof(_, _) => AlgorithmKind::Blake3Xof,
+            #[cfg(feature = "sha")]
+            Algorithm::Sha256 => AlgorithmKind::Sha256,
+            #[cfg(feature = "sha")]
+            Algorithm::Sha512 => AlgorithmKind::Sha512,
+            #[cfg(feature = "sha")]
+            Algorithm::Sha384 => AlgorithmKind::Sha384,
+            #[cfg(feature = "sha3")]
+            Algorithm::Sha3_256 => AlgorithmKind::Sha3_256,
+            #[cfg(feature = "sha1")]
+            Algorithm::Sha1 => AlgorithmKind::Sha1,
+            #[cfg(feature = "blake2")]
+            Algorithm::Blake2b => AlgorithmKind::Blake2b,
+            #[cfg(feature = "blake2")]
+            Algorithm::Blake2s => AlgorithmKind::Blake2s,
+            #[cfg(feature = "xxhash")]
+            Algorithm::Xxh3_128 => AlgorithmKind::Xxh3_128,
+            #[cfg(feature = "crc32c")]
+            Algorithm::Crc32c => AlgorithmKind::Crc32c,
         }
+    }
 
-        #[cfg(feature = "parallel")]
-            let chunks = hashes.par_chunks(2);
+    /// Returns the human readable name of the algorithm
+    pub fn name(&self) -> &'static str {
+        self.kind().name()
+    }
 
-        #[cfg(not(feature = "parallel"))]
-            let chunks = hashes.chunks(2);
+    /// Returns true if the algorithm is collision resistant and therefore suitable for security purposes,
+    /// false if it should only be used for detecting accidental changes
+    pub fn is_cryptographic(&self) -> bool {
+        self.kind().is_cryptographic()
+    }
 
-        let output: Vec<_> = chunks
-            .flat_map(|hash_chunks| {
-                let first = hash_chunks.first()?;
-                let second = hash_chunks.get(1).unwrap_or(first);
-                let hash = self.compute_hash_from_slices(first, second);
-                Some(hash)
-            })
-            .collect();
+    /// Returns the context string of the algorithm, if it has one
+    pub fn context(&self) -> Option<&str> {
+        match self {
+            Algorithm::Blake3DeriveKey(context) => Some(context),
+            Algorithm::Blake3Xof(Blake3Mode::DeriveKey(context), _) => Some(context),
+            _ => None,
+        }
+    }
 
-        let output: Vec<_> = output
-            .iter()
-            .map(|reference| reference.as_slice())
-            .collect();
+    /// Returns the configured output length of the algorithm, if it is variable
+    pub fn output_len(&self) -> Option<OutputLength> {
+        match self {
+            Algorithm::Blake3Xof(_, output_len) => Some(*output_len),
+            _ => None,
+        }
+    }
 
-        self.compute_merkle_hash(&output)
+    /// Returns a public fingerprint of the key of the algorithm, if it has one,
+    /// which allows checking that the same key is used without revealing it
+    pub fn key_fingerprint(&self) -> Option<[u8; 32]> {
+        self.key().map(Blake3Key::fingerprint)
     }
 
-    /// Computes a single hash from 2 slices of bytes
-    pub fn compute_hash_from_slices(&self, first_slice: &[u8], second_slice: &[u8]) -> Vec<u8> {
+    /// Returns the key of the algorithm, if it has one
+    pub(crate) fn key(&self) -> Option<&Blake3Key> {
         match self {
-            Algorithm::Blake3 => {
-                let mut hasher = Hasher::new();
-                hasher.update(first_slice);
-                hasher.update(second_slice);
-                hasher.finalize().as_bytes().to_vec()
+            Algorithm::Blake3Keyed(key) => Some(key),
+            Algorithm::Blake3Xof(Blake3Mode::Keyed(key), _) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Finds the algorithm of the given kind, if all the parameters it needs are provided
+    ///
+    /// Extended output BLAKE3 is keyed if a key is given and derives keys if a context is given.
+    #[cfg(any(feature = "bincode", feature = "signing"))]
+    pub(crate) fn from_parameters(
+        kind: AlgorithmKind,
+        context: Option<String>,
+        output_len: Option<OutputLength>,
+        key: Option<Blake3Key>,
+    ) -> Option<Self> {
+        match kind {
+            AlgorithmKind::Blake3 => Some(Algorithm::Blake3),
+            AlgorithmKind::Blake3Keyed => key.map(Algorithm::Blake3Keyed),
+            AlgorithmKind::Blake3DeriveKey => context.map(Algorithm::Blake3DeriveKey),
+            AlgorithmKind::Blake3Xof => {
+                let mode = match (key, context) {
+                    (Some(key), _) => Blake3Mode::Keyed(key),
+                    (None, Some(context)) => Blake3Mode::DeriveKey(context),
+                    (None, None) => Blake3Mode::Plain,
+                };
+                output_len.map(|output_len| Algorithm::Blake3Xof(mode, output_len))
             }
             #[cfg(feature = "sha")]
-            Algorithm::Sha256 => {
-                let mut hasher = Sha256::new();
-                hasher.update(first_slice);
-                hasher.update(second_slice);
-                hasher.finalize().to_vec()
-            }
+            AlgorithmKind::Sha256 => Some(Algorithm::Sha256),
             #[cfg(feature = "sha")]
-            Algorithm::Sha512 => {
-                let mut hasher = Sha512::new();
-                hasher.update(first_slice);
-                hasher.update(second_slice);
-                hasher.finalize().to_vec()
-            }
+            AlgorithmKind::Sha512 => Some(Algorithm::Sha512),
+            #[cfg(feature = "sha")]
+            AlgorithmKind::Sha384 => Some(Algorithm::Sha384),
+            #[cfg(feature = "sha3")]
+            AlgorithmKind::Sha3_256 => Some(Algorithm::Sha3_256),
+            #[cfg(feature = "sha1")]
+            AlgorithmKind::Sha1 => Some(Algorithm::Sha1),
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2b => Some(Algorithm::Blake2b),
+            #[cfg(feature = "blake2")]
+            AlgorithmKind::Blake2s => Some(Algorithm::Blake2s),
+            #[cfg(feature = "xxhash")]
+            AlgorithmKind::Xxh3_128 => Some(Algorithm::Xxh3_128),
+            #[cfg(feature = "crc32c")]
+            AlgorithmKind::Crc32c => Some(Algorithm::Crc32c),
+        }
+    }
+
+    /// Computes a merkle hash from a slice of digests
+    pub fn compute_merkle_hash(&self, hashes: &[Digest]) -> Option<Digest> {
+        self.compute_merkle_hash_with(hashes, true)
+    }
+
+    /// Computes a merkle hash from a slice of digests, only using multiple threads if allowed to
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    pub(crate) fn compute_merkle_hash_with(&self, hashes: &[Digest], parallel: bool) -> Option<Digest> {
+        if hashes.len() <= 1 {
+            return hashes.first().copied();
+        }
+
+        let hash_pair = |hash_chunks: &[Digest]| {
+            let first = hash_chunks.first()?;
+            let second = hash_chunks.get(1).unwrap_or(first);
+            let hash = self.compute_hash_from_slices(first.as_ref(), second.as_ref());
+            Some(hash)
+        };
+
+        // Hashes pairs of digests level by level, until only the root is left
+        let mut level = hashes.to_vec();
+        while level.len() > 1 {
+            #[cfg(feature = "parallel")]
+            let output: Vec<_> = if parallel {
+                level.par_chunks(2).flat_map(hash_pair).collect()
+            } else {
+                level.chunks(2).flat_map(hash_pair).collect()
+            };
+
+            #[cfg(not(feature = "parallel"))]
+            let output: Vec<_> = level.chunks(2).flat_map(hash_pair).collect();
+
+            level = output;
         }
+
+        level.first().copied()
+    }
+
+    /// Computes a single hash from 2 slices of bytes
+    pub fn compute_hash_from_slices(&self, first_slice: &[u8], second_slice: &[u8]) -> Digest {
+        self.hash_slices(&[first_slice, second_slice])
     }
 
     /// Computes a hash from a slice of bytes
-    pub fn compute_hash(&self, bytes: &[u8]) -> Vec<u8> {
+    pub fn compute_hash(&self, bytes: &[u8]) -> Digest {
+        self.hash_slices(&[bytes])
+    }
+
+    /// Computes a single hash over the concatenation of the given slices
+    fn hash_slices(&self, slices: &[&[u8]]) -> Digest {
+        let kind = self.kind();
         match self {
-            Algorithm::Blake3 => blake3::hash(bytes).as_bytes().to_vec(),
+            Algorithm::Blake3 => Self::blake3_slices(kind, Hasher::new(), slices),
+            Algorithm::Blake3Keyed(key) => Self::blake3_slices(kind, Hasher::new_keyed(key.as_bytes()), slices),
+            Algorithm::Blake3DeriveKey(context) => Self::blake3_slices(kind, Hasher::new_derive_key(context), slices),
+            Algorithm::Blake3Xof(mode, output_len) => {
+                let mut hasher = mode.hasher();
+                for slice in slices {
+                    hasher.update(slice);
+                }
+                let mut output = [0u8; MAX_DIGEST_LEN];
+                hasher.finalize_xof().fill(&mut output[..output_len.get()]);
+                hasher.zeroize();
+                Digest::from_output(kind, &output[..output_len.get()])
+            }
             #[cfg(feature = "sha")]
-            Algorithm::Sha256 => {
-                let mut hasher = Sha256::new();
+            Algorithm::Sha256 => Self::digest_slices::<sha2::Sha256>(kind, slices),
+            #[cfg(feature = "sha")]
+            Algorithm::Sha512 => Self::digest_slices::<sha2::Sha512>(kind, slices),
+            #[cfg(feature = "sha")]
+            Algorithm::Sha384 => Self::digest_slices::<sha2::Sha384>(kind, slices),
+            #[cfg(feature = "sha3")]
+            Algorithm::Sha3_256 => Self::digest_slices::<sha3::Sha3_256>(kind, slices),
+            #[cfg(feature = "sha1")]
+            Algorithm::Sha1 => Self::digest_slices::<sha1::Sha1>(kind, slices),
+            #[cfg(feature = "blake2")]
+            Algorithm::Blake2b => Self::digest_slices::<blake2::Blake2b512>(kind, slices),
+            #[cfg(feature = "blake2")]
+            Algorithm::Blake2s => Self::digest_slices::<blake2::Blake2s256>(kind, slices),
+            #[cfg(feature = "xxhash")]
+            Algorithm::Xxh3_128 => {
+                let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+                for slice in slices {
+                    hasher.update(slice);
+                }
+                // Canonical big endian representation, as printed by xxhsum
+                Digest::from_output(kind, &hasher.digest128().to_be_bytes())
+            }
+            #[cfg(feature = "crc32c")]
+            Algorithm::Crc32c => {
+                let checksum = slices
+                    .iter()
+                    .fold(0, |checksum, slice| crc32c::crc32c_append(checksum, slice));
+                Digest::from_output(kind, &checksum.to_be_bytes())
+            }
+        }
+    }
+
+    /// Computes a hash over the concatenation of the given slices with a blake3 hasher in any mode
+    fn blake3_slices(kind: AlgorithmKind, mut hasher: Hasher, slices: &[&[u8]]) -> Digest {
+        for slice in slices {
+            hasher.update(slice);
+        }
+        let digest = Digest::from_output(kind, hasher.finalize().as_bytes());
+        // Keyed hashers hold the key in their state
+        hasher.zeroize();
+        digest
+    }
 
-                hasher.update(bytes);
+    /// Computes a hash over the concatenation of the given slices with any RustCrypto hasher
+    #[cfg(feature = "digest")]
+    fn digest_slices<D: digest::Digest>(kind: AlgorithmKind, slices: &[&[u8]]) -> Digest {
+        let mut hasher = D::new();
+        for slice in slices {
+            hasher.update(slice);
+        }
+        Digest::from_output(kind, &hasher.finalize())
+    }
+}
 
-                hasher.finalize().to_vec()
+impl PartialEq for Algorithm {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Algorithm::Blake3Keyed(first), Algorithm::Blake3Keyed(second)) => first == second,
+            (Algorithm::Blake3DeriveKey(first), Algorithm::Blake3DeriveKey(second)) => first == second,
+            (Algorithm::Blake3Xof(first, first_len), Algorithm::Blake3Xof(second, second_len)) => {
+                first == second && first_len == second_len
             }
-            #[cfg(feature = "sha")]
-            Algorithm::Sha512 => {
-                let mut hasher = Sha512::new();
+            _ => self.kind() == other.kind(),
+        }
+    }
+}
 
-                hasher.update(bytes);
+impl Eq for Algorithm {}
 
-                hasher.finalize().to_vec()
+impl Debug for Algorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // Never prints the key
+            Algorithm::Blake3Keyed(_) => f.write_str("Blake3Keyed(..)"),
+            Algorithm::Blake3DeriveKey(context) => f.debug_tuple("Blake3DeriveKey").field(context).finish(),
+            Algorithm::Blake3Xof(mode, output_len) => {
+                f.debug_tuple("Blake3Xof").field(mode).field(&output_len.get()).finish()
             }
+            _ => Debug::fmt(&self.kind(), f),
+        }
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::fmt::Display for AlgorithmKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for Algorithm {
+    fn encode<E: bincode::enc::Encoder>(&self, encoder: &mut E) -> Result<(), bincode::error::EncodeError> {
+        // Keys are never persisted, algorithms that need one cannot be decoded
+        bincode::Encode::encode(&self.kind(), encoder)?;
+        bincode::Encode::encode(&self.context(), encoder)?;
+        bincode::Encode::encode(&self.output_len().map(|output_len| output_len.get() as u8), encoder)?;
+        bincode::Encode::encode(&self.key_fingerprint().is_some(), encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for Algorithm {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        let kind: AlgorithmKind = bincode::Decode::decode(decoder)?;
+        let context: Option<String> = bincode::Decode::decode(decoder)?;
+        let output_len: Option<u8> = bincode::Decode::decode(decoder)?;
+        let keyed: bool = bincode::Decode::decode(decoder)?;
+        let output_len = output_len
+            .map(|output_len| OutputLength::new(output_len as usize))
+            .transpose()
+            .map_err(|error| bincode::error::DecodeError::OtherString(error.to_string()))?;
+        let algorithm = match keyed {
+            true => None,
+            false => Algorithm::from_parameters(kind, context, output_len, None),
+        };
+        algorithm.ok_or(bincode::error::DecodeError::OtherString(format!(
+            "Hashing algorithm cannot be restored without its parameters: {}",
+            kind
+        )))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(Algorithm);
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for AlgorithmKind {
+    fn encode<E: bincode::enc::Encoder>(&self, encoder: &mut E) -> Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.id(), encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for AlgorithmKind {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        let id: u8 = bincode::Decode::decode(decoder)?;
+        AlgorithmKind::from_id(id).ok_or(bincode::error::DecodeError::OtherString(format!(
+            "Unknown or disabled hashing algorithm id: {}",
+            id
+        )))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(AlgorithmKind);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Key and context of the official BLAKE3 test vectors, whose inputs are `i % 251` for every byte index `i`
+    const BLAKE3_TEST_KEY: [u8; 32] = *b"whats the Elvish word for friend";
+    const BLAKE3_TEST_CONTEXT: &str = "BLAKE3 2019-12-27 16:29:52 test vectors context";
+
+    /// The first 64 bytes of the extended outputs of the official BLAKE3 test vectors for an empty input
+    const BLAKE3_EMPTY_HASH: &str = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262e00f03e7b69af26b7faaf09fcd333050338ddfe085b8cc869ca98b206c08243a";
+    const BLAKE3_EMPTY_KEYED_HASH: &str = "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26b18171a2f22a4b94822c701f107153dba24918c4bae4d2945c20ece13387627d";
+    const BLAKE3_EMPTY_DERIVE_KEY: &str = "2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d905630c8be290dfcf3e6842f13bddd573c098c3f17361f1f206b8cad9d088aa4";
+
+    fn hex(algorithm: &Algorithm, input: &[u8]) -> String {
+        algorithm.compute_hash(input).to_hex()
+    }
+
+    #[test]
+    fn blake3_matches_reference_vectors() {
+        assert_eq!(hex(&Algorithm::Blake3, b""), BLAKE3_EMPTY_HASH[..64]);
+        assert_eq!(
+            hex(&Algorithm::Blake3, b"abc"),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert_eq!(
+            hex(&Algorithm::Blake3Keyed(Blake3Key::new(BLAKE3_TEST_KEY)), b""),
+            BLAKE3_EMPTY_KEYED_HASH[..64]
+        );
+        assert_eq!(
+            hex(&Algorithm::Blake3DeriveKey(BLAKE3_TEST_CONTEXT.to_string()), b""),
+            BLAKE3_EMPTY_DERIVE_KEY[..64]
+        );
+    }
+
+    #[test]
+    fn blake3_xof_matches_reference_vectors() {
+        let xof = |mode: &Blake3Mode, len| Algorithm::Blake3Xof(mode.clone(), OutputLength::new(len).unwrap());
+        let modes = [
+            (Blake3Mode::Plain, BLAKE3_EMPTY_HASH),
+            (Blake3Mode::Keyed(Blake3Key::new(BLAKE3_TEST_KEY)), BLAKE3_EMPTY_KEYED_HASH),
+            (Blake3Mode::DeriveKey(BLAKE3_TEST_CONTEXT.to_string()), BLAKE3_EMPTY_DERIVE_KEY),
+        ];
+        for (mode, expected) in &modes {
+            assert_eq!(hex(&xof(mode, 64), b""), *expected);
+            assert_eq!(hex(&xof(mode, 48), b""), expected[..96]);
+            assert_eq!(hex(&xof(mode, 16), b""), expected[..32]);
+        }
+    }
+
+    #[test]
+    fn xof_parameters_follow_the_mode() {
+        let output_len = OutputLength::new(48).unwrap();
+        let keyed = Algorithm::Blake3Xof(Blake3Mode::Keyed(Blake3Key::new([7; 32])), output_len);
+        assert_eq!(keyed.kind(), AlgorithmKind::Blake3Xof);
+        assert_eq!(keyed.key_fingerprint(), Some(Blake3Key::new([7; 32]).fingerprint()));
+        assert_eq!(keyed.context(), None);
+        assert_eq!(keyed.output_len(), Some(output_len));
+        assert_eq!(format!("{:?}", keyed), "Blake3Xof(Keyed(Blake3Key(..)), 48)");
+
+        let derive_key = Algorithm::Blake3Xof(Blake3Mode::DeriveKey("context".to_string()), output_len);
+        assert_eq!(derive_key.context(), Some("context"));
+        assert_eq!(derive_key.key_fingerprint(), None);
+        assert_ne!(derive_key, Algorithm::Blake3Xof(Blake3Mode::Plain, output_len));
+    }
+
+    #[cfg(feature = "sha")]
+    #[test]
+    fn sha2_matches_fips_180_vectors() {
+        assert_eq!(
+            hex(&Algorithm::Sha256, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(&Algorithm::Sha256, b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            hex(&Algorithm::Sha384, b"abc"),
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"
+        );
+        assert_eq!(
+            hex(&Algorithm::Sha512, b"abc"),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+    }
+
+    #[cfg(feature = "sha3")]
+    #[test]
+    fn sha3_matches_fips_202_vectors() {
+        assert_eq!(
+            hex(&Algorithm::Sha3_256, b"abc"),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+        assert_eq!(
+            hex(&Algorithm::Sha3_256, b""),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+    }
+
+    #[cfg(feature = "sha1")]
+    #[test]
+    fn sha1_matches_fips_180_vectors() {
+        assert_eq!(hex(&Algorithm::Sha1, b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[cfg(feature = "blake2")]
+    #[test]
+    fn blake2_matches_rfc_7693_vectors() {
+        assert_eq!(
+            hex(&Algorithm::Blake2b, b"abc"),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            hex(&Algorithm::Blake2s, b"abc"),
+            "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"
+        );
+    }
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn xxh3_matches_xxhsum() {
+        assert_eq!(hex(&Algorithm::Xxh3_128, b""), "99aa06d3014798d86001c324468d497f");
+    }
+
+    #[cfg(feature = "crc32c")]
+    #[test]
+    fn crc32c_matches_the_check_value() {
+        assert_eq!(hex(&Algorithm::Crc32c, b"123456789"), "e3069283");
+    }
+
+    #[test]
+    fn keys_are_shared_compared_and_never_printed() {
+        let key = Blake3Key::new([7; 32]);
+        let algorithm = Algorithm::Blake3Keyed(key.clone());
+        let clone = algorithm.clone();
+        assert!(matches!(&clone, Algorithm::Blake3Keyed(cloned) if Arc::ptr_eq(&cloned.0, &key.0)));
+
+        assert_eq!(algorithm, Algorithm::Blake3Keyed(Blake3Key::new([7; 32])));
+        assert_ne!(algorithm, Algorithm::Blake3Keyed(Blake3Key::new([8; 32])));
+        assert_eq!(algorithm.key_fingerprint(), Some(key.fingerprint()));
+        assert_ne!(key.fingerprint(), [7; 32]);
+
+        assert_eq!(format!("{:?}", algorithm), "Blake3Keyed(..)");
+        assert_eq!(format!("{:?}", key), "Blake3Key(..)");
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn encoding_never_restores_keyed_algorithms() {
+        let config = bincode::config::standard();
+        let output_len = OutputLength::new(48).unwrap();
+        for algorithm in [
+            Algorithm::Blake3,
+            Algorithm::Blake3DeriveKey("context".to_string()),
+            Algorithm::Blake3Xof(Blake3Mode::Plain, output_len),
+            Algorithm::Blake3Xof(Blake3Mode::DeriveKey("context".to_string()), output_len),
+        ] {
+            let bytes = bincode::encode_to_vec(&algorithm, config).unwrap();
+            let (decoded, _): (Algorithm, _) = bincode::decode_from_slice(&bytes, config).unwrap();
+            assert_eq!(decoded, algorithm);
+        }
+
+        for algorithm in [
+            Algorithm::Blake3Keyed(Blake3Key::new([7; 32])),
+            Algorithm::Blake3Xof(Blake3Mode::Keyed(Blake3Key::new([7; 32])), output_len),
+        ] {
+            let bytes = bincode::encode_to_vec(&algorithm, config).unwrap();
+            assert!(bincode::decode_from_slice::<Algorithm, _>(&bytes, config).is_err());
         }
     }
+
+    #[test]
+    fn hashes_slices_as_their_concatenation() {
+        let algorithm = Algorithm::Blake3;
+        assert_eq!(algorithm.compute_hash_from_slices(b"ab", b"c"), algorithm.compute_hash(b"abc"));
+    }
 }
@@ -0,0 +1,71 @@
+use blake3::hazmat::{left_subtree_len, merge_subtrees_non_root, merge_subtrees_root, ChainingValue, HasherExt, Mode};
+use blake3::CHUNK_LEN;
+
+/// Length of the little endian content length every encoding starts with
+pub(crate) const HEADER_LEN: usize = 8;
+
+/// Length of a parent node, the chaining values of its left and right subtrees
+pub(crate) const PARENT_LEN: usize = 64;
+
+/// Returns true if a subtree of the given length is a single chunk
+pub(crate) fn is_chunk(len: u64) -> bool {
+    len <= CHUNK_LEN as u64
+}
+
+/// Returns the length of the left subtree of a parent node
+pub(crate) fn left_len(len: u64) -> u64 {
+    left_subtree_len(len)
+}
+
+/// Returns the number of parent nodes in a subtree of the given length
+pub(crate) fn parent_count(len: u64) -> u64 {
+    len.div_ceil(CHUNK_LEN as u64).max(1) - 1
+}
+
+/// Finds the chaining value of a chunk, or the root hash if the chunk is the whole content
+pub(crate) fn chunk_cv(chunk: &[u8], offset: u64, is_root: bool) -> ChainingValue {
+    if is_root {
+        return *blake3::hash(chunk).as_bytes();
+    }
+    blake3::Hasher::new()
+        .set_input_offset(offset)
+        .update(chunk)
+        .finalize_non_root()
+}
+
+/// Finds the chaining value of a parent node, or the root hash if the parent is the root
+pub(crate) fn parent_cv(left: &ChainingValue, right: &ChainingValue, is_root: bool) -> ChainingValue {
+    if is_root {
+        return *merge_subtrees_root(left, right, Mode::Hash).as_bytes();
+    }
+    merge_subtrees_non_root(left, right, Mode::Hash)
+}
+
+/// Compares two chaining values in constant time
+pub(crate) fn cv_eq(a: &ChainingValue, b: &ChainingValue) -> bool {
+    blake3::Hash::from_bytes(*a) == blake3::Hash::from_bytes(*b)
+}
+
+/// The range of content the nodes of a slice are chosen by
+///
+/// Empty ranges are widened to a single byte and ranges past the end are moved onto the final chunk,
+/// so a slice always carries at least one chunk that authenticates the content length.
+pub(crate) struct SliceRange {
+    start: u64,
+    end: u64,
+}
+
+impl SliceRange {
+    pub(crate) fn new(start: u64, len: u64, content_len: u64) -> Self {
+        let start = if start >= content_len { content_len.saturating_sub(1) } else { start };
+        Self {
+            start,
+            end: start.saturating_add(len.max(1)),
+        }
+    }
+
+    /// Returns true if a subtree covering the given content is part of the slice
+    pub(crate) fn overlaps(&self, node_start: u64, node_len: u64) -> bool {
+        node_start < self.end && self.start < node_start + node_len
+    }
+}
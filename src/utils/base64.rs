@@ -0,0 +1,17 @@
+/// Standard RFC 4648 base64 alphabet
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as padded RFC 4648 base64
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let buffer = group.iter().enumerate().fold(0u32, |buffer, (index, &byte)| buffer | (byte as u32) << (16 - index * 8));
+        for index in 0..4 {
+            match index <= group.len() {
+                true => encoded.push(ALPHABET[(buffer >> (18 - index * 6) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
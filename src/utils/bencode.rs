@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+
+/// A bencoded value, dictionaries keep their keys sorted by their raw bytes
+pub(crate) enum Bencode {
+    Integer(i64),
+    Bytes(Vec<u8>),
+    Dictionary(BTreeMap<Vec<u8>, Bencode>),
+}
+
+impl Bencode {
+    /// Creates a dictionary from keys and values
+    pub(crate) fn dictionary<'a>(entries: impl IntoIterator<Item = (&'a [u8], Bencode)>) -> Self {
+        Bencode::Dictionary(entries.into_iter().map(|(key, value)| (key.to_vec(), value)).collect())
+    }
+
+    /// Appends the encoded value
+    pub(crate) fn encode(&self, output: &mut Vec<u8>) {
+        match self {
+            Bencode::Integer(integer) => {
+                output.extend_from_slice(format!("i{}e", integer).as_bytes());
+            }
+            Bencode::Bytes(bytes) => {
+                output.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+                output.extend_from_slice(bytes);
+            }
+            Bencode::Dictionary(entries) => {
+                output.push(b'd');
+                for (key, value) in entries {
+                    Bencode::Bytes(key.clone()).encode(output);
+                    value.encode(output);
+                }
+                output.push(b'e');
+            }
+        }
+    }
+}
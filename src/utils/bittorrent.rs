@@ -0,0 +1,192 @@
+use crate::components::digest::Digest;
+use crate::components::torrent_file::TorrentFile;
+use crate::error::TorrentError;
+use crate::utils::algorithm::{Algorithm, AlgorithmKind};
+
+/// Size of the blocks the leaves of a BitTorrent v2 file tree are hashed from
+pub const TORRENT_BLOCK_SIZE: u64 = 16_384;
+
+/// Number of bytes covered by each piece of a BitTorrent v2 torrent, a power of two of at least 16 KiB
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "bincode", derive(bincode::Encode))]
+pub struct PieceLength(u64);
+
+impl PieceLength {
+    /// Creates a piece length, checking that it is a power of two of at least 16 KiB
+    pub fn new(len: u64) -> Result<Self, TorrentError> {
+        if len < TORRENT_BLOCK_SIZE || !len.is_power_of_two() {
+            return Err(TorrentError::InvalidPieceLength(len));
+        }
+        Ok(Self(len))
+    }
+
+    /// Returns the piece length in bytes
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for PieceLength {
+    /// 256 KiB pieces
+    fn default() -> Self {
+        Self(262_144)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for PieceLength {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        let len: u64 = bincode::Decode::decode(decoder)?;
+        PieceLength::new(len).map_err(|error| bincode::error::DecodeError::OtherString(error.to_string()))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(PieceLength);
+
+/// Hashes a file into its BitTorrent v2 merkle tree of SHA-256 hashed 16 KiB blocks
+///
+/// Leaves are padded with zero hashes to a power of two,
+/// the piece layer only holds the hashes of pieces that contain file data.
+pub(crate) fn file_pieces(bytes: &[u8], piece_length: PieceLength) -> TorrentFile {
+    let length = bytes.len() as u64;
+    let algorithm = Algorithm::Sha256;
+
+    let mut layer: Vec<Digest> = bytes
+        .chunks(TORRENT_BLOCK_SIZE as usize)
+        .map(|block| algorithm.compute_hash(block))
+        .collect();
+
+    // Empty files have no pieces root
+    if layer.is_empty() {
+        return TorrentFile {
+            length,
+            piece_length,
+            pieces_root: None,
+            piece_layer: Vec::new(),
+        };
+    }
+
+    layer.resize(layer.len().next_power_of_two(), Digest::from_output(AlgorithmKind::Sha256, &[0; 32]));
+
+    let blocks_per_piece = piece_length.get() / TORRENT_BLOCK_SIZE;
+    let mut blocks_per_node = 1;
+    let mut piece_layer = Vec::new();
+
+    loop {
+        if blocks_per_node == blocks_per_piece && length > piece_length.get() {
+            piece_layer = layer[..length.div_ceil(piece_length.get()) as usize].to_vec();
+        }
+        if layer.len() == 1 {
+            break;
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| algorithm.compute_hash_from_slices(pair[0].as_ref(), pair[1].as_ref()))
+            .collect();
+        blocks_per_node *= 2;
+    }
+
+    TorrentFile {
+        length,
+        piece_length,
+        pieces_root: Some(layer[0]),
+        piece_layer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::torrent::FileTreeNode;
+    use crate::utils::fixture::{fixture, path};
+    use crate::MerkleTree;
+
+    const BLOCK: usize = TORRENT_BLOCK_SIZE as usize;
+
+    fn sha256(bytes: &[u8]) -> Digest {
+        Algorithm::Sha256.compute_hash(bytes)
+    }
+
+    fn pair(left: &Digest, right: &Digest) -> Digest {
+        Algorithm::Sha256.compute_hash_from_slices(left.as_ref(), right.as_ref())
+    }
+
+    fn contents(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn rejects_invalid_piece_lengths() {
+        assert!(PieceLength::new(16_384).is_ok());
+        assert!(PieceLength::new(4_194_304).is_ok());
+        assert!(matches!(PieceLength::new(8_192), Err(TorrentError::InvalidPieceLength(8_192))));
+        assert!(matches!(PieceLength::new(20_000), Err(TorrentError::InvalidPieceLength(20_000))));
+    }
+
+    #[test]
+    fn small_files_are_rooted_at_their_block_hash() {
+        let bytes = contents(1000);
+        let file = file_pieces(&bytes, PieceLength::default());
+        assert_eq!(file.pieces_root, Some(sha256(&bytes)));
+        assert!(file.piece_layer.is_empty());
+
+        let empty = file_pieces(b"", PieceLength::default());
+        assert_eq!(empty.pieces_root, None);
+        assert_eq!(empty.length, 0);
+    }
+
+    #[test]
+    fn pads_leaves_with_zero_hashes() {
+        let bytes = contents(2 * BLOCK + 100);
+        let blocks: Vec<_> = bytes.chunks(BLOCK).map(sha256).collect();
+        let zero = Digest::from_output(AlgorithmKind::Sha256, &[0; 32]);
+
+        let file = file_pieces(&bytes, PieceLength::default());
+        let root = pair(&pair(&blocks[0], &blocks[1]), &pair(&blocks[2], &zero));
+        assert_eq!(file.pieces_root, Some(root));
+        assert!(file.piece_layer.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_piece_layer_of_files_over_a_piece() {
+        let bytes = contents(4 * BLOCK + 1);
+        let blocks: Vec<_> = bytes.chunks(BLOCK).map(sha256).collect();
+        let zero = Digest::from_output(AlgorithmKind::Sha256, &[0; 32]);
+
+        let file = file_pieces(&bytes, PieceLength::new(2 * TORRENT_BLOCK_SIZE).unwrap());
+        let pieces = vec![
+            pair(&blocks[0], &blocks[1]),
+            pair(&blocks[2], &blocks[3]),
+            pair(&blocks[4], &zero),
+        ];
+        assert_eq!(file.piece_layer, pieces);
+
+        // Padding above the piece layer is hashed from the zero leaves as well
+        let padding = pair(&zero, &zero);
+        let root = pair(&pair(&pieces[0], &pieces[1]), &pair(&pieces[2], &padding));
+        assert_eq!(file.pieces_root, Some(root));
+    }
+
+    #[test]
+    fn torrents_list_files_and_their_piece_layers() {
+        let large = contents(3 * BLOCK);
+        let dir = fixture(&[("a", b"one"), ("b/c", &large), ("b/empty/", b""), ("d", b"")]);
+        let tree = MerkleTree::builder(path(&dir))
+            .bittorrent(PieceLength::new(TORRENT_BLOCK_SIZE).unwrap())
+            .build()
+            .unwrap();
+        let torrent = tree.to_torrent().unwrap();
+
+        let large_root = file_pieces(&large, PieceLength::new(TORRENT_BLOCK_SIZE).unwrap()).pieces_root.unwrap();
+        assert_eq!(torrent.piece_layers.keys().collect::<Vec<_>>(), [&large_root]);
+        assert_eq!(torrent.piece_layers[&large_root].len(), 3);
+        assert_eq!(
+            torrent.file_tree.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["a", "b", "d"]
+        );
+        assert!(matches!(&torrent.file_tree["b"], FileTreeNode::Directory(entries) if entries.len() == 1));
+        assert_eq!(torrent.info_hash(), sha256(&torrent.info_bytes()));
+        assert!(torrent.info_bytes().windows(16).any(|window| window == b"12:meta versioni"));
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "fastcdc")]
+use std::ops::Range;
+use std::ops::RangeInclusive;
+
+use crate::error::ChunkParamsError;
+
+/// Allowed range of the minimum chunk size in bytes
+pub const MIN_CHUNK_SIZE_RANGE: RangeInclusive<u32> = 64..=1_048_576;
+
+/// Allowed range of the average chunk size in bytes
+pub const AVG_CHUNK_SIZE_RANGE: RangeInclusive<u32> = 256..=4_194_304;
+
+/// Allowed range of the maximum chunk size in bytes
+pub const MAX_CHUNK_SIZE_RANGE: RangeInclusive<u32> = 1024..=16_777_216;
+
+/// Minimum, average and maximum chunk sizes of FastCDC content-defined chunking
+///
+/// Files split into chunks are hashed as the merkle hash of their chunk hashes,
+/// so a file that fits in a single chunk keeps its plain contents hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkParams {
+    min: u32,
+    avg: u32,
+    max: u32,
+}
+
+impl ChunkParams {
+    /// Creates chunk parameters, checking that the sizes are ordered and within the supported ranges
+    pub fn new(min: u32, avg: u32, max: u32) -> Result<Self, ChunkParamsError> {
+        if !MIN_CHUNK_SIZE_RANGE.contains(&min) {
+            return Err(ChunkParamsError::MinSizeOutOfRange(min, MIN_CHUNK_SIZE_RANGE));
+        }
+        if !AVG_CHUNK_SIZE_RANGE.contains(&avg) {
+            return Err(ChunkParamsError::AvgSizeOutOfRange(avg, AVG_CHUNK_SIZE_RANGE));
+        }
+        if !MAX_CHUNK_SIZE_RANGE.contains(&max) {
+            return Err(ChunkParamsError::MaxSizeOutOfRange(max, MAX_CHUNK_SIZE_RANGE));
+        }
+        if min > avg || avg > max {
+            return Err(ChunkParamsError::Unordered(min, avg, max));
+        }
+        Ok(Self { min, avg, max })
+    }
+
+    /// Returns the minimum chunk size in bytes
+    pub fn min(&self) -> u32 {
+        self.min
+    }
+
+    /// Returns the average chunk size in bytes
+    pub fn avg(&self) -> u32 {
+        self.avg
+    }
+
+    /// Returns the maximum chunk size in bytes
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Finds the byte ranges of the content-defined chunks of the given bytes
+    #[cfg(feature = "fastcdc")]
+    pub(crate) fn split(&self, bytes: &[u8]) -> Vec<Range<usize>> {
+        fastcdc::v2020::FastCDC::new(bytes, self.min, self.avg, self.max)
+            .map(|chunk| chunk.offset..chunk.offset + chunk.length)
+            .collect()
+    }
+}
+
+impl Default for ChunkParams {
+    /// 16 KiB minimum, 64 KiB average and 256 KiB maximum chunk sizes
+    fn default() -> Self {
+        Self {
+            min: 16_384,
+            avg: 65_536,
+            max: 262_144,
+        }
+    }
+}
+
+impl Display for ChunkParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.min, self.avg, self.max)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl bincode::Encode for ChunkParams {
+    fn encode<E: bincode::enc::Encoder>(&self, encoder: &mut E) -> Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&[self.min, self.avg, self.max], encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<Context> bincode::Decode<Context> for ChunkParams {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError> {
+        let [min, avg, max]: [u32; 3] = bincode::Decode::decode(decoder)?;
+        ChunkParams::new(min, avg, max).map_err(|error| bincode::error::DecodeError::OtherString(error.to_string()))
+    }
+}
+
+#[cfg(feature = "bincode")]
+bincode::impl_borrow_decode!(ChunkParams);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unordered_and_out_of_range_sizes() {
+        assert!(ChunkParams::new(64, 256, 1024).is_ok());
+        assert!(matches!(ChunkParams::new(32, 256, 1024), Err(ChunkParamsError::MinSizeOutOfRange(32, _))));
+        assert!(matches!(ChunkParams::new(64, 128, 1024), Err(ChunkParamsError::AvgSizeOutOfRange(128, _))));
+        assert!(matches!(ChunkParams::new(64, 256, 512), Err(ChunkParamsError::MaxSizeOutOfRange(512, _))));
+        assert!(matches!(ChunkParams::new(2048, 1024, 4096), Err(ChunkParamsError::Unordered(2048, 1024, 4096))));
+    }
+
+    #[cfg(feature = "fastcdc")]
+    mod chunked_trees {
+        use super::*;
+        use crate::utils::fixture::{fixture, path};
+        use crate::{Algorithm, MerkleTree};
+
+        /// Deterministic bytes without repetitions, so chunk boundaries depend on the contents
+        fn noise(len: usize, seed: u64) -> Vec<u8> {
+            let mut state = seed;
+            (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect()
+        }
+
+        fn params() -> ChunkParams {
+            ChunkParams::new(1024, 4096, 16384).unwrap()
+        }
+
+        #[test]
+        fn chunks_cover_files_and_make_up_their_hash() {
+            let contents = noise(200_000, 1);
+            let dir = fixture(&[("a", &contents), ("small", b"small")]);
+            let tree = MerkleTree::builder(path(&dir)).chunking(params()).build().unwrap();
+
+            let file = tree.iter().find(|item| item.path.relative.ends_with("a")).unwrap();
+            assert!(file.chunks.len() > 1);
+            let mut offset = 0;
+            for chunk in &file.chunks {
+                assert_eq!(chunk.offset, offset);
+                let range = chunk.offset as usize..(chunk.offset + chunk.len) as usize;
+                assert_eq!(chunk.hash, Algorithm::Blake3.compute_hash(&contents[range]));
+                offset += chunk.len;
+            }
+            assert_eq!(offset, contents.len() as u64);
+
+            let chunk_hashes: Vec<_> = file.chunks.iter().map(|chunk| chunk.hash).collect();
+            assert_eq!(Some(file.hash), Algorithm::Blake3.compute_merkle_hash(&chunk_hashes));
+
+            let small = tree.iter().find(|item| item.path.relative.ends_with("small")).unwrap();
+            assert_eq!(small.hash, Algorithm::Blake3.compute_hash(b"small"));
+        }
+
+        #[test]
+        fn insertions_only_change_nearby_chunks() {
+            let contents = noise(200_000, 2);
+            let mut edited = noise(100, 3);
+            edited.extend_from_slice(&contents);
+
+            let dir = fixture(&[("a", &contents)]);
+            let before = MerkleTree::builder(path(&dir)).chunking(params()).build().unwrap();
+            crate::utils::fixture::write(dir.path(), "a", &edited);
+            let after = MerkleTree::builder(path(&dir)).chunking(params()).build().unwrap();
+
+            let hashes = |tree: &MerkleTree| -> Vec<_> {
+                let file = tree.iter().find(|item| item.path.relative.ends_with("a")).unwrap();
+                file.chunks.iter().map(|chunk| chunk.hash).collect()
+            };
+            let (before, after) = (hashes(&before), hashes(&after));
+            assert!(before.len() > 10);
+            let kept = after.iter().filter(|hash| before.contains(hash)).count();
+            assert!(kept + 2 >= before.len(), "kept {} of {} chunks", kept, before.len());
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::TempDir;
+
+/// Creates a temporary directory tree for tests
+///
+/// Paths ending with a slash become directories, every other path becomes a file with the given contents.
+pub(crate) fn fixture(entries: &[(&str, &[u8])]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in entries {
+        write(dir.path(), path, contents);
+    }
+    dir
+}
+
+/// Creates or replaces an entry of a fixture
+pub(crate) fn write(root: &Path, path: &str, contents: &[u8]) {
+    let full = root.join(path.trim_end_matches('/'));
+    if path.ends_with('/') {
+        fs::create_dir_all(full).unwrap();
+        return;
+    }
+    if let Some(parent) = full.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(full, contents).unwrap();
+}
+
+/// Returns the path of a fixture as a string
+pub(crate) fn path(dir: &TempDir) -> &str {
+    dir.path().to_str().unwrap()
+}
@@ -28,4 +28,7 @@ impl Encodable for &[u8] {
     fn to_hex_string(&self) -> String { bytes_to_hex(self) }
 }
 
-
+#[cfg(feature = "encode")]
+impl Encodable for crate::components::digest::Digest {
+    fn to_hex_string(&self) -> String { bytes_to_hex(self) }
+}
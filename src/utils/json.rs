@@ -0,0 +1,36 @@
+use std::fmt::Write as _;
+
+/// Encodes a string as a quoted JSON string
+pub(crate) fn string(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len() + 2);
+    encoded.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            control if control < ' ' => {
+                let _ = write!(encoded, "\\u{:04x}", control as u32);
+            }
+            other => encoded.push(other),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+/// Encodes keys and already encoded values as a JSON object, in the given order
+pub(crate) fn object<'a>(entries: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    let entries: Vec<_> = entries
+        .into_iter()
+        .map(|(key, value)| format!("{}:{}", string(key), value))
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+/// Encodes already encoded values as a JSON array
+pub(crate) fn array(values: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", values.into_iter().collect::<Vec<_>>().join(","))
+}
@@ -1,2 +1,28 @@
 pub mod algorithm;
+#[cfg(feature = "unixfs")]
+pub mod base32;
+#[cfg(feature = "bao")]
+pub mod bao;
+#[cfg(any(feature = "nar", feature = "dirhash", all(feature = "attestation", feature = "signing")))]
+pub mod base64;
+#[cfg(feature = "bittorrent")]
+pub mod bencode;
+#[cfg(feature = "bittorrent")]
+pub mod bittorrent;
+pub mod chunking;
+#[cfg(test)]
+pub(crate) mod fixture;
 pub mod hex_encoding;
+#[cfg(feature = "attestation")]
+pub mod json;
+#[cfg(feature = "nar")]
+pub mod nar;
+#[cfg(feature = "unixfs")]
+pub mod protobuf;
+pub mod read_limiter;
+#[cfg(feature = "sync")]
+pub mod sync_wire;
+#[cfg(feature = "oci")]
+pub mod tar;
+#[cfg(feature = "unixfs")]
+pub mod unixfs;
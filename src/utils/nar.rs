@@ -0,0 +1,149 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use sha2::Digest as _;
+
+use crate::error::NarError;
+
+/// Magic string every Nix archive starts with
+pub const NAR_MAGIC: &str = "nix-archive-1";
+
+/// Alphabet of Nix's own base32 encoding, which omits `e`, `o`, `u` and `t`
+const NIX32_ALPHABET: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+/// The kind of a filesystem object as it is stored in a Nix archive
+pub(crate) enum NarNode {
+    Regular { executable: bool, size: u64 },
+    Symlink(Vec<u8>),
+    Directory,
+}
+
+impl NarNode {
+    /// Reads the kind of the object at a path, without following symbolic links
+    pub(crate) fn of(path: &Path) -> Result<Self, NarError> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) => return Err(NarError::UnableToReadMetadata(path.to_path_buf(), err)),
+        };
+
+        if metadata.file_type().is_symlink() {
+            let target = match fs::read_link(path) {
+                Ok(target) => target,
+                Err(err) => return Err(NarError::UnableToReadLink(path.to_path_buf(), err)),
+            };
+            #[cfg(unix)]
+            let target = std::os::unix::ffi::OsStringExt::into_vec(target.into_os_string());
+            #[cfg(not(unix))]
+            let target = target.to_string_lossy().replace('\\', "/").into_bytes();
+            return Ok(NarNode::Symlink(target));
+        }
+
+        if metadata.is_dir() {
+            return Ok(NarNode::Directory);
+        }
+
+        #[cfg(unix)]
+        let executable = std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o100 != 0;
+        #[cfg(not(unix))]
+        let executable = false;
+
+        Ok(NarNode::Regular {
+            executable,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// Writes the tokens of a Nix archive, every string length prefixed and padded to eight bytes
+pub(crate) struct NarWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> NarWriter<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes several strings
+    pub(crate) fn strings(&mut self, strings: &[&[u8]]) -> Result<(), NarError> {
+        for string in strings {
+            self.string(string)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a single length prefixed and padded string
+    pub(crate) fn string(&mut self, string: &[u8]) -> Result<(), NarError> {
+        self.write(&(string.len() as u64).to_le_bytes())?;
+        self.write(string)?;
+        self.padding(string.len() as u64)
+    }
+
+    /// Writes the contents of a regular file as a single string, streaming it from disk
+    pub(crate) fn contents(&mut self, path: &Path, size: u64) -> Result<(), NarError> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) => return Err(NarError::UnableToReadFile(path.to_path_buf(), err)),
+        };
+
+        self.write(&size.to_le_bytes())?;
+        let copied = match io::copy(&mut file.take(size), &mut self.writer) {
+            Ok(copied) => copied,
+            Err(err) => return Err(NarError::UnableToReadFile(path.to_path_buf(), err)),
+        };
+        if copied != size {
+            return Err(NarError::FileChangedWhileReading(path.to_path_buf()));
+        }
+        self.padding(size)
+    }
+
+    pub(crate) fn flush(&mut self) -> Result<(), NarError> {
+        self.writer.flush().map_err(NarError::UnableToWrite)
+    }
+
+    fn padding(&mut self, len: u64) -> Result<(), NarError> {
+        let padding = (8 - len % 8) % 8;
+        self.write(&[0; 8][..padding as usize])
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), NarError> {
+        self.writer.write_all(bytes).map_err(NarError::UnableToWrite)
+    }
+}
+
+/// Hashes everything written to it with SHA-256
+#[derive(Default)]
+pub(crate) struct Sha256Writer(sha2::Sha256);
+
+impl Sha256Writer {
+    pub(crate) fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+}
+
+impl Write for Sha256Writer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.update(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encodes bytes in Nix's base32, which reads the bytes from the end
+pub(crate) fn nix32(bytes: &[u8]) -> String {
+    let len = (bytes.len() * 8).div_ceil(5);
+    (0..len)
+        .rev()
+        .map(|index| {
+            let bit = index * 5;
+            let (byte, shift) = (bit / 8, bit % 8);
+            let low = bytes[byte] as u16 >> shift;
+            let high = bytes.get(byte + 1).map_or(0, |&next| (next as u16) << (8 - shift));
+            NIX32_ALPHABET[((low | high) & 0x1f) as usize] as char
+        })
+        .collect()
+}
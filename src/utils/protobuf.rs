@@ -0,0 +1,35 @@
+/// Appends an unsigned LEB128 varint
+pub(crate) fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Reads an unsigned LEB128 varint from the front of the bytes
+pub(crate) fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Appends a varint field
+pub(crate) fn write_uint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    write_varint(buffer, field << 3);
+    write_varint(buffer, value);
+}
+
+/// Appends a length delimited field
+pub(crate) fn write_bytes_field(buffer: &mut Vec<u8>, field: u64, value: &[u8]) {
+    write_varint(buffer, (field << 3) | 2);
+    write_varint(buffer, value.len() as u64);
+    buffer.extend_from_slice(value);
+}
@@ -0,0 +1,64 @@
+use std::sync::{Condvar, Mutex};
+
+/// Blocking counting semaphore that limits how many files are read at the same time
+pub(crate) struct ReadLimiter {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Allows a single read until dropped
+pub(crate) struct ReadPermit<'a> {
+    limiter: &'a ReadLimiter,
+}
+
+impl ReadLimiter {
+    pub(crate) fn new(max_concurrent_reads: usize) -> Self {
+        Self {
+            available: Mutex::new(max_concurrent_reads.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a read is allowed
+    pub(crate) fn acquire(&self) -> ReadPermit<'_> {
+        let mut available = self.available.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        while *available == 0 {
+            available = self.released.wait(available).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *available -= 1;
+        ReadPermit { limiter: self }
+    }
+}
+
+impl Drop for ReadPermit<'_> {
+    fn drop(&mut self) {
+        let mut available = self.limiter.available.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *available += 1;
+        self.limiter.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn blocks_until_a_permit_is_dropped() {
+        let limiter = Arc::new(ReadLimiter::new(1));
+        let permit = limiter.acquire();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            thread::spawn(move || drop(limiter.acquire()))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+
+        drop(permit);
+        waiter.join().unwrap();
+        assert_eq!(*limiter.available.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,343 @@
+use std::io::{Read, Write};
+
+use crate::components::digest::Digest;
+use crate::error::SyncError;
+use crate::utils::algorithm::{AlgorithmKind, OutputLength};
+use crate::utils::chunking::ChunkParams;
+
+/// Magic bytes every sync session starts with, the last byte is the version of the protocol
+pub const SYNC_MAGIC: [u8; 8] = *b"MRKLSYN\x01";
+
+/// Largest frame a peer accepts, larger frames end the session
+pub const MAX_FRAME_LEN: u32 = 64 * 1024 * 1024;
+
+/// Length of the tag and item count at the start of a `List` or `Listing` frame body
+pub(crate) const LIST_HEADER_LEN: usize = 5;
+
+/// Settings both peers must share for their hashes to be comparable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SyncSettings {
+    pub(crate) algorithm: AlgorithmKind,
+    pub(crate) context: Option<String>,
+    pub(crate) output_len: Option<OutputLength>,
+    pub(crate) key_fingerprint: Option<[u8; 32]>,
+    pub(crate) hash_names: bool,
+    pub(crate) chunking: Option<ChunkParams>,
+}
+
+/// A child of a directory as it is listed to the other peer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct SyncEntry {
+    pub(crate) name: String,
+    pub(crate) is_dir: bool,
+    pub(crate) hash: Digest,
+}
+
+/// Messages exchanged by the peers, each sent as a single length prefixed frame
+///
+/// Every frame is a `u32` big-endian length followed by a tag byte and the fields of the message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Frame {
+    /// Sent by both peers first, with their settings and root
+    Hello {
+        settings: SyncSettings,
+        root_hash: Digest,
+        root_is_dir: bool,
+    },
+    /// Asks for the children of the directories at the given relative paths
+    List(Vec<String>),
+    /// Answers a list request in order, none for paths that are not directories
+    ///
+    /// Only the first paths are answered when the full answer would not fit in a frame.
+    Listing(Vec<Option<Vec<SyncEntry>>>),
+    /// Ends the session with the differences as seen by the initiator
+    Done {
+        changed: Vec<String>,
+        only_local: Vec<String>,
+        only_remote: Vec<String>,
+    },
+}
+
+impl Frame {
+    /// Writes the frame and flushes the writer, refusing frames the peer would not accept before writing anything
+    pub(crate) fn write(&self, writer: &mut impl Write) -> Result<(), SyncError> {
+        let mut body = Vec::new();
+        self.encode(&mut body);
+        let len = match u32::try_from(body.len()) {
+            Ok(len) if len <= MAX_FRAME_LEN => len,
+            _ => return Err(SyncError::FrameTooLargeToSend(body.len())),
+        };
+        let mut frame = len.to_be_bytes().to_vec();
+        frame.extend_from_slice(&body);
+        writer.write_all(&frame).map_err(SyncError::Io)?;
+        writer.flush().map_err(SyncError::Io)
+    }
+
+    /// Reads the next frame, tagging listed hashes with the given algorithm
+    pub(crate) fn read(reader: &mut impl Read, algorithm: AlgorithmKind) -> Result<Self, SyncError> {
+        let mut len = [0; 4];
+        reader.read_exact(&mut len).map_err(SyncError::Io)?;
+        let len = u32::from_be_bytes(len);
+        if len > MAX_FRAME_LEN {
+            return Err(SyncError::FrameTooLarge(len));
+        }
+        let mut body = vec![0; len as usize];
+        reader.read_exact(&mut body).map_err(SyncError::Io)?;
+
+        let mut decoder = Decoder(&body);
+        let frame = Self::decode(&mut decoder, algorithm)?;
+        if !decoder.0.is_empty() {
+            return Err(SyncError::Malformed);
+        }
+        Ok(frame)
+    }
+
+    /// Returns the length a path takes in the body of a `List` frame
+    pub(crate) fn list_item_len(path: &str) -> usize {
+        4 + path.len()
+    }
+
+    /// Returns the length a listing takes in the body of a `Listing` frame
+    pub(crate) fn listing_len(listing: &Option<Vec<SyncEntry>>) -> usize {
+        let entries = listing.iter().flatten();
+        let entries_len: usize = entries.map(|entry| 4 + entry.name.len() + 1 + 4 + entry.hash.as_bytes().len()).sum();
+        1 + listing.as_ref().map_or(0, |_| 4 + entries_len)
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        match self {
+            Frame::Hello {
+                settings,
+                root_hash,
+                root_is_dir,
+            } => {
+                output.push(0);
+                output.extend_from_slice(&SYNC_MAGIC);
+                put_string(output, settings.algorithm.name());
+                put_option(output, settings.context.as_deref(), put_string);
+                output.push(settings.output_len.map_or(0, |output_len| output_len.get() as u8));
+                put_option(output, settings.key_fingerprint.as_ref(), |output, key_fingerprint| {
+                    output.extend_from_slice(key_fingerprint)
+                });
+                output.push(settings.hash_names as u8);
+                put_option(output, settings.chunking.as_ref(), |output, chunking| {
+                    for size in [chunking.min(), chunking.avg(), chunking.max()] {
+                        output.extend_from_slice(&size.to_be_bytes());
+                    }
+                });
+                put_bytes(output, root_hash.as_bytes());
+                output.push(*root_is_dir as u8);
+            }
+            Frame::List(paths) => {
+                output.push(1);
+                put_strings(output, paths);
+            }
+            Frame::Listing(listings) => {
+                output.push(2);
+                output.extend_from_slice(&(listings.len() as u32).to_be_bytes());
+                for listing in listings {
+                    put_option(output, listing.as_ref(), |output, entries| {
+                        output.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+                        for entry in entries {
+                            put_string(output, &entry.name);
+                            output.push(entry.is_dir as u8);
+                            put_bytes(output, entry.hash.as_bytes());
+                        }
+                    });
+                }
+            }
+            Frame::Done {
+                changed,
+                only_local,
+                only_remote,
+            } => {
+                output.push(3);
+                put_strings(output, changed);
+                put_strings(output, only_local);
+                put_strings(output, only_remote);
+            }
+        }
+    }
+
+    fn decode(decoder: &mut Decoder, listed_algorithm: AlgorithmKind) -> Result<Self, SyncError> {
+        match decoder.u8()? {
+            0 => {
+                if decoder.take(SYNC_MAGIC.len())? != SYNC_MAGIC {
+                    return Err(SyncError::InvalidMagic);
+                }
+                let name = decoder.string()?;
+                let algorithm = match AlgorithmKind::from_name(&name) {
+                    Some(algorithm) => algorithm,
+                    None => return Err(SyncError::UnsupportedAlgorithm(name)),
+                };
+                let context = decoder.option(Decoder::string)?;
+                let output_len = match decoder.u8()? {
+                    0 => None,
+                    len => Some(OutputLength::new(len as usize).map_err(|_| SyncError::Malformed)?),
+                };
+                let key_fingerprint = decoder.option(Decoder::array)?;
+                let hash_names = decoder.u8()? != 0;
+                let chunking = decoder.option(|decoder| {
+                    let min = u32::from_be_bytes(decoder.array()?);
+                    let avg = u32::from_be_bytes(decoder.array()?);
+                    let max = u32::from_be_bytes(decoder.array()?);
+                    ChunkParams::new(min, avg, max).map_err(|_| SyncError::Malformed)
+                })?;
+                let root_hash = decoder.digest(algorithm)?;
+                let root_is_dir = decoder.u8()? != 0;
+                Ok(Frame::Hello {
+                    settings: SyncSettings {
+                        algorithm,
+                        context,
+                        output_len,
+                        key_fingerprint,
+                        hash_names,
+                        chunking,
+                    },
+                    root_hash,
+                    root_is_dir,
+                })
+            }
+            1 => Ok(Frame::List(decoder.strings()?)),
+            2 => {
+                let len = decoder.u32()?;
+                let mut listings = Vec::new();
+                for _ in 0..len {
+                    listings.push(decoder.option(|decoder| {
+                        let len = decoder.u32()?;
+                        let mut entries = Vec::new();
+                        for _ in 0..len {
+                            let name = decoder.string()?;
+                            let is_dir = decoder.u8()? != 0;
+                            let hash = decoder.digest(listed_algorithm)?;
+                            entries.push(SyncEntry { name, is_dir, hash });
+                        }
+                        Ok(entries)
+                    })?);
+                }
+                Ok(Frame::Listing(listings))
+            }
+            3 => Ok(Frame::Done {
+                changed: decoder.strings()?,
+                only_local: decoder.strings()?,
+                only_remote: decoder.strings()?,
+            }),
+            _ => Err(SyncError::Malformed),
+        }
+    }
+}
+
+fn put_bytes(output: &mut Vec<u8>, bytes: &[u8]) {
+    output.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    output.extend_from_slice(bytes);
+}
+
+fn put_string(output: &mut Vec<u8>, string: &str) {
+    put_bytes(output, string.as_bytes());
+}
+
+fn put_strings(output: &mut Vec<u8>, strings: &[String]) {
+    output.extend_from_slice(&(strings.len() as u32).to_be_bytes());
+    for string in strings {
+        put_string(output, string);
+    }
+}
+
+fn put_option<T>(output: &mut Vec<u8>, value: Option<T>, put: impl FnOnce(&mut Vec<u8>, T)) {
+    match value {
+        Some(value) => {
+            output.push(1);
+            put(output, value);
+        }
+        None => output.push(0),
+    }
+}
+
+/// Reads the fields of a frame front to back
+struct Decoder<'a>(&'a [u8]);
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SyncError> {
+        if self.0.len() < len {
+            return Err(SyncError::Malformed);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, SyncError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SyncError> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SyncError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], SyncError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, SyncError> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| SyncError::Malformed)
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, SyncError> {
+        let len = self.u32()?;
+        (0..len).map(|_| self.string()).collect()
+    }
+
+    fn digest(&mut self, algorithm: AlgorithmKind) -> Result<Digest, SyncError> {
+        Digest::new(algorithm, self.bytes()?).map_err(|_| SyncError::Malformed)
+    }
+
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T, SyncError>) -> Result<Option<T>, SyncError> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => read(self).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::digest::Digest;
+    use crate::error::SyncError;
+    use crate::utils::algorithm::AlgorithmKind;
+    use crate::utils::sync_wire::{Frame, SyncEntry, LIST_HEADER_LEN, MAX_FRAME_LEN};
+
+    #[test]
+    fn item_lengths_match_the_encoding() {
+        let hash = Digest::new(AlgorithmKind::Blake3, &[7; 32]).unwrap();
+        let entry = |name: &str| SyncEntry {
+            name: name.to_string(),
+            is_dir: false,
+            hash,
+        };
+        let listings = vec![None, Some(Vec::new()), Some(vec![entry("a"), entry("long name")])];
+        let paths = vec![String::new(), "a/b".to_string()];
+
+        let mut body = Vec::new();
+        Frame::Listing(listings.clone()).encode(&mut body);
+        assert_eq!(body.len(), LIST_HEADER_LEN + listings.iter().map(Frame::listing_len).sum::<usize>());
+
+        let mut body = Vec::new();
+        Frame::List(paths.clone()).encode(&mut body);
+        assert_eq!(body.len(), LIST_HEADER_LEN + paths.iter().map(|path| Frame::list_item_len(path)).sum::<usize>());
+    }
+
+    #[test]
+    fn oversized_frames_are_not_written() {
+        let mut written = Vec::new();
+        let frame = Frame::List(vec!["x".repeat(MAX_FRAME_LEN as usize)]);
+        assert!(matches!(frame.write(&mut written), Err(SyncError::FrameTooLargeToSend(_))));
+        assert!(written.is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::{self, Write};
+
+/// Size of a tar block, headers and contents are padded to it
+pub(crate) const BLOCK_SIZE: usize = 512;
+
+/// Largest size the octal size field of a ustar header can hold
+const MAX_USTAR_SIZE: u64 = 0o77777777777;
+
+/// Kind of a tar entry
+#[derive(Clone, Copy)]
+pub(crate) enum EntryKind {
+    File { executable: bool },
+    Directory,
+}
+
+/// Writes a reproducible tar stream, every entry owned by root with a zero modification time
+///
+/// Entries that do not fit a ustar header are preceded by a PAX extended header.
+pub(crate) struct TarWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes an entry, its contents must be empty for directories
+    pub(crate) fn entry(&mut self, path: &[u8], kind: EntryKind, contents: &[u8]) -> io::Result<()> {
+        let (path, mode, typeflag) = match kind {
+            EntryKind::File { executable: true } => (path.to_vec(), 0o755, b'0'),
+            EntryKind::File { executable: false } => (path.to_vec(), 0o644, b'0'),
+            EntryKind::Directory => ([path, b"/"].concat(), 0o755, b'5'),
+        };
+        let size = contents.len() as u64;
+
+        let split = Self::split_path(&path);
+        let mut records = Vec::new();
+        if split.is_none() {
+            Self::pax_record(&mut records, b"path", &path);
+        }
+        if size > MAX_USTAR_SIZE {
+            Self::pax_record(&mut records, b"size", size.to_string().as_bytes());
+        }
+        if !records.is_empty() {
+            let header = Self::header(b"", b"././@PaxHeader", 0o644, records.len() as u64, b'x');
+            self.writer.write_all(&header)?;
+            self.contents(&records)?;
+        }
+
+        let (prefix, name) = split.unwrap_or((&[], &path[..path.len().min(100)]));
+        let header = Self::header(prefix, name, mode, if size > MAX_USTAR_SIZE { 0 } else { size }, typeflag);
+        self.writer.write_all(&header)?;
+        self.contents(contents)
+    }
+
+    /// Writes the two zero blocks that end the archive and returns the writer
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.writer.write_all(&[0; BLOCK_SIZE * 2])?;
+        Ok(self.writer)
+    }
+
+    fn contents(&mut self, contents: &[u8]) -> io::Result<()> {
+        self.writer.write_all(contents)?;
+        let padding = (BLOCK_SIZE - contents.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        self.writer.write_all(&[0; BLOCK_SIZE][..padding])
+    }
+
+    /// Splits a path into the prefix and name fields of a ustar header, none if it does not fit
+    fn split_path(path: &[u8]) -> Option<(&[u8], &[u8])> {
+        if path.len() <= 100 {
+            return Some((&[], path));
+        }
+        // Directories end with a slash that must stay in the name
+        let searched = &path[..path.len() - 1];
+        searched
+            .iter()
+            .enumerate()
+            .filter(|(index, &byte)| byte == b'/' && *index <= 155 && path.len() - index - 1 <= 100)
+            .map(|(index, _)| (&path[..index], &path[index + 1..]))
+            .next()
+    }
+
+    /// Appends a PAX record, whose length prefix counts its own digits
+    fn pax_record(records: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+        let rest = key.len() + value.len() + 3;
+        let mut len = rest + rest.to_string().len();
+        if len.to_string().len() + rest != len {
+            len = rest + len.to_string().len();
+        }
+        records.extend_from_slice(format!("{} ", len).as_bytes());
+        records.extend_from_slice(key);
+        records.push(b'=');
+        records.extend_from_slice(value);
+        records.push(b'\n');
+    }
+
+    fn header(prefix: &[u8], name: &[u8], mode: u32, size: u64, typeflag: u8) -> [u8; BLOCK_SIZE] {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name);
+        Self::octal(&mut header[100..108], mode as u64);
+        Self::octal(&mut header[108..116], 0);
+        Self::octal(&mut header[116..124], 0);
+        Self::octal(&mut header[124..136], size);
+        Self::octal(&mut header[136..148], 0);
+        header[156] = typeflag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        Self::octal(&mut header[329..337], 0);
+        Self::octal(&mut header[337..345], 0);
+        header[345..345 + prefix.len()].copy_from_slice(prefix);
+
+        // The checksum is computed with its own field filled with spaces
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u32 = header.iter().map(|&byte| byte as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+        header
+    }
+
+    /// Writes a zero padded octal number followed by a NUL into a field
+    fn octal(field: &mut [u8], value: u64) {
+        let digits = field.len() - 1;
+        field[..digits].copy_from_slice(format!("{:0width$o}", value, width = digits).as_bytes());
+        field[digits] = 0;
+    }
+}
+
+/// Passes everything written through to a writer, hashing it with SHA-256 and counting its bytes
+pub(crate) struct Sha256Tee<W: Write> {
+    writer: W,
+    hasher: sha2::Sha256,
+    len: u64,
+}
+
+impl<W: Write> Sha256Tee<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self {
+            writer,
+            hasher: sha2::Sha256::default(),
+            len: 0,
+        }
+    }
+
+    /// Returns the inner writer, the SHA-256 hash and the number of bytes written
+    pub(crate) fn finish(self) -> (W, [u8; 32], u64) {
+        (self.writer, sha2::Digest::finalize(self.hasher).into(), self.len)
+    }
+}
+
+impl<W: Write> Write for Sha256Tee<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(bytes)?;
+        sha2::Digest::update(&mut self.hasher, &bytes[..written]);
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,173 @@
+use std::iter::Peekable;
+
+use crate::components::cid::{Cid, CidCodec};
+use crate::components::unixfs_entry::UnixFsEntry;
+use crate::utils::protobuf;
+
+/// Size of the fixed-size chunks files are split into, kubo's `size-262144` chunker
+pub const UNIXFS_CHUNK_SIZE: usize = 262_144;
+
+/// Maximum number of links of a file node in the balanced layout
+pub const UNIXFS_MAX_LINKS: usize = 174;
+
+/// UnixFS data type of directories
+const DIRECTORY: u64 = 1;
+
+/// UnixFS data type of files
+const FILE: u64 = 2;
+
+/// A link to a child block along with the number of file bytes below it
+struct FileLink {
+    entry: UnixFsEntry,
+    file_size: u64,
+}
+
+/// Builds the UnixFS DAG of a file with raw leaves and the balanced layout
+pub(crate) fn file_entry(bytes: &[u8]) -> UnixFsEntry {
+    let mut leaves = bytes
+        .chunks(UNIXFS_CHUNK_SIZE)
+        .map(|chunk| FileLink {
+            entry: UnixFsEntry {
+                cid: Cid::of_block(CidCodec::Raw, chunk),
+                cumulative_size: chunk.len() as u64,
+            },
+            file_size: chunk.len() as u64,
+        })
+        .peekable();
+
+    // An empty file is a single empty raw leaf
+    let mut root = match leaves.next() {
+        Some(leaf) => leaf,
+        None => return UnixFsEntry {
+            cid: Cid::of_block(CidCodec::Raw, &[]),
+            cumulative_size: 0,
+        },
+    };
+
+    // Every new root keeps the previous one as its first child and is filled up to one level deeper
+    let mut depth = 1;
+    while leaves.peek().is_some() {
+        root = fill_file_node(&mut leaves, vec![root], depth);
+        depth += 1;
+    }
+
+    root.entry
+}
+
+/// Fills a file node with leaves or with full subtrees of the given depth
+fn fill_file_node(leaves: &mut Peekable<impl Iterator<Item = FileLink>>, mut links: Vec<FileLink>, depth: usize) -> FileLink {
+    while links.len() < UNIXFS_MAX_LINKS && leaves.peek().is_some() {
+        let child = match depth {
+            1 => leaves.next().unwrap(),
+            _ => fill_file_node(leaves, Vec::new(), depth - 1),
+        };
+        links.push(child);
+    }
+
+    let file_size = links.iter().map(|link| link.file_size).sum();
+
+    let mut data = Vec::new();
+    protobuf::write_uint_field(&mut data, 1, FILE);
+    protobuf::write_uint_field(&mut data, 3, file_size);
+    for link in &links {
+        protobuf::write_uint_field(&mut data, 4, link.file_size);
+    }
+
+    let entry = dag_pb_entry(links.iter().map(|link| (&b""[..], &link.entry)), &data);
+
+    FileLink { entry, file_size }
+}
+
+/// Builds the UnixFS node of a directory from the names and entries of its children
+pub(crate) fn directory_entry<'a>(children: impl IntoIterator<Item = (&'a [u8], &'a UnixFsEntry)>) -> UnixFsEntry {
+    let mut children: Vec<_> = children.into_iter().collect();
+    children.sort_by_key(|(name, _)| *name);
+
+    let mut data = Vec::new();
+    protobuf::write_uint_field(&mut data, 1, DIRECTORY);
+
+    dag_pb_entry(children, &data)
+}
+
+/// Encodes a dag-pb node, its links always come before its data
+fn dag_pb_entry<'a>(links: impl IntoIterator<Item = (&'a [u8], &'a UnixFsEntry)>, data: &[u8]) -> UnixFsEntry {
+    let mut block = Vec::new();
+    let mut cumulative_size = 0;
+
+    for (name, entry) in links {
+        let mut link = Vec::new();
+        protobuf::write_bytes_field(&mut link, 1, &entry.cid.to_bytes());
+        protobuf::write_bytes_field(&mut link, 2, name);
+        protobuf::write_uint_field(&mut link, 3, entry.cumulative_size);
+
+        protobuf::write_bytes_field(&mut block, 2, &link);
+        cumulative_size += entry.cumulative_size;
+    }
+    protobuf::write_bytes_field(&mut block, 1, data);
+
+    UnixFsEntry {
+        cid: Cid::of_block(CidCodec::DagPb, &block),
+        cumulative_size: cumulative_size + block.len() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixture::{fixture, path};
+    use crate::MerkleTree;
+
+    // Published CIDs of `ipfs add --cid-version=1 --raw-leaves` for an empty file, an empty directory and `hello world`
+    const EMPTY_FILE: &str = "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+    const EMPTY_DIRECTORY: &str = "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354";
+    const HELLO_WORLD: &str = "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e";
+
+    // CIDs of multi-chunk files and nested directories whose bytes are `i % 251` at index `i`, for kubo's default
+    // importer settings, 256 KiB chunks and 174 links per node. They come from an independent encoder of the
+    // UnixFS spec and are still to be confirmed against kubo, by writing the fixtures below and running
+    //
+    //     python3 -c 'import sys; sys.stdout.buffer.write(bytes(i % 251 for i in range(600000)))' > big
+    //     ipfs add --only-hash --cid-version=1 --raw-leaves big
+    //     ipfs add -r --hidden --only-hash --cid-version=1 --raw-leaves <directory of identifies_nested_directories>
+    const FILE_600000: &str = "bafybeicp64het67shnhxiyl3sg5mylxqop6pnqsqpfecb6pmni2ghoxzom";
+    const FILE_175_CHUNKS: &str = "bafybeib4y7ghw2rq7bracc4xwtxrbzo7cfvagdpte2tmrkgwl6dyard3cm";
+    const NESTED: &str = "bafybeie3xprb5eljrbgw6mkiavcowphbhieciwlo7u7knj56ymbg5i6tha";
+
+    fn contents(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn matches_published_cids() {
+        assert_eq!(file_entry(b"").cid.to_string(), EMPTY_FILE);
+        assert_eq!(file_entry(b"hello world").cid.to_string(), HELLO_WORLD);
+        assert_eq!(directory_entry([]).cid.to_string(), EMPTY_DIRECTORY);
+        assert_eq!(EMPTY_DIRECTORY.parse::<Cid>().unwrap(), directory_entry([]).cid);
+    }
+
+    #[test]
+    fn lays_out_files_over_a_chunk() {
+        let entry = file_entry(&contents(600_000));
+        assert_eq!(entry.cid.to_string(), FILE_600000);
+        assert_eq!(entry.cumulative_size, 600_158);
+
+        // One more chunk than fits in a single node needs a second level
+        let entry = file_entry(&contents(UNIXFS_CHUNK_SIZE * UNIXFS_MAX_LINKS + 1));
+        assert_eq!(entry.cid.to_string(), FILE_175_CHUNKS);
+        assert_eq!(entry.cumulative_size, 45_621_926);
+    }
+
+    #[test]
+    fn identifies_nested_directories() {
+        let big = contents(600_000);
+        let dir = fixture(&[("a", b"one"), ("b/c", b"two"), ("b/d/e", b""), ("b/f/", b""), ("big", &big)]);
+        let tree = MerkleTree::builder(path(&dir)).unixfs(true).build().unwrap();
+        assert_eq!(tree.root_cid().unwrap().to_string(), NESTED);
+        assert_eq!(tree.root.item.unixfs.unwrap().cumulative_size, 600_500);
+
+        let empty = tree.iter().find(|item| item.path.relative.ends_with("b/f")).unwrap();
+        assert_eq!(empty.unixfs.unwrap().cid.to_string(), EMPTY_DIRECTORY);
+        let empty = tree.iter().find(|item| item.path.relative.ends_with("b/d/e")).unwrap();
+        assert_eq!(empty.unixfs.unwrap().cid.to_string(), EMPTY_FILE);
+    }
+}